@@ -0,0 +1,88 @@
+// benches/query.rs
+
+//! Compares the query interpreter (`query_native`) with compiled queries
+//! (`query_compiler::CompiledQuery`), against the vault and against the metadata index.
+//!
+//! Run with `cargo bench --bench query`.
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use notemancy_core::index::VaultIndex;
+use notemancy_core::query_compiler::CompiledQuery;
+use notemancy_core::query_native::{query_notes_indexed, query_notes_native};
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use tempfile::tempdir;
+
+const NOTE_COUNT: usize = 2000;
+
+const QUERIES: [(&str, &str); 4] = [
+    ("selective_tag", "tag = rare and priority >= 2"),
+    ("common_tag", "tag = common and status != done"),
+    ("unused_tag", "tag = unknown and status = open"),
+    (
+        "field_only",
+        "status = open and priority > 3 order by priority desc limit 20",
+    ),
+];
+
+/// Writes a vault with frontmatter of varying tags, statuses and priorities.
+fn write_vault(vault_dir: &Path) {
+    for i in 0..NOTE_COUNT {
+        let mut tags = vec!["common".to_string(), format!("topic-{}", i % 50)];
+        if i % 100 == 0 {
+            tags.push("rare".to_string());
+        }
+        let status = ["open", "done", "blocked"][i % 3];
+        let dir = vault_dir.join(format!("folder-{}", i % 10));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(format!("note-{}.md", i)),
+            format!(
+                "---\ntitle: Note {}\ntags: [{}]\nstatus: {}\npriority: {}\ncreated_on: 2025-01-{:02}\n---\n\nBody of note {}.\n",
+                i,
+                tags.join(", "),
+                status,
+                i % 5,
+                i % 28 + 1,
+                i
+            ),
+        )
+        .unwrap();
+    }
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let temp_dir = tempdir().unwrap();
+    let vault_dir = temp_dir.path();
+    write_vault(vault_dir);
+    let index = VaultIndex::open(vault_dir).unwrap();
+
+    let mut vault_group = c.benchmark_group("query_vault");
+    vault_group.sample_size(10);
+    for (name, query) in QUERIES {
+        let compiled = CompiledQuery::compile(query).unwrap();
+        vault_group.bench_with_input(BenchmarkId::new("native", name), query, |b, query| {
+            b.iter(|| query_notes_native(vault_dir, black_box(query)).unwrap())
+        });
+        vault_group.bench_with_input(BenchmarkId::new("compiled", name), &compiled, |b, q| {
+            b.iter(|| q.run(vault_dir).unwrap())
+        });
+    }
+    vault_group.finish();
+
+    let mut index_group = c.benchmark_group("query_indexed");
+    for (name, query) in QUERIES {
+        let compiled = CompiledQuery::compile(query).unwrap();
+        index_group.bench_with_input(BenchmarkId::new("native", name), query, |b, query| {
+            b.iter(|| query_notes_indexed(&index, black_box(query)).unwrap())
+        });
+        index_group.bench_with_input(BenchmarkId::new("compiled", name), &compiled, |b, q| {
+            b.iter(|| q.run_indexed(&index).unwrap())
+        });
+    }
+    index_group.finish();
+}
+
+criterion_group!(benches, bench_queries);
+criterion_main!(benches);
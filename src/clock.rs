@@ -0,0 +1,51 @@
+// src/clock.rs
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// A source of the current time, so that code depending on "now" (like relative dates in
+/// queries) can be tested deterministically.
+pub trait Clock: Send + Sync {
+    /// Returns the current local date and time.
+    fn now(&self) -> NaiveDateTime;
+
+    /// Returns the current local date.
+    fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+}
+
+/// The clock of the system, in local time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// A clock stopped at a given time.
+///
+/// # Examples
+/// ```
+/// use chrono::NaiveDate;
+/// use notemancy_core::clock::{Clock, FixedClock};
+///
+/// let clock = FixedClock::on(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+/// assert_eq!(clock.today().to_string(), "2025-03-14");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedClock(pub NaiveDateTime);
+
+impl FixedClock {
+    /// Creates a clock stopped at midnight of the given date.
+    pub fn on(date: NaiveDate) -> Self {
+        FixedClock(date.and_time(NaiveTime::MIN))
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> NaiveDateTime {
+        self.0
+    }
+}
@@ -0,0 +1,564 @@
+// src/doctor.rs
+
+use crate::kanban::crud::{generate_next_id, list_boards, read_board, save_board};
+use crate::links::check_vault_links;
+use crate::notes::crud::sync_file_name;
+use crate::notes::frontmatter::{DATE_FORMAT, Frontmatter, TIMESTAMP_FORMAT};
+use crate::notes::utils::{note_file_stem, sanitize_title};
+use crate::query_native::{LoadErrorKind, load_notes_tolerant};
+use crate::workspaces::crud::remove_from_workspace;
+use crate::workspaces::utils::{get_workspace_files, list_workspaces};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The kinds of problems `vault_doctor` looks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// The note can't be read or isn't valid UTF-8
+    UnreadableNote,
+    /// The frontmatter isn't valid YAML, isn't a mapping or has fields of the wrong type
+    MalformedFrontmatter,
+    /// Several notes have titles that map to the same file name
+    DuplicateTitle,
+    /// The file name isn't the sanitized title of the note
+    FileNameMismatch,
+    /// The note has no `created_on` or no `modified_at`
+    MissingTimestamps,
+    /// A link points at a note or file that doesn't exist
+    BrokenLink,
+    /// A workspace lists a note that doesn't exist
+    StaleWorkspaceEntry,
+    /// Several tasks of a kanban board share an ID
+    DuplicateTaskId,
+}
+
+/// An automatic fix for a finding.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Fix {
+    /// Renames the note file to its sanitized title and rewrites the links to it
+    /// (see `notes::crud::sync_file_name`).
+    RenameFile { path: String, new_path: String },
+    /// Adds the missing timestamps, taken from the modification time of the file.
+    AddTimestamps {
+        path: String,
+        created_on: Option<String>,
+        modified_at: Option<String>,
+    },
+    /// Removes the entry from the workspace.
+    RemoveWorkspaceEntry { workspace: String, entry: String },
+    /// Gives a new ID to the task at `position` (counting from 0) in `column`, which
+    /// reuses the ID `task_id`.
+    RenumberTask {
+        kanban_directory: PathBuf,
+        board: String,
+        task_id: String,
+        column: String,
+        position: usize,
+    },
+}
+
+/// A problem found in the vault.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// The file with the problem: a note path relative to the vault, a workspace name
+    /// or a kanban board name
+    pub path: String,
+    /// The 1-based line of the problem, when known
+    pub line: Option<usize>,
+    pub message: String,
+    /// How to fix the problem automatically, if it can be
+    pub fix: Option<Fix>,
+}
+
+/// The findings of `vault_doctor`, sorted by kind, path and line.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+}
+
+impl DoctorReport {
+    /// Returns true if no problem was found.
+    pub fn is_healthy(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Returns the fixes of the findings that have one.
+    pub fn fixes(&self) -> Vec<&Fix> {
+        self.findings
+            .iter()
+            .filter_map(|f| f.fix.as_ref())
+            .collect()
+    }
+}
+
+/// Whether `apply_fixes` changes the vault.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixMode {
+    /// Only report what would be done
+    DryRun,
+    /// Apply the fixes
+    Commit,
+}
+
+/// What happened to a fix in `apply_fixes`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FixOutcome {
+    pub fix: Fix,
+    /// True if the fix was applied (always false in dry-run mode)
+    pub applied: bool,
+    /// Why the fix couldn't be applied
+    pub error: Option<String>,
+}
+
+/// Scans the vault for consistency problems.
+///
+/// The checks are: notes that can't be read or whose frontmatter is malformed, titles
+/// mapping to the same file name (which `check_unique_title` only prevents at creation
+/// time), file names that no longer match `sanitize_title(title)`, missing `created_on`
+/// or `modified_at`, broken links, workspace entries pointing at deleted notes and, if a
+/// kanban directory is given, tasks with duplicate IDs.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `kanban_directory` - The directory of the kanban boards, if they should be checked
+///
+/// # Returns
+/// * `Result<DoctorReport>` - The problems found, with their automatic fix when there is one
+///
+/// # Errors
+/// * Returns an error if the vault directory does not exist or can't be walked
+/// * Returns an error if a workspace or kanban board can't be read
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::doctor::{FixMode, apply_fixes, vault_doctor};
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let report = vault_doctor(vault_dir, None)?;
+/// for finding in &report.findings {
+///     println!("{}: {}", finding.path, finding.message);
+/// }
+/// apply_fixes(vault_dir, &report, FixMode::Commit)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn vault_doctor(
+    vault_directory: &Path,
+    kanban_directory: Option<&Path>,
+) -> Result<DoctorReport> {
+    let mut findings = Vec::new();
+    let loaded = load_notes_tolerant(vault_directory)?;
+
+    for diagnostic in &loaded.diagnostics {
+        let kind = match diagnostic.kind {
+            LoadErrorKind::Unreadable | LoadErrorKind::InvalidEncoding => {
+                FindingKind::UnreadableNote
+            }
+            LoadErrorKind::MalformedYaml | LoadErrorKind::InvalidFrontmatter => {
+                FindingKind::MalformedFrontmatter
+            }
+        };
+        findings.push(Finding {
+            kind,
+            path: diagnostic.path.clone(),
+            line: diagnostic.line,
+            message: format!("{}: {}", diagnostic.kind, diagnostic.message),
+            fix: None,
+        });
+    }
+
+    // Titles that map to the same file name, as compared by `check_unique_title`.
+    let mut by_file_name: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for note in &loaded.notes {
+        by_file_name
+            .entry(sanitize_title(&note.title))
+            .or_default()
+            .push(&note.path);
+    }
+    let duplicates: HashSet<&str> = by_file_name
+        .values()
+        .filter(|paths| paths.len() > 1)
+        .flatten()
+        .copied()
+        .collect();
+
+    for note in &loaded.notes {
+        if duplicates.contains(note.path.as_str()) {
+            let others: Vec<&str> = by_file_name[&sanitize_title(&note.title)]
+                .iter()
+                .copied()
+                .filter(|path| *path != note.path)
+                .collect();
+            findings.push(Finding {
+                kind: FindingKind::DuplicateTitle,
+                path: note.path.clone(),
+                line: None,
+                message: format!(
+                    "The title '{}' is also used by {}",
+                    note.title,
+                    others.join(", ")
+                ),
+                fix: None,
+            });
+        }
+
+        if let Some(title) = &note.frontmatter.title {
+            let expected = sanitize_title(title);
+            if note_file_stem(&note.path) != expected {
+                let new_path = match note.path.rsplit_once('/') {
+                    Some((directory, file)) => {
+                        format!("{}/{}{}", directory, expected, extension_of(file))
+                    }
+                    None => format!("{}{}", expected, extension_of(&note.path)),
+                };
+                findings.push(Finding {
+                    kind: FindingKind::FileNameMismatch,
+                    path: note.path.clone(),
+                    line: None,
+                    message: format!(
+                        "The file name doesn't match the title '{}', expected '{}'",
+                        title, new_path
+                    ),
+                    // Renaming would collide with the other notes of the same title.
+                    fix: (!duplicates.contains(note.path.as_str())).then(|| Fix::RenameFile {
+                        path: note.path.clone(),
+                        new_path,
+                    }),
+                });
+            }
+        }
+
+        let missing_created = note.frontmatter.created_on.is_none();
+        let missing_modified = note.frontmatter.modified_at.is_none();
+        if missing_created || missing_modified {
+            let missing: Vec<&str> = [
+                (missing_created, "created_on"),
+                (missing_modified, "modified_at"),
+            ]
+            .into_iter()
+            .filter_map(|(missing, field)| missing.then_some(field))
+            .collect();
+            let modified = file_modified_time(&vault_directory.join(&note.path));
+            findings.push(Finding {
+                kind: FindingKind::MissingTimestamps,
+                path: note.path.clone(),
+                line: None,
+                message: format!("Missing {}", missing.join(" and ")),
+                fix: modified.map(|modified| Fix::AddTimestamps {
+                    path: note.path.clone(),
+                    created_on: missing_created.then(|| modified.format(DATE_FORMAT).to_string()),
+                    modified_at: missing_modified
+                        .then(|| modified.format(TIMESTAMP_FORMAT).to_string()),
+                }),
+            });
+        }
+    }
+
+    findings.extend(check_links(vault_directory)?);
+    findings.extend(check_workspaces(vault_directory)?);
+    if let Some(kanban_directory) = kanban_directory {
+        findings.extend(check_kanban_boards(kanban_directory)?);
+    }
+
+    findings.sort_by(|a, b| (a.kind, &a.path, a.line).cmp(&(b.kind, &b.path, b.line)));
+    Ok(DoctorReport { findings })
+}
+
+/// Applies the fixes of a doctor report.
+///
+/// In dry-run mode nothing is changed and every fix is returned as not applied. In
+/// commit mode the fixes are applied one by one; a fix that fails doesn't stop the
+/// others, and its error is recorded in its outcome. File renames are applied last, so
+/// that the other fixes still find the notes at the paths they were reported with.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `report` - The report from `vault_doctor`
+/// * `mode` - Whether to only preview the fixes or apply them
+///
+/// # Returns
+/// * `Result<Vec<FixOutcome>>` - The outcome of each fix, in the order they were applied
+pub fn apply_fixes(
+    vault_directory: &Path,
+    report: &DoctorReport,
+    mode: FixMode,
+) -> Result<Vec<FixOutcome>> {
+    let mut fixes = report.fixes();
+    fixes.sort_by_key(|fix| matches!(fix, Fix::RenameFile { .. }));
+
+    Ok(fixes
+        .into_iter()
+        .map(|fix| {
+            let result = match mode {
+                FixMode::DryRun => None,
+                FixMode::Commit => Some(apply_fix(vault_directory, fix)),
+            };
+            FixOutcome {
+                fix: fix.clone(),
+                applied: matches!(result, Some(Ok(()))),
+                error: result.and_then(|r| r.err()).map(|e| format!("{:#}", e)),
+            }
+        })
+        .collect())
+}
+
+/// Helper function to apply a single fix.
+fn apply_fix(vault_directory: &Path, fix: &Fix) -> Result<()> {
+    match fix {
+        Fix::RenameFile { path, .. } => sync_file_name(path, vault_directory).map(|_| ()),
+        Fix::AddTimestamps {
+            path,
+            created_on,
+            modified_at,
+        } => {
+            let file_path = vault_directory.join(path);
+            let content = fs::read_to_string(&file_path)
+                .with_context(|| format!("Failed to read note file: {}", path))?;
+            let (mut frontmatter, body) = Frontmatter::from_content(&content)?
+                .unwrap_or_else(|| (Frontmatter::default(), content.as_str()));
+            if frontmatter.created_on.is_none() {
+                frontmatter.created_on = created_on.clone();
+            }
+            if frontmatter.modified_at.is_none() {
+                frontmatter.modified_at = modified_at.clone();
+            }
+            fs::write(&file_path, frontmatter.render(body)?)
+                .with_context(|| format!("Failed to write note file: {}", path))
+        }
+        Fix::RemoveWorkspaceEntry { workspace, entry } => {
+            remove_from_workspace(vault_directory, workspace, entry).map(|_| ())
+        }
+        Fix::RenumberTask {
+            kanban_directory,
+            board,
+            task_id,
+            column,
+            position,
+        } => {
+            let mut kanban_board = read_board(board, kanban_directory)?;
+            let new_id = generate_next_id(&kanban_board)?;
+            // Renumbering a task doesn't move any other, so positions stay valid
+            // across the fixes of one board.
+            let task = kanban_board
+                .tasks
+                .get_mut(column)
+                .and_then(|tasks| tasks.get_mut(*position))
+                .filter(|task| &task.id == task_id)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Task '{}' not found at position {} of column '{}' in board '{}'",
+                        task_id,
+                        position,
+                        column,
+                        board
+                    )
+                })?;
+            task.id = new_id;
+            save_board(&kanban_board, kanban_directory).map(|_| ())
+        }
+    }
+}
+
+/// Helper function to return the extension of a file name, with its dot.
+fn extension_of(file_name: &str) -> &str {
+    file_name.rfind('.').map_or("", |dot| &file_name[dot..])
+}
+
+/// Helper function to read the modification time of a file in local time.
+fn file_modified_time(path: &Path) -> Option<chrono::NaiveDateTime> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+/// Helper function to report links that resolve neither to a note nor to a file.
+fn check_links(vault_directory: &Path) -> Result<Vec<Finding>> {
+    Ok(check_vault_links(vault_directory)?
+        .broken_links
+        .into_iter()
+        .map(|broken| {
+            let mut message = format!("The link to '{}' doesn't resolve", broken.target);
+            if let Some(suggestion) = broken.suggestions.first() {
+                message.push_str(&format!(" (did you mean '{}'?)", suggestion.title));
+            }
+            Finding {
+                kind: FindingKind::BrokenLink,
+                path: broken.path,
+                line: Some(broken.line),
+                message,
+                fix: None,
+            }
+        })
+        .collect())
+}
+
+/// Helper function to report workspace entries pointing at missing notes.
+fn check_workspaces(vault_directory: &Path) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for workspace in list_workspaces(vault_directory)? {
+        for entry in get_workspace_files(vault_directory, &workspace)? {
+            if vault_directory.join(&entry).is_file() {
+                continue;
+            }
+            findings.push(Finding {
+                kind: FindingKind::StaleWorkspaceEntry,
+                path: workspace.clone(),
+                line: None,
+                message: format!("The workspace lists '{}', which doesn't exist", entry),
+                fix: Some(Fix::RemoveWorkspaceEntry {
+                    workspace: workspace.clone(),
+                    entry,
+                }),
+            });
+        }
+    }
+    Ok(findings)
+}
+
+/// Helper function to report kanban tasks sharing an ID.
+fn check_kanban_boards(kanban_directory: &Path) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for board_name in list_boards(kanban_directory)? {
+        let board = read_board(&board_name, kanban_directory)?;
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (column, tasks) in board
+            .columns
+            .iter()
+            .filter_map(|column| board.tasks.get(column).map(|tasks| (column, tasks)))
+        {
+            for (position, task) in tasks.iter().enumerate() {
+                if seen.insert(&task.id) {
+                    continue;
+                }
+                findings.push(Finding {
+                    kind: FindingKind::DuplicateTaskId,
+                    path: board_name.clone(),
+                    line: None,
+                    message: format!(
+                        "Task '{}' in column '{}' reuses the ID {}",
+                        task.title, task.column, task.id
+                    ),
+                    fix: Some(Fix::RenumberTask {
+                        kanban_directory: kanban_directory.to_path_buf(),
+                        board: board_name.clone(),
+                        task_id: task.id.clone(),
+                        column: column.clone(),
+                        position,
+                    }),
+                });
+            }
+        }
+    }
+    Ok(findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspaces::crud::create_workspace;
+    use tempfile::tempdir;
+
+    fn kinds(report: &DoctorReport) -> Vec<(FindingKind, &str)> {
+        report
+            .findings
+            .iter()
+            .map(|f| (f.kind, f.path.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_vault_doctor() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        let stamps = "created_on: 2025-01-01\nmodified_at: 2025-01-01 10:00:00\n";
+        fs::write(
+            vault_dir.join("Good.md"),
+            format!(
+                "---\ntitle: Good\n{}---\n[[Renamed Title]] ![[pic.png]] [[Gone]]\n",
+                stamps
+            ),
+        )?;
+        fs::write(
+            vault_dir.join("Old-Name.md"),
+            format!("---\ntitle: Renamed Title\n{}---\nBody\n", stamps),
+        )?;
+        fs::create_dir_all(vault_dir.join("sub"))?;
+        fs::write(
+            vault_dir.join("sub/Good.md"),
+            format!("---\ntitle: Good\n{}---\n", stamps),
+        )?;
+        fs::write(vault_dir.join("Bare.md"), "---\ntitle: Bare\n---\nText\n")?;
+        fs::write(vault_dir.join("Broken.md"), "---\ntitle: [oops\n---\n")?;
+        fs::write(vault_dir.join("pic.png"), [0u8; 4])?;
+
+        create_workspace(vault_dir, "Focus", "Good.md")?;
+        fs::write(
+            vault_dir.join("workspaces/Focus.txt"),
+            "Good.md\ndeleted.md\n",
+        )?;
+
+        let kanban_dir = vault_dir.join("boards");
+        fs::create_dir_all(&kanban_dir)?;
+        fs::write(
+            kanban_dir.join("Work.tkf"),
+            "Date: 2025-01-01\nDescription: Tasks\n\n== Todo ==\n* [ID:1] First\n* [ID:2] Second\n\n== Done ==\n* [ID:1] Third\n* [ID:1] Fourth\n",
+        )?;
+
+        let report = vault_doctor(vault_dir, Some(&kanban_dir))?;
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (FindingKind::MalformedFrontmatter, "Broken.md"),
+                (FindingKind::DuplicateTitle, "Good.md"),
+                (FindingKind::DuplicateTitle, "sub/Good.md"),
+                (FindingKind::FileNameMismatch, "Old-Name.md"),
+                (FindingKind::MissingTimestamps, "Bare.md"),
+                (FindingKind::BrokenLink, "Good.md"),
+                (FindingKind::StaleWorkspaceEntry, "Focus"),
+                (FindingKind::DuplicateTaskId, "Work"),
+                (FindingKind::DuplicateTaskId, "Work"),
+            ]
+        );
+        assert!(report.findings[0].line.is_some());
+        assert!(report.findings[5].message.contains("Gone"));
+        assert_eq!(report.fixes().len(), 5);
+
+        // A dry run changes nothing.
+        let outcomes = apply_fixes(vault_dir, &report, FixMode::DryRun)?;
+        assert_eq!(outcomes.len(), 5);
+        assert!(outcomes.iter().all(|o| !o.applied && o.error.is_none()));
+        assert!(vault_dir.join("Old-Name.md").exists());
+
+        let outcomes = apply_fixes(vault_dir, &report, FixMode::Commit)?;
+        assert!(outcomes.iter().all(|o| o.applied), "{:?}", outcomes);
+        assert!(matches!(
+            outcomes.last().map(|o| &o.fix),
+            Some(Fix::RenameFile { .. })
+        ));
+
+        let report = vault_doctor(vault_dir, Some(&kanban_dir))?;
+        assert_eq!(
+            kinds(&report),
+            vec![
+                (FindingKind::MalformedFrontmatter, "Broken.md"),
+                (FindingKind::DuplicateTitle, "Good.md"),
+                (FindingKind::DuplicateTitle, "sub/Good.md"),
+                (FindingKind::BrokenLink, "Good.md"),
+            ]
+        );
+        assert!(vault_dir.join("Renamed-Title.md").exists());
+        assert!(fs::read_to_string(vault_dir.join("Bare.md"))?.contains("created_on: "));
+        assert!(fs::read_to_string(vault_dir.join("Bare.md"))?.ends_with("---\nText\n"));
+        let board = read_board("Work", &kanban_dir)?;
+        let ids: Vec<_> = board.tasks["Done"].iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "4"]);
+        assert_eq!(board.tasks["Todo"][0].id, "1");
+        Ok(())
+    }
+}
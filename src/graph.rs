@@ -0,0 +1,621 @@
+// src/graph.rs
+
+use crate::index::VaultIndex;
+use crate::links::LinkResolver;
+use crate::tags::TagPolicy;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+
+/// Prefix of the ids of tag nodes, which keeps them apart from note paths.
+const TAG_ID_PREFIX: &str = "tag:";
+
+/// Damping factor of PageRank: the probability of following a link rather than jumping
+/// to a random node.
+const PAGERANK_DAMPING: f64 = 0.85;
+
+/// PageRank stops once the scores change by less than this in total.
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+
+const PAGERANK_MAX_ITERATIONS: usize = 100;
+
+/// What a node of the note graph stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Note,
+    Tag,
+}
+
+/// A node of the note graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphNode {
+    /// The vault-relative path of a note, or `tag:<tag>` for a tag
+    pub id: String,
+    pub kind: NodeKind,
+    /// The title of a note, or `#<tag>` for a tag
+    pub label: String,
+}
+
+/// What an edge of the note graph stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// Links in the body of the source note to the target note
+    Link,
+    /// The source note has the target tag, in its frontmatter or as a `#hashtag`
+    Tag,
+}
+
+/// A directed edge of the note graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GraphEdge {
+    /// Id of the source node
+    pub source: String,
+    /// Id of the target node
+    pub target: String,
+    pub kind: EdgeKind,
+    /// Number of links from the source to the target (always 1 for tag edges)
+    pub weight: usize,
+}
+
+/// Degree and PageRank of a node, as returned by `NoteGraph::centrality`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Centrality {
+    pub id: String,
+    /// Number of edges pointing to the node
+    pub in_degree: usize,
+    /// Number of edges leaving the node
+    pub out_degree: usize,
+    /// PageRank of the node, with all scores summing to 1
+    pub pagerank: f64,
+}
+
+/// The graph of the notes of a vault, with the links between notes and, optionally,
+/// the tags of each note as edges to tag nodes.
+///
+/// Links are directed edges from the linking note to the linked note. Neighborhoods,
+/// shortest paths and components ignore the direction of the edges, so two notes tagged
+/// alike are two hops apart through their tag. Serialized, the graph is the JSON
+/// `{"nodes": [...], "edges": [...]}` list also returned by `to_json`.
+///
+/// Functions taking a node accept its id (a note path or `tag:<tag>`) or the title of
+/// a note, compared case-insensitively.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NoteGraph {
+    /// Nodes sorted by id
+    nodes: Vec<GraphNode>,
+    /// Edges sorted by source, target and kind
+    edges: Vec<GraphEdge>,
+    #[serde(skip)]
+    node_index: HashMap<String, usize>,
+}
+
+impl NoteGraph {
+    /// Builds the graph of a vault from the current content of its notes.
+    ///
+    /// The stored `VaultIndex` is reused for notes that didn't change, but isn't
+    /// written back.
+    ///
+    /// # Arguments
+    /// * `vault_directory` - The base directory of the vault
+    /// * `include_tags` - If true, tags (frontmatter and inline) are added as nodes linked to their notes
+    ///
+    /// # Returns
+    /// * `Result<NoteGraph>` - The graph of the vault
+    ///
+    /// # Errors
+    /// * Returns an error if the vault can't be walked or the tag policy can't be read
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::path::Path;
+    /// use notemancy_core::graph::NoteGraph;
+    ///
+    /// let graph = NoteGraph::build(Path::new("/path/to/vault"), true)?;
+    /// let nearby = graph.neighborhood("Meeting Notes", 2)?;
+    /// std::fs::write("meeting.dot", nearby.to_dot())?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn build(vault_directory: &Path, include_tags: bool) -> Result<Self> {
+        let mut index = VaultIndex::load(vault_directory)?;
+        index.refresh()?;
+        Self::from_index(&index, include_tags)
+    }
+
+    /// Builds the graph from a `VaultIndex` without reading the notes.
+    ///
+    /// Tags are resolved to their canonical form through the vault's `TagPolicy`.
+    ///
+    /// # Arguments
+    /// * `index` - A refreshed index of the vault
+    /// * `include_tags` - If true, tags (frontmatter and inline) are added as nodes linked to their notes
+    ///
+    /// # Returns
+    /// * `Result<NoteGraph>` - The graph of the indexed notes
+    ///
+    /// # Errors
+    /// * Returns an error if the tag policy of the vault can't be read
+    pub fn from_index(index: &VaultIndex, include_tags: bool) -> Result<Self> {
+        let policy = TagPolicy::load(index.vault_directory())?;
+        let resolver = LinkResolver::from_index(index);
+
+        let mut nodes = BTreeMap::new();
+        let mut edges: BTreeMap<(String, String, EdgeKind), usize> = BTreeMap::new();
+        for entry in index.entries() {
+            nodes.insert(entry.path.clone(), (NodeKind::Note, entry.title.clone()));
+            for link in &entry.links {
+                // Self-links and links to missing notes or websites are left out.
+                if let Some(target) = resolver.resolve(&entry.path, link)
+                    && target != entry.path
+                {
+                    *edges
+                        .entry((entry.path.clone(), target.to_string(), EdgeKind::Link))
+                        .or_insert(0) += 1;
+                }
+            }
+            if include_tags {
+                for tag in entry.tags.iter().map(|tag| policy.canonical(tag)) {
+                    let id = format!("{}{}", TAG_ID_PREFIX, tag);
+                    nodes.insert(id.clone(), (NodeKind::Tag, format!("#{}", tag)));
+                    edges.insert((entry.path.clone(), id, EdgeKind::Tag), 1);
+                }
+            }
+        }
+
+        Ok(Self::from_parts(
+            nodes
+                .into_iter()
+                .map(|(id, (kind, label))| GraphNode { id, kind, label })
+                .collect(),
+            edges
+                .into_iter()
+                .map(|((source, target, kind), weight)| GraphEdge {
+                    source,
+                    target,
+                    kind,
+                    weight,
+                })
+                .collect(),
+        ))
+    }
+
+    /// Helper function to assemble a graph from sorted nodes and edges between them.
+    fn from_parts(nodes: Vec<GraphNode>, edges: Vec<GraphEdge>) -> Self {
+        let node_index = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.clone(), i))
+            .collect();
+        NoteGraph {
+            nodes,
+            edges,
+            node_index,
+        }
+    }
+
+    /// Returns the nodes of the graph, sorted by id.
+    pub fn nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    /// Returns the edges of the graph, sorted by source, target and kind.
+    pub fn edges(&self) -> &[GraphEdge] {
+        &self.edges
+    }
+
+    /// Finds a node by id, or a note by title (case-insensitively).
+    pub fn find(&self, node: &str) -> Option<&GraphNode> {
+        self.find_index(node).map(|i| &self.nodes[i])
+    }
+
+    /// Helper function to find the position of a node by id or note title.
+    fn find_index(&self, node: &str) -> Option<usize> {
+        self.node_index.get(node).copied().or_else(|| {
+            self.nodes
+                .iter()
+                .position(|n| n.kind == NodeKind::Note && n.label.eq_ignore_ascii_case(node))
+        })
+    }
+
+    /// Helper function to find the position of a node, or fail with a readable error.
+    fn require(&self, node: &str) -> Result<usize> {
+        self.find_index(node)
+            .ok_or_else(|| anyhow!("Failed to find note or tag in the graph: {}", node))
+    }
+
+    /// Helper function to list the neighbors of each node, ignoring edge direction.
+    fn undirected_adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); self.nodes.len()];
+        for edge in &self.edges {
+            let (source, target) = (self.node_index[&edge.source], self.node_index[&edge.target]);
+            adjacency[source].push(target);
+            adjacency[target].push(source);
+        }
+        for neighbors in &mut adjacency {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+        adjacency
+    }
+
+    /// Returns the subgraph of the nodes at most `hops` edges away from a node,
+    /// with the edges between them.
+    ///
+    /// # Arguments
+    /// * `node` - The id of the center node, or the title of a note
+    /// * `hops` - The maximum distance from the center, ignoring edge direction
+    ///
+    /// # Returns
+    /// * `Result<NoteGraph>` - The neighborhood, which can be exported like the whole graph
+    ///
+    /// # Errors
+    /// * Returns an error if the node isn't in the graph
+    pub fn neighborhood(&self, node: &str, hops: usize) -> Result<NoteGraph> {
+        let start = self.require(node)?;
+        let adjacency = self.undirected_adjacency();
+        let mut distances = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[&current];
+            if distance == hops {
+                continue;
+            }
+            for &next in &adjacency[current] {
+                if let Entry::Vacant(vacant) = distances.entry(next) {
+                    vacant.insert(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let ids: BTreeSet<&str> = distances
+            .keys()
+            .map(|&i| self.nodes[i].id.as_str())
+            .collect();
+        Ok(Self::from_parts(
+            self.nodes
+                .iter()
+                .filter(|n| ids.contains(n.id.as_str()))
+                .cloned()
+                .collect(),
+            self.edges
+                .iter()
+                .filter(|e| ids.contains(e.source.as_str()) && ids.contains(e.target.as_str()))
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    /// Finds a shortest path between two nodes, ignoring edge direction.
+    ///
+    /// # Arguments
+    /// * `from` - The id or note title of the first node
+    /// * `to` - The id or note title of the last node
+    ///
+    /// # Returns
+    /// * `Result<Option<Vec<&GraphNode>>>` - The nodes of the path from `from` to `to`,
+    ///   both included, or `None` if they aren't connected. Among paths of equal length,
+    ///   the one through the smallest ids is returned.
+    ///
+    /// # Errors
+    /// * Returns an error if either node isn't in the graph
+    pub fn shortest_path(&self, from: &str, to: &str) -> Result<Option<Vec<&GraphNode>>> {
+        let (start, end) = (self.require(from)?, self.require(to)?);
+        let adjacency = self.undirected_adjacency();
+        let mut previous: HashMap<usize, usize> = HashMap::new();
+        let mut visited = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            if current == end {
+                break;
+            }
+            for &next in &adjacency[current] {
+                if visited.insert(next) {
+                    previous.insert(next, current);
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !visited.contains(&end) {
+            return Ok(None);
+        }
+
+        let mut path = vec![end];
+        while let Some(&before) = path.last().and_then(|last| previous.get(last)) {
+            path.push(before);
+        }
+        path.reverse();
+        Ok(Some(path.into_iter().map(|i| &self.nodes[i]).collect()))
+    }
+
+    /// Splits the graph into connected components, ignoring edge direction.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<&GraphNode>>` - The components, largest first (ties broken by their
+    ///   first id), each sorted by id. An isolated note is a component of its own.
+    pub fn components(&self) -> Vec<Vec<&GraphNode>> {
+        let adjacency = self.undirected_adjacency();
+        let mut component_of = vec![usize::MAX; self.nodes.len()];
+        let mut components: Vec<Vec<&GraphNode>> = Vec::new();
+        for start in 0..self.nodes.len() {
+            if component_of[start] != usize::MAX {
+                continue;
+            }
+            let mut members = Vec::new();
+            let mut stack = vec![start];
+            component_of[start] = components.len();
+            while let Some(current) = stack.pop() {
+                members.push(current);
+                for &next in &adjacency[current] {
+                    if component_of[next] == usize::MAX {
+                        component_of[next] = components.len();
+                        stack.push(next);
+                    }
+                }
+            }
+            members.sort_unstable();
+            components.push(members.into_iter().map(|i| &self.nodes[i]).collect());
+        }
+        components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].id.cmp(&b[0].id)));
+        components
+    }
+
+    /// Computes the degree and PageRank of every node.
+    ///
+    /// PageRank follows the edges in their direction, weighted by the number of links,
+    /// with a damping factor of 0.85. The score of nodes without outgoing edges (like
+    /// tags) is spread evenly over the graph.
+    ///
+    /// # Returns
+    /// * `Vec<Centrality>` - One entry per node, by decreasing PageRank, then by id
+    pub fn centrality(&self) -> Vec<Centrality> {
+        let count = self.nodes.len();
+        if count == 0 {
+            return Vec::new();
+        }
+        let mut in_degree = vec![0; count];
+        let mut out_degree = vec![0; count];
+        let mut out_weight = vec![0.0; count];
+        let mut edges = Vec::with_capacity(self.edges.len());
+        for edge in &self.edges {
+            let (source, target) = (self.node_index[&edge.source], self.node_index[&edge.target]);
+            in_degree[target] += 1;
+            out_degree[source] += 1;
+            out_weight[source] += edge.weight as f64;
+            edges.push((source, target, edge.weight as f64));
+        }
+
+        let uniform = 1.0 / count as f64;
+        let mut ranks = vec![uniform; count];
+        for _ in 0..PAGERANK_MAX_ITERATIONS {
+            let dangling: f64 = (0..count)
+                .filter(|&i| out_degree[i] == 0)
+                .map(|i| ranks[i])
+                .sum();
+            let base = (1.0 - PAGERANK_DAMPING) * uniform + PAGERANK_DAMPING * dangling * uniform;
+            let mut next = vec![base; count];
+            for &(source, target, weight) in &edges {
+                next[target] += PAGERANK_DAMPING * ranks[source] * weight / out_weight[source];
+            }
+            let change: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+            ranks = next;
+            if change < PAGERANK_TOLERANCE {
+                break;
+            }
+        }
+
+        let mut centrality: Vec<Centrality> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| Centrality {
+                id: node.id.clone(),
+                in_degree: in_degree[i],
+                out_degree: out_degree[i],
+                pagerank: ranks[i],
+            })
+            .collect();
+        centrality.sort_by(|a, b| {
+            b.pagerank
+                .total_cmp(&a.pagerank)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        centrality
+    }
+
+    /// Exports the graph in the Graphviz DOT format.
+    ///
+    /// Notes are drawn as ellipses and tags as boxes; tag edges are dashed and link edges
+    /// carry their number of links as `weight`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph notes {\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Note => "ellipse",
+                NodeKind::Tag => "box",
+            };
+            dot.push_str(&format!(
+                "  {} [label={}, shape={}];\n",
+                dot_string(&node.id),
+                dot_string(&node.label),
+                shape
+            ));
+        }
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::Link => format!("weight={}", edge.weight),
+                EdgeKind::Tag => "style=dashed".to_string(),
+            };
+            dot.push_str(&format!(
+                "  {} -> {} [{}];\n",
+                dot_string(&edge.source),
+                dot_string(&edge.target),
+                attributes
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the graph in the GraphML format, with `label` and `kind` attributes on
+    /// nodes and `kind` and `weight` attributes on edges.
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n\
+             \x20 <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"kind\" for=\"all\" attr.name=\"kind\" attr.type=\"string\"/>\n\
+             \x20 <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"int\"/>\n\
+             \x20 <graph id=\"notes\" edgedefault=\"directed\">\n",
+        );
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"kind\">{}</data>\n    </node>\n",
+                xml_escape(&node.id),
+                xml_escape(&node.label),
+                kind_name(&node.kind)
+            ));
+        }
+        for edge in &self.edges {
+            xml.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"kind\">{}</data>\n      <data key=\"weight\">{}</data>\n    </edge>\n",
+                xml_escape(&edge.source),
+                xml_escape(&edge.target),
+                kind_name(&edge.kind),
+                edge.weight
+            ));
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// Exports the graph as a JSON object with a `nodes` and an `edges` list.
+    ///
+    /// # Errors
+    /// * Returns an error if the graph can't be serialized
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize the note graph")
+    }
+}
+
+/// Helper function to quote a string as a DOT identifier.
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Helper function to escape text for XML content and attributes.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Helper function to get the serialized name of a node or edge kind.
+fn kind_name<T: Serialize>(kind: &T) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_vault(vault_dir: &Path) -> Result<()> {
+        for (file, content) in [
+            (
+                "a.md",
+                "---\ntitle: Alpha\ntags: [rust]\n---\nSee [[Beta]] and [[Beta|again]].",
+            ),
+            (
+                "b.md",
+                "---\ntitle: Beta\n---\nOn to [[Gamma]], and [[Missing]].",
+            ),
+            (
+                "c.md",
+                "---\ntitle: Gamma\ntags: [rust, \"a&b\"]\n---\nBack to [[Alpha]].",
+            ),
+            ("d.md", "---\ntitle: Delta\n---\nAlone, linking [[Delta]]."),
+        ] {
+            fs::write(vault_dir.join(file), content)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_queries() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        write_vault(vault_dir)?;
+
+        let graph = NoteGraph::build(vault_dir, true)?;
+        let ids: Vec<_> = graph.nodes().iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec!["a.md", "b.md", "c.md", "d.md", "tag:a&b", "tag:rust"]
+        );
+        assert_eq!(graph.edges().len(), 6);
+        assert_eq!(graph.edges()[0].weight, 2);
+        assert!(!VaultIndex::index_path(vault_dir).exists());
+
+        let ids = |nodes: &[&GraphNode]| nodes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        let path = graph.shortest_path("alpha", "c.md")?.unwrap();
+        assert_eq!(ids(&path), vec!["a.md", "c.md"]);
+        assert!(graph.shortest_path("Alpha", "Delta")?.is_none());
+        assert!(graph.shortest_path("Alpha", "Nowhere").is_err());
+
+        let near = graph.neighborhood("Beta", 1)?;
+        let near_ids: Vec<_> = near.nodes().iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(near_ids, vec!["a.md", "b.md", "c.md"]);
+        assert_eq!(near.edges().len(), 3);
+        assert_eq!(graph.neighborhood("tag:a&b", 2)?.nodes().len(), 5);
+
+        let components = graph.components();
+        assert_eq!(components.len(), 2);
+        assert_eq!(ids(&components[1]), vec!["d.md"]);
+
+        let centrality = graph.centrality();
+        let total: f64 = centrality.iter().map(|c| c.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        let rust = centrality.iter().find(|c| c.id == "tag:rust").unwrap();
+        assert_eq!((rust.in_degree, rust.out_degree), (2, 0));
+        assert_eq!(centrality.last().unwrap().id, "d.md");
+
+        let untagged = NoteGraph::build(vault_dir, false)?;
+        assert_eq!(untagged.nodes().len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_graph_export() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        write_vault(vault_dir)?;
+        let graph = NoteGraph::build(vault_dir, true)?.neighborhood("Gamma", 1)?;
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph notes {\n"));
+        assert!(dot.contains("  \"c.md\" [label=\"Gamma\", shape=ellipse];\n"));
+        assert!(dot.contains("  \"a.md\" -> \"tag:rust\" [style=dashed];\n"));
+        assert!(dot.contains("  \"b.md\" -> \"c.md\" [weight=1];\n"));
+
+        let graphml = graph.to_graphml();
+        assert!(graphml.contains("<node id=\"tag:a&amp;b\">"));
+        assert!(graphml.contains("<edge source=\"c.md\" target=\"a.md\">"));
+        assert!(graphml.contains("<data key=\"kind\">tag</data>"));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()?)?;
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 5);
+        assert_eq!(json["edges"][0]["source"], "a.md");
+        assert_eq!(json["edges"][0]["kind"], "link");
+        Ok(())
+    }
+}
@@ -0,0 +1,519 @@
+// src/index.rs
+
+use crate::links::{Link, LinkResolver, ResolvedLink, extract_links};
+use crate::notes::frontmatter::Frontmatter;
+use crate::notes::utils::{
+    fallback_title, note_file_stem, relative_note_path, sanitize_title, walk_markdown_files,
+    yaml_to_json,
+};
+use crate::query_native::NoteMetadata;
+use crate::tags::note_tags;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Name of the hidden directory inside the vault where notemancy keeps its caches.
+pub const INDEX_DIRECTORY: &str = ".notemancy";
+
+/// Name of the metadata index file inside [`INDEX_DIRECTORY`].
+const INDEX_FILE: &str = "index.json";
+
+/// Version of the on-disk format. Indexes written with another version are rebuilt.
+const INDEX_VERSION: u32 = 4;
+
+/// Metadata for a single note, as stored in the vault index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Path of the note relative to the vault directory, using forward slashes
+    pub path: String,
+    /// Title from the frontmatter, falling back to the file name
+    pub title: String,
+    /// The parsed YAML frontmatter as JSON (`null` if missing or malformed)
+    pub frontmatter: JsonValue,
+    /// Tags listed in the frontmatter, then inline `#hashtags` of the body
+    pub tags: Vec<String>,
+    /// Aliases listed in the frontmatter
+    pub aliases: Vec<String>,
+    /// Outgoing markdown links and wikilinks in the note
+    pub links: Vec<Link>,
+    /// Modification time in nanoseconds since the Unix epoch
+    pub mtime: u64,
+    /// File size in bytes
+    pub size: u64,
+    /// FNV-1a hash of the file content
+    pub hash: u64,
+}
+
+/// Counts of what happened to the index entries during a refresh.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshStats {
+    /// Notes that were not in the index before
+    pub added: usize,
+    /// Notes whose content changed and that were reparsed
+    pub updated: usize,
+    /// Notes that were reused from the index without reparsing
+    pub unchanged: usize,
+    /// Notes that no longer exist in the vault
+    pub removed: usize,
+}
+
+/// A persistent index of note metadata stored under `.notemancy/index.json` in the vault.
+///
+/// Opening the index refreshes it incrementally: only files whose modification time or
+/// size changed are read again, and of those only files whose content hash changed are
+/// reparsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultIndex {
+    version: u32,
+    #[serde(skip)]
+    vault_directory: PathBuf,
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+impl VaultIndex {
+    /// Loads the index for the vault, refreshes it and writes it back to disk.
+    ///
+    /// # Arguments
+    /// * `vault_directory` - The base directory of the vault
+    ///
+    /// # Returns
+    /// * `Result<VaultIndex>` - An index that reflects the current state of the vault
+    ///
+    /// # Errors
+    /// * Returns an error if the vault can't be walked
+    /// * Returns an error if the index file can't be written
+    pub fn open(vault_directory: &Path) -> Result<Self> {
+        let mut index = Self::load(vault_directory)?;
+        index.refresh()?;
+        index.save()?;
+        Ok(index)
+    }
+
+    /// Loads the index from disk without refreshing it.
+    ///
+    /// A missing, unreadable or outdated index file results in an empty index, which
+    /// will be rebuilt completely on the next refresh.
+    ///
+    /// # Arguments
+    /// * `vault_directory` - The base directory of the vault
+    ///
+    /// # Returns
+    /// * `Result<VaultIndex>` - The stored index, or an empty one
+    pub fn load(vault_directory: &Path) -> Result<Self> {
+        let index_path = Self::index_path(vault_directory);
+        let stored = fs::read(&index_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<VaultIndex>(&bytes).ok())
+            .filter(|index| index.version == INDEX_VERSION);
+
+        let mut index = stored.unwrap_or_else(|| VaultIndex {
+            version: INDEX_VERSION,
+            vault_directory: PathBuf::new(),
+            entries: BTreeMap::new(),
+        });
+        index.vault_directory = vault_directory.to_path_buf();
+        Ok(index)
+    }
+
+    /// Brings the index up to date with the files in the vault.
+    ///
+    /// # Returns
+    /// * `Result<RefreshStats>` - How many entries were added, updated, reused and removed
+    ///
+    /// # Errors
+    /// * Returns an error if the vault can't be walked
+    pub fn refresh(&mut self) -> Result<RefreshStats> {
+        let files = walk_markdown_files(&self.vault_directory)?;
+        let mut stats = RefreshStats::default();
+
+        // Work out which files need to be read again based on mtime and size.
+        let mut seen = BTreeSet::new();
+        let mut to_read = Vec::new();
+        for path in files {
+            let relative_path = relative_note_path(&path, &self.vault_directory);
+            let Ok((mtime, size)) = file_stamp(&path) else {
+                continue;
+            };
+            seen.insert(relative_path.clone());
+            match self.entries.get(&relative_path) {
+                Some(entry) if entry.mtime == mtime && entry.size == size => stats.unchanged += 1,
+                _ => to_read.push((relative_path, path, mtime, size)),
+            }
+        }
+
+        // Read and (if the hash changed) parse the candidates in parallel.
+        let previous = &self.entries;
+        let results: Vec<(IndexEntry, bool)> = to_read
+            .into_par_iter()
+            .filter_map(|(relative_path, path, mtime, size)| {
+                let bytes = fs::read(&path).ok()?;
+                let hash = fnv1a_hash(&bytes);
+                match previous.get(&relative_path) {
+                    Some(entry) if entry.hash == hash => {
+                        let mut entry = entry.clone();
+                        entry.mtime = mtime;
+                        entry.size = size;
+                        Some((entry, false))
+                    }
+                    _ => {
+                        let content = String::from_utf8_lossy(&bytes);
+                        let entry = parse_entry(&relative_path, &content, mtime, size, hash);
+                        Some((entry, true))
+                    }
+                }
+            })
+            .collect();
+
+        for (entry, reparsed) in results {
+            match (reparsed, self.entries.contains_key(&entry.path)) {
+                (false, _) => stats.unchanged += 1,
+                (true, true) => stats.updated += 1,
+                (true, false) => stats.added += 1,
+            }
+            self.entries.insert(entry.path.clone(), entry);
+        }
+
+        // Drop entries for files that no longer exist.
+        let before = self.entries.len();
+        self.entries.retain(|path, _| seen.contains(path));
+        stats.removed = before - self.entries.len();
+
+        Ok(stats)
+    }
+
+    /// Writes the index to `.notemancy/index.json` in the vault.
+    ///
+    /// # Returns
+    /// * `Result<PathBuf>` - The path to the index file
+    ///
+    /// # Errors
+    /// * Returns an error if the index directory or file can't be written
+    pub fn save(&self) -> Result<PathBuf> {
+        let index_dir = self.vault_directory.join(INDEX_DIRECTORY);
+        fs::create_dir_all(&index_dir).context("Failed to create index directory")?;
+
+        let index_path = index_dir.join(INDEX_FILE);
+        let json = serde_json::to_vec(self).context("Failed to serialize vault index")?;
+        write_atomically(&index_path, &json).context("Failed to write vault index")?;
+
+        Ok(index_path)
+    }
+
+    /// Returns the path of the index file for the given vault.
+    pub fn index_path(vault_directory: &Path) -> PathBuf {
+        vault_directory.join(INDEX_DIRECTORY).join(INDEX_FILE)
+    }
+
+    /// Returns the vault directory this index belongs to.
+    pub fn vault_directory(&self) -> &Path {
+        &self.vault_directory
+    }
+
+    /// Returns all entries, ordered by relative path.
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
+    }
+
+    /// Returns the number of notes in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the index contains no notes.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry for a note by its path relative to the vault.
+    pub fn get(&self, relative_path: &str) -> Option<&IndexEntry> {
+        self.entries.get(relative_path)
+    }
+
+    /// Finds a note by title.
+    ///
+    /// Like `notes::utils::get_file_path`, a note whose frontmatter title matches is
+    /// preferred, and a note whose file name is the sanitized title is the fallback.
+    pub fn find_by_title(&self, title: &str) -> Option<&IndexEntry> {
+        self.entries
+            .values()
+            .find(|entry| entry.frontmatter_title() == Some(title))
+            .or_else(|| {
+                let sanitized = sanitize_title(title);
+                self.entries
+                    .values()
+                    .find(|entry| note_file_stem(&entry.path) == sanitized)
+            })
+    }
+
+    /// Returns the notes that link to the note at `relative_path`, ordered by path.
+    pub fn backlinks(&self, relative_path: &str) -> Vec<&IndexEntry> {
+        if !self.entries.contains_key(relative_path) {
+            return Vec::new();
+        }
+        let resolver = LinkResolver::from_index(self);
+        self.entries
+            .values()
+            .filter(|entry| entry.path != relative_path)
+            .filter(|entry| {
+                entry
+                    .links
+                    .iter()
+                    .any(|link| resolver.resolve(&entry.path, link) == Some(relative_path))
+            })
+            .collect()
+    }
+
+    /// Returns the outgoing links of the note at `relative_path`, resolved against the index.
+    pub fn outgoing_links(&self, relative_path: &str) -> Vec<ResolvedLink> {
+        let Some(entry) = self.entries.get(relative_path) else {
+            return Vec::new();
+        };
+        let resolver = LinkResolver::from_index(self);
+        entry
+            .links
+            .iter()
+            .map(|link| ResolvedLink {
+                link: link.clone(),
+                path: resolver.resolve(&entry.path, link).map(str::to_string),
+            })
+            .collect()
+    }
+
+    /// Converts the entries with frontmatter into `NoteMetadata` records for the query engine.
+    pub fn note_metadata(&self) -> Vec<NoteMetadata> {
+        self.entries
+            .values()
+            .filter_map(IndexEntry::note_metadata)
+            .collect()
+    }
+}
+
+impl IndexEntry {
+    /// Returns the `title` field of the frontmatter, if present.
+    pub fn frontmatter_title(&self) -> Option<&str> {
+        self.frontmatter.get("title").and_then(|t| t.as_str())
+    }
+
+    /// Converts the entry into a `NoteMetadata` record for the query engine.
+    /// Returns `None` if the note has no frontmatter mapping.
+    pub fn note_metadata(&self) -> Option<NoteMetadata> {
+        if !self.frontmatter.is_object() {
+            return None;
+        }
+        let yaml = serde_yaml::to_value(&self.frontmatter).ok()?;
+        let frontmatter = Frontmatter::from_value(yaml).ok()?;
+        Some(NoteMetadata {
+            path: self.path.clone(),
+            title: self.title.clone(),
+            date: self
+                .frontmatter
+                .get("date")
+                .and_then(|d| d.as_str())
+                .map(|d| d.to_string()),
+            tags: self.tags.clone(),
+            frontmatter,
+        })
+    }
+}
+
+/// Helper function to parse a note into an index entry.
+fn parse_entry(relative_path: &str, content: &str, mtime: u64, size: u64, hash: u64) -> IndexEntry {
+    let yaml = Frontmatter::split(content)
+        .and_then(|(front, _)| serde_yaml::from_str::<serde_yaml::Value>(front).ok());
+    let parsed = yaml
+        .clone()
+        .filter(|value| value.is_mapping())
+        .and_then(|value| Frontmatter::from_value(value).ok())
+        .unwrap_or_default();
+    let frontmatter = yaml.map(yaml_to_json).unwrap_or(JsonValue::Null);
+
+    let title = parsed
+        .title
+        .unwrap_or_else(|| fallback_title(relative_path));
+
+    IndexEntry {
+        path: relative_path.to_string(),
+        title,
+        frontmatter,
+        tags: note_tags(content),
+        aliases: parsed.aliases,
+        links: extract_links(content),
+        mtime,
+        size,
+        hash,
+    }
+}
+
+/// Helper function to replace a file without ever leaving it truncated.
+///
+/// The contents are written to a uniquely named temporary file in the same directory,
+/// which is then renamed over `path`, so concurrent writers never share a temporary file.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file =
+        tempfile::NamedTempFile::new_in(directory).context("Failed to create temporary file")?;
+    file.write_all(contents)
+        .context("Failed to write temporary file")?;
+    file.persist(path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Helper function to read the modification time (ns since epoch) and size of a file.
+pub(crate) fn file_stamp(path: &Path) -> Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let mtime = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    Ok((mtime, metadata.len()))
+}
+
+/// Computes the 64-bit FNV-1a hash of the given bytes.
+///
+/// This is stable across platforms and Rust versions, unlike `DefaultHasher`.
+pub(crate) fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    // Helper: Create a markdown note with a title, tags and body.
+    fn write_note(dir: &Path, filename: &str, title: &str, tags: &[&str], body: &str) -> PathBuf {
+        let mut content = format!("---\ntitle: {}\ntags:\n", title);
+        for tag in tags {
+            content.push_str(&format!("  - {}\n", tag));
+        }
+        content.push_str(&format!("---\n\n{}\n", body));
+        let path = dir.join(filename);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_build_index() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::create_dir_all(vault_dir.join("project"))?;
+        write_note(
+            vault_dir,
+            "One.md",
+            "Note One",
+            &["rust"],
+            "See [[Note Two]].",
+        );
+        write_note(
+            vault_dir,
+            "project/Two.md",
+            "Note Two",
+            &[],
+            "Back to [one](../One.md) and [site](https://example.com).",
+        );
+
+        let index = VaultIndex::open(vault_dir)?;
+        assert_eq!(index.len(), 2);
+        assert!(VaultIndex::index_path(vault_dir).exists());
+
+        let one = index.get("One.md").unwrap();
+        assert_eq!(one.title, "Note One");
+        assert_eq!(one.tags, vec!["rust".to_string()]);
+        let targets: Vec<_> = one.links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["Note Two"]);
+
+        let two = index.get("project/Two.md").unwrap();
+        let targets: Vec<_> = two.links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["../One.md", "https://example.com"]);
+        let outgoing: Vec<_> = index
+            .outgoing_links("project/Two.md")
+            .into_iter()
+            .map(|resolved| resolved.path)
+            .collect();
+        assert_eq!(outgoing, vec![Some("One.md".to_string()), None]);
+        assert_eq!(
+            index.find_by_title("Note Two").unwrap().path,
+            "project/Two.md"
+        );
+
+        // [[Note Two]] resolves by title
+        let backlinks: Vec<_> = index.backlinks("project/Two.md");
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].path, "One.md");
+
+        // ../One.md resolves relative to the folder of the linking note
+        let backlinks: Vec<_> = index.backlinks("One.md");
+        assert_eq!(backlinks.len(), 1);
+        assert_eq!(backlinks[0].path, "project/Two.md");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_refresh() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        write_note(vault_dir, "a.md", "A", &[], "first");
+        let b_path = write_note(vault_dir, "b.md", "B", &[], "second");
+        let c_path = write_note(vault_dir, "c.md", "C", &[], "third");
+        VaultIndex::open(vault_dir)?;
+
+        // Reloading from disk reuses every entry.
+        let mut index = VaultIndex::load(vault_dir)?;
+        assert_eq!(index.len(), 3);
+        let stats = index.refresh()?;
+        assert_eq!(stats.unchanged, 3);
+        assert_eq!(stats.added + stats.updated + stats.removed, 0);
+
+        // Touching a file without changing its content does not reparse it.
+        let later = SystemTime::now() + Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&b_path)?
+            .set_modified(later)?;
+        // Changing the content reparses the file, and deleting one removes it.
+        write_note(vault_dir, "c.md", "C Renamed", &["new"], "changed");
+        File::options()
+            .write(true)
+            .open(&c_path)?
+            .set_modified(later)?;
+        fs::remove_file(vault_dir.join("a.md"))?;
+        write_note(vault_dir, "d.md", "D", &[], "fourth");
+
+        let stats = index.refresh()?;
+        assert_eq!(
+            stats,
+            RefreshStats {
+                added: 1,
+                updated: 1,
+                unchanged: 1,
+                removed: 1,
+            }
+        );
+        assert_eq!(index.get("c.md").unwrap().title, "C Renamed");
+        assert!(index.get("a.md").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fnv1a_hash() {
+        assert_eq!(fnv1a_hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(fnv1a_hash(b"note"), fnv1a_hash(b"notes"));
+    }
+}
@@ -4,6 +4,7 @@ use anyhow::{Context, Result, anyhow};
 use chrono::prelude::*;
 use regex::Regex;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::{self, File, create_dir_all};
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
@@ -44,35 +45,6 @@ impl Task {
         }
     }
 
-    /// Convert a task to its string representation in TKF format
-    pub fn to_string(&self) -> String {
-        let mut result = format!("* [ID:{}] {}", self.id, self.title);
-
-        // Add priority if present
-        if let Some(priority) = &self.priority {
-            result.push_str(&format!(" | Priority: {}", priority));
-        }
-
-        // Add tags if present
-        if !self.tags.is_empty() {
-            result.push_str(&format!(" | Tags: {}", self.tags.join(", ")));
-        }
-
-        // Add created date if present
-        if let Some(created) = &self.created {
-            result.push_str(&format!(" | Created: {}", created));
-        }
-
-        // Add any additional metadata
-        for (key, value) in &self.metadata {
-            if key != "Priority" && key != "Tags" && key != "Created" {
-                result.push_str(&format!(" | {}: {}", key, value));
-            }
-        }
-
-        result
-    }
-
     /// Parse a task from a TKF format string
     pub fn from_string(line: &str, column: &str) -> Result<Self> {
         // Extract ID and title
@@ -134,6 +106,37 @@ impl Task {
     }
 }
 
+/// Formats a task as its string representation in TKF format
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "* [ID:{}] {}", self.id, self.title)?;
+
+        // Add priority if present
+        if let Some(priority) = &self.priority {
+            write!(f, " | Priority: {}", priority)?;
+        }
+
+        // Add tags if present
+        if !self.tags.is_empty() {
+            write!(f, " | Tags: {}", self.tags.join(", "))?;
+        }
+
+        // Add created date if present
+        if let Some(created) = &self.created {
+            write!(f, " | Created: {}", created)?;
+        }
+
+        // Add any additional metadata
+        for (key, value) in &self.metadata {
+            if key != "Priority" && key != "Tags" && key != "Created" {
+                write!(f, " | {}: {}", key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Create a new Kanban board with the specified name and columns
 pub fn create_board(
     board_name: &str,
@@ -226,18 +229,18 @@ pub fn read_board(board_name: &str, kanban_directory: &Path) -> Result<KanbanBoa
             continue;
         }
 
-        if let Some(column) = &current_column {
-            if task_regex.is_match(trimmed_line) {
-                match Task::from_string(trimmed_line, column) {
-                    Ok(task) => {
-                        if let Some(tasks) = board.tasks.get_mut(column) {
-                            tasks.push(task);
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Warning: Failed to parse task '{}': {}", trimmed_line, e);
+        if let Some(column) = &current_column
+            && task_regex.is_match(trimmed_line)
+        {
+            match Task::from_string(trimmed_line, column) {
+                Ok(task) => {
+                    if let Some(tasks) = board.tasks.get_mut(column) {
+                        tasks.push(task);
                     }
                 }
+                Err(e) => {
+                    eprintln!("Warning: Failed to parse task '{}': {}", trimmed_line, e);
+                }
             }
         }
     }
@@ -258,7 +261,7 @@ pub fn save_board(board: &KanbanBoard, kanban_directory: &Path) -> Result<PathBu
         content.push_str(&format!("== {} ==\n", column));
         if let Some(tasks) = board.tasks.get(column) {
             for task in tasks {
-                content.push_str(&format!("{}\n", task.to_string()));
+                content.push_str(&format!("{}\n", task));
             }
         }
         content.push('\n');
@@ -294,12 +297,12 @@ pub fn list_boards(kanban_directory: &Path) -> Result<Vec<String>> {
         let entry = entry.context("Failed to read directory entry")?;
         let path = entry.path();
 
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "tkf") {
-            if let Some(filename) = path.file_stem() {
-                if let Some(name) = filename.to_str() {
-                    boards.push(name.to_string());
-                }
-            }
+        if path.is_file()
+            && path.extension().is_some_and(|ext| ext == "tkf")
+            && let Some(filename) = path.file_stem()
+            && let Some(name) = filename.to_str()
+        {
+            boards.push(name.to_string());
         }
     }
 
@@ -355,7 +358,7 @@ pub fn add_task(
 pub fn get_task(board_name: &str, task_id: &str, kanban_directory: &Path) -> Result<Task> {
     let board = read_board(board_name, kanban_directory)?;
 
-    for (_, tasks) in &board.tasks {
+    for tasks in board.tasks.values() {
         for task in tasks {
             if task.id == task_id {
                 return Ok(task.clone());
@@ -477,7 +480,7 @@ pub fn delete_task(board_name: &str, task_id: &str, kanban_directory: &Path) ->
     let mut board = read_board(board_name, kanban_directory)?;
     let mut task_found = false;
 
-    for (_, tasks) in &mut board.tasks {
+    for tasks in board.tasks.values_mut() {
         let task_pos = tasks.iter().position(|t| t.id == task_id);
         if let Some(pos) = task_pos {
             tasks.remove(pos);
@@ -597,15 +600,15 @@ fn sanitize_filename(name: &str) -> String {
 }
 
 /// Generate the next task ID for a board
-fn generate_next_id(board: &KanbanBoard) -> Result<String> {
+pub(crate) fn generate_next_id(board: &KanbanBoard) -> Result<String> {
     let mut max_id = 0;
 
-    for (_, tasks) in &board.tasks {
+    for tasks in board.tasks.values() {
         for task in tasks {
-            if let Ok(id_num) = task.id.parse::<usize>() {
-                if id_num > max_id {
-                    max_id = id_num;
-                }
+            if let Ok(id_num) = task.id.parse::<usize>()
+                && id_num > max_id
+            {
+                max_id = id_num;
             }
         }
     }
@@ -1,9 +1,17 @@
 // pub mod ai;
+pub mod clock;
+pub mod doctor;
+pub mod graph;
+pub mod index;
 pub mod kanban;
+pub mod links;
 pub mod notes;
 pub mod query;
+pub mod query_compiler;
 pub mod query_native;
 pub mod query_parser;
+pub mod saved_queries;
+pub mod search;
 pub mod tags;
 pub mod utils;
 pub mod workspaces;
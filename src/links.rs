@@ -0,0 +1,873 @@
+// src/links.rs
+
+use crate::index::VaultIndex;
+use crate::notes::frontmatter::Frontmatter;
+use crate::notes::utils::{
+    fallback_title, normalize_relative_path, note_file_stem, relative_note_path, sanitize_title,
+    walk_markdown_files,
+};
+use crate::utils::closest_matches;
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use walkdir::WalkDir;
+
+static WIKI_LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(!?)\[\[([^\[\]\n]+)\]\]").unwrap());
+static MARKDOWN_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(!?)\[([^\]\n]*)\]\(\s*(<[^<>\n]*>|[^()\s]+)(?:\s+(?:"[^"\n]*"|'[^'\n]*'))?\s*\)"#,
+    )
+    .unwrap()
+});
+
+/// The syntax a link was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LinkKind {
+    /// `[[Target]]`, `[[Target#Heading|alias]]`
+    WikiLink,
+    /// `[text](path/to/target.md#heading)`
+    MarkdownLink,
+}
+
+/// The part of the target note a link points to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LinkAnchor {
+    /// A heading, from `#Heading`
+    Heading(String),
+    /// A block reference, from `#^block-id`
+    Block(String),
+}
+
+/// A link found in the content of a note.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Link {
+    /// Whether this is a wikilink or a markdown link
+    pub kind: LinkKind,
+    /// True for embeds (`![[Target]]`, `![alt](image.png)`)
+    pub embed: bool,
+    /// The linked note or file, without anchor and URL-decoded. Empty for links to
+    /// an anchor in the same note.
+    pub target: String,
+    /// The heading or block the link points to, if any
+    pub anchor: Option<LinkAnchor>,
+    /// The wikilink alias or the markdown link text
+    pub label: Option<String>,
+    /// Byte range of the whole link in the note content
+    pub span: Range<usize>,
+    /// Byte range of the destination as written, anchor included: the wikilink text
+    /// before the alias, or the markdown link destination without its `<>` brackets
+    pub destination: Range<usize>,
+    /// Line of the link, starting at 1
+    pub line: usize,
+    /// Column of the link in characters, starting at 1
+    pub column: usize,
+}
+
+impl Link {
+    /// Returns true for links with a URL scheme, like `https://` or `mailto:`.
+    pub fn is_external(&self) -> bool {
+        self.kind == LinkKind::MarkdownLink
+            && (self.target.contains("://") || self.target.starts_with("mailto:"))
+    }
+}
+
+/// A link together with the vault note it resolves to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedLink {
+    /// The link as written in the source note
+    pub link: Link,
+    /// Path of the linked note relative to the vault, or `None` if the link is
+    /// external or points to a note that doesn't exist
+    pub path: Option<String>,
+}
+
+/// The outgoing links of a single note.
+#[derive(Debug, Clone)]
+pub struct NoteLinks {
+    /// Path of the note relative to the vault
+    pub path: String,
+    /// Title of the note, falling back to the file name
+    pub title: String,
+    /// Links found in the note, in order of appearance
+    pub links: Vec<Link>,
+}
+
+/// Extracts all wikilinks and markdown links from note content.
+///
+/// Links inside fenced code blocks and inline code spans are ignored.
+///
+/// # Arguments
+/// * `content` - The content of a note
+///
+/// # Returns
+/// * `Vec<Link>` - The links in order of appearance
+///
+/// # Examples
+/// ```
+/// use notemancy_core::links::{LinkAnchor, extract_links};
+///
+/// let links = extract_links("See [[Other Note#Setup|setup]] and `[[not a link]]`.");
+/// assert_eq!(links.len(), 1);
+/// assert_eq!(links[0].target, "Other Note");
+/// assert_eq!(links[0].anchor, Some(LinkAnchor::Heading("Setup".to_string())));
+/// ```
+pub fn extract_links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for (line_number, line_start, line) in unfenced_lines(content, 0) {
+        scan_line(line, line_start, line_number, &mut links);
+    }
+    links
+}
+
+/// Helper function to iterate over the lines of a note from `start` (the start of a
+/// line, usually the start of the body) that are outside of fenced code blocks.
+///
+/// Yields the 1-based line number, the byte offset and the text of each line, with its
+/// line ending. Fence lines themselves are skipped.
+pub(crate) fn unfenced_lines(
+    content: &str,
+    start: usize,
+) -> impl Iterator<Item = (usize, usize, &str)> {
+    let first_line = content[..start].matches('\n').count() + 1;
+    let mut fence: Option<(char, usize)> = None;
+    let mut offset = start;
+    content[start..]
+        .split_inclusive('\n')
+        .enumerate()
+        .filter_map(move |(line_index, line)| {
+            let line_start = offset;
+            offset += line.len();
+            if let Some((marker, length, rest)) = fence_marker(line) {
+                match fence {
+                    None => fence = Some((marker, length)),
+                    Some((open_marker, open_length))
+                        if marker == open_marker
+                            && length >= open_length
+                            && rest.trim().is_empty() =>
+                    {
+                        fence = None
+                    }
+                    Some(_) => {}
+                }
+                return None;
+            }
+            fence
+                .is_none()
+                .then_some((first_line + line_index, line_start, line))
+        })
+}
+
+/// Helper function to detect a code fence line (three or more backticks or tildes).
+///
+/// Returns the fence character, its length and the rest of the line.
+pub(crate) fn fence_marker(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = trimmed.chars().take_while(|c| *c == marker).count();
+    (length >= 3).then(|| (marker, length, &trimmed[length..]))
+}
+
+/// Helper function to find the byte ranges of inline code spans in a line.
+pub(crate) fn code_spans(line: &str) -> Vec<Range<usize>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'`' {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i] == b'`' {
+            i += 1;
+        }
+        let run = i - start;
+
+        // Look for a closing run of the same length.
+        let mut j = i;
+        let mut closed = false;
+        while j < bytes.len() {
+            if bytes[j] == b'`' {
+                let close_start = j;
+                while j < bytes.len() && bytes[j] == b'`' {
+                    j += 1;
+                }
+                if j - close_start == run {
+                    spans.push(start..j);
+                    i = j;
+                    closed = true;
+                    break;
+                }
+            } else {
+                j += 1;
+            }
+        }
+        if !closed {
+            // An unmatched run is literal text.
+            i = start + run;
+        }
+    }
+    spans
+}
+
+/// Helper function to collect the links of a single line outside of code spans.
+fn scan_line(line: &str, line_start: usize, line_number: usize, links: &mut Vec<Link>) {
+    let code = code_spans(line);
+    let overlaps = |range: &Range<usize>, others: &[Range<usize>]| {
+        others
+            .iter()
+            .any(|other| range.start < other.end && other.start < range.end)
+    };
+    let column = |start: usize| line[..start].chars().count() + 1;
+
+    let mut wiki_ranges = Vec::new();
+    let mut line_links = Vec::new();
+    for caps in WIKI_LINK_RE.captures_iter(line) {
+        let whole = caps.get(0).unwrap();
+        if overlaps(&whole.range(), &code) {
+            continue;
+        }
+        wiki_ranges.push(whole.range());
+
+        let inner = caps.get(2).unwrap();
+        let (destination, label) = match inner.as_str().split_once('|') {
+            Some((destination, label)) => (destination, Some(label.trim().to_string())),
+            None => (inner.as_str(), None),
+        };
+        let destination_start =
+            line_start + inner.start() + destination.len() - destination.trim_start().len();
+        let (target, anchor) = split_anchor(destination.trim(), false);
+        line_links.push(Link {
+            kind: LinkKind::WikiLink,
+            embed: !caps[1].is_empty(),
+            target,
+            anchor,
+            label,
+            span: line_start + whole.start()..line_start + whole.end(),
+            destination: destination_start..destination_start + destination.trim().len(),
+            line: line_number,
+            column: column(whole.start()),
+        });
+    }
+
+    for caps in MARKDOWN_LINK_RE.captures_iter(line) {
+        let whole = caps.get(0).unwrap();
+        if overlaps(&whole.range(), &code) || overlaps(&whole.range(), &wiki_ranges) {
+            continue;
+        }
+
+        let written = caps.get(3).unwrap();
+        let destination = written.as_str();
+        let bracketed = destination.starts_with('<');
+        let destination_range = if bracketed {
+            written.start() + 1..written.end() - 1
+        } else {
+            written.range()
+        };
+        let destination = &line[destination_range.clone()];
+        let (target, anchor) = if destination.contains("://") || destination.starts_with("mailto:")
+        {
+            (destination.to_string(), None)
+        } else {
+            split_anchor(destination, true)
+        };
+        let text = caps[2].trim();
+        line_links.push(Link {
+            kind: LinkKind::MarkdownLink,
+            embed: !caps[1].is_empty(),
+            target,
+            anchor,
+            label: (!text.is_empty()).then(|| text.to_string()),
+            span: line_start + whole.start()..line_start + whole.end(),
+            destination: line_start + destination_range.start..line_start + destination_range.end,
+            line: line_number,
+            column: column(whole.start()),
+        });
+    }
+
+    line_links.sort_by_key(|link| link.span.start);
+    links.extend(line_links);
+}
+
+/// Helper function to split `target#anchor` into the target and a typed anchor.
+fn split_anchor(destination: &str, decode: bool) -> (String, Option<LinkAnchor>) {
+    let decode = |s: &str| {
+        if decode {
+            percent_decode(s)
+        } else {
+            s.to_string()
+        }
+    };
+    match destination.split_once('#') {
+        Some((target, anchor)) => {
+            let anchor = decode(anchor.trim());
+            let anchor = match anchor.strip_prefix('^') {
+                Some(block) => LinkAnchor::Block(block.to_string()),
+                None => LinkAnchor::Heading(anchor),
+            };
+            (decode(target.trim()), Some(anchor))
+        }
+        None => (decode(destination), None),
+    }
+}
+
+/// Decodes `%XX` escapes in a URL path, e.g. `My%20Note.md` to `My Note.md`.
+///
+/// Invalid escapes are kept as they are.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = input.get(i + 1..i + 3)
+            && hex.chars().all(|c| c.is_ascii_hexdigit())
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Resolves link targets to notes in a vault.
+///
+/// Path-like targets are looked up relative to the linking note and then relative to
+/// the vault root, with or without extension. Other targets are matched against
+/// note titles, then aliases, then file names (case-insensitively, and also in their
+/// sanitized form). When several notes match, one in the same directory as the
+/// linking note is preferred.
+#[derive(Debug, Clone, Default)]
+pub struct LinkResolver {
+    paths: BTreeSet<String>,
+    paths_without_extension: HashMap<String, String>,
+    titles: HashMap<String, Vec<String>>,
+    aliases: HashMap<String, Vec<String>>,
+    stems: HashMap<String, Vec<String>>,
+}
+
+impl LinkResolver {
+    /// Creates an empty resolver. Add notes with [`LinkResolver::add_note`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a resolver for all notes in a `VaultIndex`.
+    pub fn from_index(index: &VaultIndex) -> Self {
+        let mut resolver = Self::new();
+        for entry in index.entries() {
+            resolver.add_note(&entry.path, &entry.title, &entry.aliases);
+        }
+        resolver
+    }
+
+    /// Adds a note that links can resolve to.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the note relative to the vault
+    /// * `title` - Title of the note
+    /// * `aliases` - Alternative names of the note from its frontmatter
+    pub fn add_note(&mut self, path: &str, title: &str, aliases: &[String]) {
+        self.paths.insert(path.to_string());
+        if let Some((without_extension, _)) = path.rsplit_once('.') {
+            self.paths_without_extension
+                .entry(without_extension.to_string())
+                .or_insert_with(|| path.to_string());
+        }
+        push_name(&mut self.titles, title, path);
+        for alias in aliases {
+            push_name(&mut self.aliases, alias, path);
+        }
+        push_name(&mut self.stems, note_file_stem(path), path);
+    }
+
+    /// Resolves a link found in the note at `source_path`.
+    ///
+    /// # Returns
+    /// * `Option<&str>` - The vault-relative path of the linked note, or `None` for
+    ///   external links and links to notes that don't exist
+    pub fn resolve(&self, source_path: &str, link: &Link) -> Option<&str> {
+        if link.is_external() {
+            return None;
+        }
+        if link.target.is_empty() {
+            // A link to an anchor in the same note.
+            return self.paths.get(source_path).map(String::as_str);
+        }
+
+        let target = link.target.trim();
+        let source_directory = source_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+
+        // Path lookups, for markdown links and path-style wikilinks.
+        if link.kind == LinkKind::MarkdownLink || target.contains('/') {
+            let mut candidates = Vec::new();
+            if let Some(absolute) = target.strip_prefix('/') {
+                candidates.push(normalize_relative_path(absolute));
+            } else {
+                if link.kind == LinkKind::MarkdownLink {
+                    candidates.push(normalize_relative_path(&format!(
+                        "{}/{}",
+                        source_directory, target
+                    )));
+                }
+                candidates.push(normalize_relative_path(target));
+            }
+            for candidate in &candidates {
+                if let Some(path) = self.paths.get(candidate) {
+                    return Some(path);
+                }
+                if let Some(path) = self.paths_without_extension.get(candidate) {
+                    return Some(path);
+                }
+            }
+            if target.contains('/') {
+                return None;
+            }
+        }
+
+        // Name lookups: title, alias, then file name.
+        for names in [&self.titles, &self.aliases, &self.stems] {
+            for key in name_keys(target) {
+                if let Some(paths) = names.get(&key) {
+                    return pick_closest(paths, source_directory);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Helper function to get the keys a link target is looked up by in the names of
+/// notes: the target without markdown extension, lowercased as is and sanitized.
+fn name_keys(target: &str) -> [String; 2] {
+    let name = target
+        .strip_suffix(".md")
+        .or_else(|| target.strip_suffix(".markdown"))
+        .unwrap_or(target);
+    [name.to_lowercase(), sanitize_title(name).to_lowercase()]
+}
+
+/// Returns true if a link target names a note by `name` (its title, an alias or its
+/// file name), compared the way `LinkResolver::resolve` does.
+pub(crate) fn target_names(target: &str, name: &str) -> bool {
+    let key = name.trim().to_lowercase();
+    !key.is_empty() && name_keys(target.trim()).contains(&key)
+}
+
+/// Helper function to register a lookup name for a note.
+fn push_name(names: &mut HashMap<String, Vec<String>>, name: &str, path: &str) {
+    let key = name.trim().to_lowercase();
+    if key.is_empty() {
+        return;
+    }
+    let paths = names.entry(key).or_default();
+    if !paths.iter().any(|p| p == path) {
+        paths.push(path.to_string());
+    }
+}
+
+/// Helper function to pick the match in the source directory, or else the first one.
+fn pick_closest<'a>(paths: &'a [String], source_directory: &str) -> Option<&'a str> {
+    paths
+        .iter()
+        .find(|path| path.rsplit_once('/').map_or("", |(dir, _)| dir) == source_directory)
+        .or_else(|| paths.first())
+        .map(String::as_str)
+}
+
+/// Reads every note in the vault and extracts its links.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<(LinkResolver, Vec<NoteLinks>)>` - A resolver for all notes, and the links
+///   of each note ordered by path
+///
+/// # Errors
+/// * Returns an error if the vault directory can't be walked
+pub fn scan_vault_links(vault_directory: &Path) -> Result<(LinkResolver, Vec<NoteLinks>)> {
+    let mut resolver = LinkResolver::new();
+    let mut notes = Vec::new();
+
+    for path in walk_markdown_files(vault_directory)? {
+        let relative_path = relative_note_path(&path, vault_directory);
+        // Files that are not valid UTF-8 are still link targets, but contain no links.
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let frontmatter = Frontmatter::from_content(&content)
+            .ok()
+            .flatten()
+            .map(|(frontmatter, _)| frontmatter)
+            .unwrap_or_default();
+
+        let title = frontmatter
+            .title
+            .clone()
+            .unwrap_or_else(|| fallback_title(&relative_path));
+        resolver.add_note(&relative_path, &title, &frontmatter.aliases);
+        notes.push(NoteLinks {
+            path: relative_path,
+            title,
+            links: extract_links(&content),
+        });
+    }
+
+    Ok((resolver, notes))
+}
+
+/// Maximum number of replacements suggested for a broken link.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// A note suggested as the target of a broken link.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkSuggestion {
+    /// Path of the note relative to the vault
+    pub path: String,
+    /// Title of the note, from its frontmatter or its file name
+    pub title: String,
+}
+
+/// A link that resolves neither to a note nor to a file of the vault.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BrokenLink {
+    /// Path of the linking note relative to the vault
+    pub path: String,
+    /// 1-based line of the link in the note
+    pub line: usize,
+    /// 1-based column (in characters) of the link in its line
+    pub column: usize,
+    /// The linked note or file as in `Link::target`: without heading, block reference or
+    /// label, and URL-decoded (`My%20Note.md` is reported as `My Note.md`)
+    pub target: String,
+    /// Notes whose title is close to the target, closest first
+    pub suggestions: Vec<LinkSuggestion>,
+}
+
+/// The broken links and orphan notes of a vault, as returned by `check_vault_links`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LinkReport {
+    /// The links that don't resolve, ordered by path and position
+    pub broken_links: Vec<BrokenLink>,
+    /// The (relative path, title) pairs of the notes that neither link to another note
+    /// nor are linked from one, ordered by path
+    pub orphans: Vec<(String, String)>,
+}
+
+/// Finds the links of the vault that don't resolve and the notes that aren't linked.
+///
+/// A link is broken if it isn't external and points neither at a note (by path, title
+/// or alias, see `LinkResolver`) nor at an attachment like an image. Each broken link
+/// comes with up to three notes whose title is close to the target (see
+/// `utils::closest_matches`). A note is an orphan if no link connects it to another
+/// note; links to itself and to attachments don't count.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<LinkReport>` - The broken links and orphan notes
+///
+/// # Errors
+/// * Returns an error if the vault directory can't be walked
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::links::check_vault_links;
+///
+/// let report = check_vault_links(Path::new("/path/to/vault"))?;
+/// for broken in &report.broken_links {
+///     println!("{}:{}:{} [[{}]]", broken.path, broken.line, broken.column, broken.target);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn check_vault_links(vault_directory: &Path) -> Result<LinkReport> {
+    let (resolver, notes) = scan_vault_links(vault_directory)?;
+    let attachments = Attachments::collect(vault_directory);
+
+    let mut titles: Vec<&str> = notes.iter().map(|note| note.title.as_str()).collect();
+    titles.sort_unstable();
+    titles.dedup();
+
+    let mut report = LinkReport::default();
+    let mut linked = BTreeSet::new();
+    for note in &notes {
+        for link in &note.links {
+            if link.is_external() || attachments.contains(&note.path, link) {
+                continue;
+            }
+            match resolver.resolve(&note.path, link) {
+                Some(target) if target != note.path => {
+                    linked.insert(note.path.as_str());
+                    linked.insert(target);
+                }
+                Some(_) => {}
+                None => report.broken_links.push(BrokenLink {
+                    path: note.path.clone(),
+                    line: link.line,
+                    column: link.column,
+                    target: link.target.clone(),
+                    suggestions: suggest_targets(&link.target, &titles, &notes),
+                }),
+            }
+        }
+    }
+
+    report.orphans = notes
+        .iter()
+        .filter(|note| !linked.contains(note.path.as_str()))
+        .map(|note| (note.path.clone(), note.title.clone()))
+        .collect();
+    Ok(report)
+}
+
+/// Helper function to suggest notes for a link target, by the distance of their title
+/// to the name the target ends with.
+fn suggest_targets(target: &str, titles: &[&str], notes: &[NoteLinks]) -> Vec<LinkSuggestion> {
+    let name = target.trim().rsplit('/').next().unwrap_or_default();
+    let name = name
+        .strip_suffix(".md")
+        .or_else(|| name.strip_suffix(".markdown"))
+        .unwrap_or(name);
+    closest_matches(name, titles.iter().copied(), MAX_SUGGESTIONS)
+        .into_iter()
+        .flat_map(|title| notes.iter().filter(move |note| note.title == title))
+        .take(MAX_SUGGESTIONS)
+        .map(|note| LinkSuggestion {
+            path: note.path.clone(),
+            title: note.title.clone(),
+        })
+        .collect()
+}
+
+/// The files of the vault that aren't notes, which links may point at (images, PDFs).
+pub(crate) struct Attachments {
+    paths: HashSet<String>,
+    file_names: HashSet<String>,
+}
+
+impl Attachments {
+    /// Lists the non-markdown files of the vault, outside of hidden directories.
+    pub(crate) fn collect(vault_directory: &Path) -> Self {
+        let mut attachments = Attachments {
+            paths: HashSet::new(),
+            file_names: HashSet::new(),
+        };
+        let walker = WalkDir::new(vault_directory)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+        for entry in walker.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || path
+                    .extension()
+                    .is_some_and(|ext| ext == "md" || ext == "markdown")
+            {
+                continue;
+            }
+            attachments
+                .file_names
+                .insert(entry.file_name().to_string_lossy().to_string());
+            attachments
+                .paths
+                .insert(relative_note_path(path, vault_directory));
+        }
+        attachments
+    }
+
+    /// Returns true if the link points at an existing attachment.
+    pub(crate) fn contains(&self, source_path: &str, link: &Link) -> bool {
+        let target = link.target.trim().trim_start_matches('/');
+        let source_directory = source_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let relative = normalize_relative_path(&format!("{}/{}", source_directory, target));
+        self.paths.contains(target)
+            || self.paths.contains(&relative)
+            || (!target.contains('/') && self.file_names.contains(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_extract_links() {
+        let content = "---\ntitle: Source\n---\n\
+                       See [[Target|alias]], ![[Image.png]] and [[Target#^abc123]].\n\
+                       A [markdown link](../dir/My%20Note.md#Some%20Heading \"title\") and [site](https://example.com).\n\
+                       Inline `[[Not A Link]]` code.\n\
+                       ```\n[[Also Not A Link]]\n```\n\
+                       [[#Local heading]]\n";
+        let links = extract_links(content);
+        assert_eq!(links.len(), 6);
+
+        assert_eq!(links[0].kind, LinkKind::WikiLink);
+        assert_eq!(links[0].target, "Target");
+        assert_eq!(links[0].label.as_deref(), Some("alias"));
+        assert_eq!((links[0].line, links[0].column), (4, 5));
+        assert_eq!(&content[links[0].span.clone()], "[[Target|alias]]");
+
+        assert!(links[1].embed);
+        assert_eq!(links[1].target, "Image.png");
+
+        assert_eq!(
+            links[2].anchor,
+            Some(LinkAnchor::Block("abc123".to_string()))
+        );
+
+        assert_eq!(links[3].kind, LinkKind::MarkdownLink);
+        assert_eq!(links[3].target, "../dir/My Note.md");
+        assert_eq!(
+            links[3].anchor,
+            Some(LinkAnchor::Heading("Some Heading".to_string()))
+        );
+        assert_eq!(links[3].label.as_deref(), Some("markdown link"));
+
+        assert!(links[4].is_external());
+
+        assert_eq!(links[5].target, "");
+        assert_eq!(
+            links[5].anchor,
+            Some(LinkAnchor::Heading("Local heading".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_links() {
+        let mut resolver = LinkResolver::new();
+        resolver.add_note("Home.md", "Home", &[]);
+        resolver.add_note("dir/My Note.md", "My Note", &["Mine".to_string()]);
+        resolver.add_note("dir/Project-Plan.md", "The Plan", &[]);
+        resolver.add_note("other/Project-Plan.md", "Other Plan", &[]);
+
+        let resolve = |source: &str, content: &str| {
+            let link = extract_links(content).remove(0);
+            resolver.resolve(source, &link).map(|p| p.to_string())
+        };
+
+        assert_eq!(
+            resolve("Home.md", "[[my note]]").as_deref(),
+            Some("dir/My Note.md")
+        );
+        assert_eq!(
+            resolve("Home.md", "[[Mine]]").as_deref(),
+            Some("dir/My Note.md")
+        );
+        assert_eq!(
+            resolve("Home.md", "[x](dir/My%20Note.md)").as_deref(),
+            Some("dir/My Note.md")
+        );
+        assert_eq!(
+            resolve("other/Doc.md", "[x](../dir/My%20Note.md)").as_deref(),
+            Some("dir/My Note.md")
+        );
+        assert_eq!(
+            resolve("Home.md", "[[dir/My Note]]").as_deref(),
+            Some("dir/My Note.md")
+        );
+        assert_eq!(
+            resolve("Home.md", "[[The Plan]]").as_deref(),
+            Some("dir/Project-Plan.md")
+        );
+        // Ambiguous file names prefer the linking note's directory
+        assert_eq!(
+            resolve("other/Doc.md", "[[Project Plan]]").as_deref(),
+            Some("other/Project-Plan.md")
+        );
+        assert_eq!(
+            resolve("Home.md", "[[#Heading]]").as_deref(),
+            Some("Home.md")
+        );
+        assert_eq!(resolve("Home.md", "[[Missing]]"), None);
+        assert_eq!(resolve("Home.md", "[x](https://example.com/Home.md)"), None);
+    }
+
+    #[test]
+    fn test_scan_vault_links() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("A.md"),
+            "---\ntitle: Note A\naliases: [First]\n---\n\nLinks to [[B]].",
+        )?;
+        fs::write(vault_dir.join("B.md"), "Back to [[First]].")?;
+
+        let (resolver, notes) = scan_vault_links(vault_dir)?;
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].title, "Note A");
+        assert_eq!(notes[1].title, "B");
+        assert_eq!(resolver.resolve("A.md", &notes[0].links[0]), Some("B.md"));
+        assert_eq!(resolver.resolve("B.md", &notes[1].links[0]), Some("A.md"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_vault_links() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::create_dir_all(vault_dir.join("img"))?;
+        fs::write(vault_dir.join("img/diagram.png"), "png")?;
+        fs::write(
+            vault_dir.join("Source.md"),
+            "---\ntitle: Source\n---\nSee [[Meeting Notez]], [[Target]] and ![[diagram.png]].\n\
+             Also [gone](archive/Old%20Plan.md) and [site](https://example.com).\n",
+        )?;
+        fs::write(
+            vault_dir.join("Target.md"),
+            "---\ntitle: Target\n---\nBack to [[Source]].\n",
+        )?;
+        fs::write(
+            vault_dir.join("Meeting-Notes.md"),
+            "---\ntitle: Meeting Notes\n---\nOnly [[Meeting Notes|myself]].\n",
+        )?;
+        fs::write(
+            vault_dir.join("Old-Plans.md"),
+            "---\ntitle: Old Plans\n---\n",
+        )?;
+
+        let report = check_vault_links(vault_dir)?;
+        assert_eq!(report.broken_links.len(), 2);
+
+        let misspelled = &report.broken_links[0];
+        assert_eq!(misspelled.path, "Source.md");
+        assert_eq!((misspelled.line, misspelled.column), (4, 5));
+        assert_eq!(misspelled.target, "Meeting Notez");
+        assert_eq!(
+            misspelled.suggestions,
+            vec![LinkSuggestion {
+                path: "Meeting-Notes.md".to_string(),
+                title: "Meeting Notes".to_string(),
+            }]
+        );
+
+        let moved = &report.broken_links[1];
+        assert_eq!(
+            (moved.line, moved.target.as_str()),
+            (5, "archive/Old Plan.md")
+        );
+        assert_eq!(moved.suggestions[0].title, "Old Plans");
+
+        assert_eq!(
+            report.orphans,
+            vec![
+                ("Meeting-Notes.md".to_string(), "Meeting Notes".to_string()),
+                ("Old-Plans.md".to_string(), "Old Plans".to_string()),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,15 @@
 // src/notes/crud.rs
 
-use crate::notes::utils::{check_unique_title, sanitize_title};
+use crate::links::{Link, LinkKind, LinkResolver, percent_decode, scan_vault_links, target_names};
+use crate::notes::frontmatter::{DATE_FORMAT, Frontmatter, TIMESTAMP_FORMAT};
+use crate::notes::utils::{
+    check_unique_title, get_title, normalize_relative_path, note_file_stem, relative_note_path,
+    relative_path_between, sanitize_title,
+};
 use anyhow::{Context, Result, anyhow};
 use chrono::prelude::*;
+use serde_yaml::Value;
+use std::collections::HashSet;
 use std::fs::{self, create_dir_all};
 use std::path::{Path, PathBuf};
 
@@ -48,14 +55,12 @@ pub fn create_note(title: &str, vault_directory: &Path, project: &str) -> Result
 
     // Get the current date and time for frontmatter
     let now = Local::now();
-    let date_str = now.format("%Y-%m-%d").to_string();
-    let timestamp_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+    let date_str = now.format(DATE_FORMAT).to_string();
+    let timestamp_str = now.format(TIMESTAMP_FORMAT).to_string();
 
     // Create the frontmatter content
-    let content = format!(
-        "---\ntitle: {}\ncreated_on: {}\nmodified_at: {}\ntype:\ncontext:\ntags:\n---\n\n",
-        title, date_str, timestamp_str
-    );
+    let frontmatter = Frontmatter::new(title, &date_str, &timestamp_str);
+    let content = frontmatter.render("\n")?;
 
     // Write the content to the file
     fs::write(&note_path, content).context("Failed to write note file")?;
@@ -69,7 +74,7 @@ pub fn create_note(title: &str, vault_directory: &Path, project: &str) -> Result
 /// * `relative_path` - The relative path to the note within the vault directory
 /// * `vault_directory` - The base directory of the vault
 /// * `frontmatter` - Whether to include frontmatter in the returned content (true)
-///                   or strip it (false)
+///   or strip it (false)
 ///
 /// # Returns
 /// * `Result<String>` - The content of the note, with or without frontmatter
@@ -111,7 +116,7 @@ pub fn read_note(relative_path: &str, vault_directory: &Path, frontmatter: bool)
 ///
 /// Frontmatter is expected to start and end with "---" on its own line.
 /// If no frontmatter is detected, the original content is returned unchanged.
-fn strip_frontmatter(content: &str) -> Result<String> {
+pub(crate) fn strip_frontmatter(content: &str) -> Result<String> {
     // Check if the content starts with frontmatter delimiter
     if content.trim_start().starts_with("---") {
         // Find the end of the frontmatter (second occurrence of "---")
@@ -125,14 +130,14 @@ fn strip_frontmatter(content: &str) -> Result<String> {
             // Find the next newline after the second "---"
             if let Some(newline_pos) = content[end_pos..].find('\n') {
                 let content_start = end_pos + newline_pos + 1;
-                return Ok(content[content_start..].trim_start().to_string());
+                Ok(content[content_start..].trim_start().to_string())
             } else {
                 // No content after frontmatter
-                return Ok(String::new());
+                Ok(String::new())
             }
         } else {
             // Could not find second delimiter, return original content
-            return Ok(content.to_string());
+            Ok(content.to_string())
         }
     } else {
         // No frontmatter, return the original content
@@ -171,12 +176,9 @@ pub fn update_note(title: &str, vault_directory: &Path, updated_content: &str) -
     let current_content = fs::read_to_string(&file_path)
         .context(format!("Failed to read note file: {}", file_path))?;
 
-    // Extract the frontmatter if it exists
-    let frontmatter = extract_frontmatter(&current_content)?;
-
-    // Combine frontmatter with updated content
-    let new_content = if let Some(frontmatter) = frontmatter {
-        format!("{}\n\n{}", frontmatter, updated_content.trim())
+    // Combine the existing frontmatter with updated content
+    let new_content = if let Some((yaml, _)) = Frontmatter::split(&current_content) {
+        format!("---\n{}---\n\n{}", yaml, updated_content.trim())
     } else {
         // If no frontmatter exists, use the updated content as is
         updated_content.to_string()
@@ -251,10 +253,25 @@ fn extract_frontmatter(content: &str) -> Result<Option<String>> {
 }
 
 /// Helper function to update the modification timestamp in frontmatter.
-fn update_modification_timestamp(content: &str) -> Result<String> {
+///
+/// Frontmatter that can't be parsed as YAML (e.g. notes written by older versions with
+/// an unquoted `title: a: b`) is patched line by line instead of being rewritten.
+pub(crate) fn update_modification_timestamp(content: &str) -> Result<String> {
     let now = chrono::Local::now();
-    let timestamp_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
 
+    match Frontmatter::from_content(content) {
+        Ok(Some((mut frontmatter, body))) => {
+            frontmatter.touch(now.naive_local());
+            frontmatter.render(body)
+        }
+        // No frontmatter, return original content
+        Ok(None) => Ok(content.to_string()),
+        Err(_) => patch_modification_timestamp(content, &now.format(TIMESTAMP_FORMAT).to_string()),
+    }
+}
+
+/// Helper function to set `modified_at` in frontmatter that isn't valid YAML.
+fn patch_modification_timestamp(content: &str, timestamp_str: &str) -> Result<String> {
     // Check if content has frontmatter
     if let Some(frontmatter) = extract_frontmatter(content)? {
         // Check if frontmatter contains a modified_at field
@@ -265,17 +282,141 @@ fn update_modification_timestamp(content: &str) -> Result<String> {
             let updated = re.replace(content, &format!("modified_at: {}\n", timestamp_str));
             return Ok(updated.to_string());
         } else {
-            // Add modified_at field at the end of frontmatter
-            let before_second_delimiter = content.rfind("---").unwrap();
-            let (start, end) = content.split_at(before_second_delimiter);
+            // Add modified_at field before the closing delimiter of the frontmatter
+            let start_pos = content.find("---").unwrap_or(0);
+            let closing = start_pos + frontmatter.len() - 3;
+            let (start, end) = content.split_at(closing);
             return Ok(format!("{}modified_at: {}\n{}", start, timestamp_str, end));
         }
     }
 
-    // No frontmatter or no modified_at field, return original content
+    // No frontmatter, return original content
     Ok(content.to_string())
 }
 
+/// Reads the frontmatter of a note.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Frontmatter>` - The parsed frontmatter, empty if the note has none
+///
+/// # Errors
+/// * Returns an error if the note can't be read
+/// * Returns an error if the frontmatter is not valid YAML
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::notes::crud::read_frontmatter;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let frontmatter = read_frontmatter("notes/my-note.md", vault_dir);
+/// ```
+pub fn read_frontmatter(relative_path: &str, vault_directory: &Path) -> Result<Frontmatter> {
+    let file_path = vault_directory.join(relative_path);
+    let content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+
+    let frontmatter = Frontmatter::from_content(&content)
+        .context(format!("Invalid frontmatter in {}", file_path.display()))?
+        .map(|(frontmatter, _)| frontmatter)
+        .unwrap_or_default();
+    Ok(frontmatter)
+}
+
+/// Gets a single frontmatter field of a note.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `key` - The frontmatter key to read
+///
+/// # Returns
+/// * `Result<Option<Value>>` - The value of the field, or None if it is missing or empty
+///
+/// # Errors
+/// * Returns an error if the note can't be read or its frontmatter is not valid YAML
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::notes::crud::get_frontmatter_field;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let status = get_frontmatter_field("notes/my-note.md", vault_dir, "status");
+/// ```
+pub fn get_frontmatter_field(
+    relative_path: &str,
+    vault_directory: &Path,
+    key: &str,
+) -> Result<Option<Value>> {
+    Ok(read_frontmatter(relative_path, vault_directory)?.get(key))
+}
+
+/// Sets a single frontmatter field of a note, keeping all other fields as they are.
+///
+/// The modification timestamp is updated as well. If the note has no frontmatter,
+/// one is added. Setting `Value::Null` clears the field.
+///
+/// # Arguments
+/// * `title` - The title of the note to update
+/// * `vault_directory` - The base directory of the vault
+/// * `key` - The frontmatter key to set
+/// * `value` - The new value
+///
+/// # Returns
+/// * `Result<()>` - Ok if the field was successfully written
+///
+/// # Errors
+/// * Returns an error if the note is not found or can't be read or written
+/// * Returns an error if the frontmatter is not valid YAML
+/// * Returns an error if the value doesn't fit a known field (e.g. a list as `title`)
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::notes::crud::set_frontmatter_field;
+/// use serde_yaml::Value;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let result = set_frontmatter_field("My Note", vault_dir, "status", Value::from("done"));
+/// ```
+pub fn set_frontmatter_field(
+    title: &str,
+    vault_directory: &Path,
+    key: &str,
+    value: Value,
+) -> Result<()> {
+    // Get the file path for the note
+    let file_path = crate::notes::utils::get_file_path(title, vault_directory)?;
+
+    // Read the current content of the file
+    let current_content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path))?;
+
+    let (mut frontmatter, body) = match Frontmatter::from_content(&current_content)
+        .context(format!("Invalid frontmatter in {}", file_path))?
+    {
+        Some((frontmatter, body)) => (frontmatter, body.to_string()),
+        None => (Frontmatter::default(), format!("\n{}", current_content)),
+    };
+
+    frontmatter.set(key, value)?;
+    if key != "modified_at" {
+        frontmatter.touch(Local::now().naive_local());
+    }
+
+    fs::write(&file_path, frontmatter.render(&body)?).context(format!(
+        "Failed to write updated content to file: {}",
+        file_path
+    ))?;
+
+    Ok(())
+}
+
 /// Appends content to an existing markdown note.
 ///
 /// # Arguments
@@ -322,9 +463,531 @@ pub fn append_to_note(title: &str, vault_directory: &Path, content: &str) -> Res
     Ok(())
 }
 
+/// A file whose links were rewritten by `move_note` or `rename_note`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewrittenFile {
+    /// Vault-relative path of the file (after the move, for the moved note itself)
+    pub path: String,
+    /// Number of links that were rewritten in the file
+    pub links: usize,
+}
+
+/// Summary of a `move_note` or `rename_note` operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveReport {
+    /// Vault-relative path of the note before the operation
+    pub old_path: String,
+    /// Vault-relative path of the note after the operation
+    pub new_path: String,
+    /// Every file in which links were rewritten, sorted by path
+    pub rewritten: Vec<RewrittenFile>,
+}
+
+/// Renames a note, updating its `title` frontmatter, its file name and every link to it.
+///
+/// The note stays in its directory and its file is renamed to the sanitized new title.
+/// Markdown links and wikilinks in the whole vault that point at the note are rewritten,
+/// keeping their style (title, file name, vault-relative or relative path), anchors and
+/// aliases. Links are found and resolved as for `get_backlinks`, so links in code blocks
+/// and code spans, and links that resolve to another note, are left alone.
+///
+/// # Arguments
+/// * `title` - The current title of the note
+/// * `new_title` - The new title of the note
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<MoveReport>` - The old and new paths of the note and the files that were rewritten
+///
+/// # Errors
+/// * Returns an error if the note is not found
+/// * Returns an error if another note already uses the new title
+/// * Returns an error if the note's frontmatter is not valid YAML
+/// * Returns an error if there is an issue reading or writing files
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::notes::crud::rename_note;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let result = rename_note("My Note", "My Renamed Note", vault_dir);
+/// ```
+pub fn rename_note(title: &str, new_title: &str, vault_directory: &Path) -> Result<MoveReport> {
+    relocate_note(title, Some(new_title), None, vault_directory)
+}
+
+/// Moves a note to another project directory and rewrites every link to it.
+///
+/// The file name and title are kept. Path-based links to the note are rewritten, and
+/// relative links inside the moved note are adjusted to its new location.
+///
+/// # Arguments
+/// * `title` - The title of the note to move
+/// * `project` - The sub-path within the vault where the note should be moved
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<MoveReport>` - The old and new paths of the note and the files that were rewritten
+///
+/// # Errors
+/// * Returns an error if the note is not found
+/// * Returns an error if a file with the same name already exists in the target project
+/// * Returns an error if there is an issue reading, writing or creating files
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::notes::crud::move_note;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let result = move_note("My Note", "archive/2024", vault_dir);
+/// ```
+pub fn move_note(title: &str, project: &str, vault_directory: &Path) -> Result<MoveReport> {
+    relocate_note(title, None, Some(project), vault_directory)
+}
+
+/// Renames the file of a note to the sanitized form of its title, rewriting every link to it.
+///
+/// This brings back in line a note whose title was edited by hand without renaming the
+/// file. The note stays in its directory, and links are rewritten as for `rename_note`.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<MoveReport>` - The old and new paths of the note and the files that were rewritten
+///
+/// # Errors
+/// * Returns an error if the note doesn't exist
+/// * Returns an error if another note already uses the file name of the title
+/// * Returns an error if there is an issue reading or writing files
+pub fn sync_file_name(relative_path: &str, vault_directory: &Path) -> Result<MoveReport> {
+    let file_path = vault_directory.join(relative_path);
+    if !file_path.is_file() {
+        return Err(anyhow!("Note '{}' does not exist", relative_path));
+    }
+    relocate_file(file_path, None, None, true, vault_directory)
+}
+
+/// Old and new location of a note that is being moved or renamed.
+struct Relocation {
+    old_path: String,
+    new_path: String,
+    old_title: String,
+    new_title: String,
+    /// Aliases of the note, which links may also name it by
+    aliases: Vec<String>,
+}
+
+/// Helper function implementing `rename_note` and `move_note`.
+///
+/// All new file contents are computed before anything is written, so parse errors
+/// leave the vault untouched.
+fn relocate_note(
+    title: &str,
+    new_title: Option<&str>,
+    project: Option<&str>,
+    vault_directory: &Path,
+) -> Result<MoveReport> {
+    let file_path = PathBuf::from(crate::notes::utils::get_file_path(title, vault_directory)?);
+    relocate_file(file_path, new_title, project, false, vault_directory)
+}
+
+/// Helper function to move or rename the note file at `file_path`. With `match_title`,
+/// the file is renamed to the sanitized title even if the title doesn't change.
+fn relocate_file(
+    file_path: PathBuf,
+    new_title: Option<&str>,
+    project: Option<&str>,
+    match_title: bool,
+    vault_directory: &Path,
+) -> Result<MoveReport> {
+    let old_path = relative_note_path(&file_path, vault_directory);
+    let old_title = get_title(&file_path)?;
+    let new_title = new_title.unwrap_or(&old_title).to_string();
+
+    // Work out the new location of the note.
+    let old_directory = old_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let new_directory = match project {
+        Some(project) => normalize_relative_path(project),
+        None => old_directory.to_string(),
+    };
+    let extension = file_path
+        .extension()
+        .map_or("md".to_string(), |ext| ext.to_string_lossy().to_string());
+    let old_stem = note_file_stem(&old_path).to_string();
+    let new_stem = if new_title == old_title && !match_title {
+        old_stem.clone()
+    } else {
+        sanitize_title(&new_title)
+    };
+    let new_path = if new_directory.is_empty() {
+        format!("{}.{}", new_stem, extension)
+    } else {
+        format!("{}/{}.{}", new_directory, new_stem, extension)
+    };
+
+    if new_path == old_path && new_title == old_title {
+        return Ok(MoveReport {
+            old_path,
+            new_path,
+            rewritten: Vec::new(),
+        });
+    }
+    // The note itself may already match the new file name once sanitized.
+    if new_stem != old_stem
+        && sanitize_title(&old_stem) != new_stem
+        && !check_unique_title(&new_title, vault_directory)?
+    {
+        return Err(anyhow!(
+            "A note with the title '{}' already exists",
+            new_title
+        ));
+    }
+    if new_path != old_path && vault_directory.join(&new_path).exists() {
+        return Err(anyhow!("A file already exists at '{}'", new_path));
+    }
+
+    let content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+    let aliases = Frontmatter::from_content(&content)
+        .ok()
+        .flatten()
+        .map(|(frontmatter, _)| frontmatter.aliases)
+        .unwrap_or_default();
+    let relocation = Relocation {
+        old_path,
+        new_path,
+        old_title,
+        new_title,
+        aliases,
+    };
+
+    // Compute the new content of every affected file. Links are found and resolved like
+    // backlinks are, so links in code and links to other notes are left alone.
+    let (resolver, notes) = scan_vault_links(vault_directory)?;
+    let mut writes: Vec<(PathBuf, String)> = Vec::new();
+    let mut rewritten = Vec::new();
+    for note in notes {
+        let path = vault_directory.join(&note.path);
+        if note.path == relocation.old_path {
+            let content = fs::read_to_string(&path)
+                .context(format!("Failed to read note file: {}", path.display()))?;
+            let (new_content, links) = rewrite_moved_note(
+                &content,
+                &note.links,
+                &relocation,
+                &resolver,
+                vault_directory,
+            )?;
+            if links > 0 {
+                rewritten.push(RewrittenFile {
+                    path: relocation.new_path.clone(),
+                    links,
+                });
+            }
+            writes.push((vault_directory.join(&relocation.new_path), new_content));
+            continue;
+        }
+
+        let points_at_note =
+            |link: &Link| resolver.resolve(&note.path, link) == Some(relocation.old_path.as_str());
+        if !note.links.iter().any(points_at_note) {
+            continue;
+        }
+        // Files that can't be read were scanned without links.
+        let content = fs::read_to_string(&path)
+            .context(format!("Failed to read note file: {}", path.display()))?;
+        let (new_content, links) = rewrite_links(
+            &content,
+            &note.links,
+            &note.path,
+            &note.path,
+            &relocation,
+            &resolver,
+            None,
+        )?;
+        if links > 0 {
+            rewritten.push(RewrittenFile {
+                path: note.path,
+                links,
+            });
+            writes.push((path, new_content));
+        }
+    }
+
+    // Write everything, then remove the old file.
+    create_dir_all(vault_directory.join(&new_directory))
+        .context("Failed to create project directory")?;
+    for (path, content) in writes {
+        fs::write(&path, content).context(format!("Failed to write file: {}", path.display()))?;
+    }
+    if relocation.new_path != relocation.old_path {
+        fs::remove_file(&file_path).context(format!(
+            "Failed to remove old note file: {}",
+            file_path.display()
+        ))?;
+    }
+
+    rewritten.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(MoveReport {
+        old_path: relocation.old_path,
+        new_path: relocation.new_path,
+        rewritten,
+    })
+}
+
+/// Helper function to compute the new content of the note being moved: its frontmatter
+/// title is updated and its own links are rewritten for the new location.
+fn rewrite_moved_note(
+    content: &str,
+    links: &[Link],
+    relocation: &Relocation,
+    resolver: &LinkResolver,
+    vault_directory: &Path,
+) -> Result<(String, usize)> {
+    let (new_content, links) = rewrite_links(
+        content,
+        links,
+        &relocation.old_path,
+        &relocation.new_path,
+        relocation,
+        resolver,
+        Some(vault_directory),
+    )?;
+    if relocation.new_title == relocation.old_title {
+        return Ok((new_content, links));
+    }
+
+    match Frontmatter::from_content(&new_content)
+        .context(format!("Invalid frontmatter in {}", relocation.old_path))?
+    {
+        Some((mut frontmatter, body)) => {
+            frontmatter.title = Some(relocation.new_title.clone());
+            frontmatter.touch(Local::now().naive_local());
+            Ok((frontmatter.render(body)?, links))
+        }
+        // Without frontmatter the title comes from the file name, which is renamed.
+        None => Ok((new_content, links)),
+    }
+}
+
+/// Helper function to rewrite the links in `content` that point at a relocated note.
+///
+/// `links` are the links of `content` as found by `extract_links`; only their spans are
+/// rewritten, and only if `resolver` resolves them to the relocated note. `source_path`
+/// is the location of the file containing the links, and `new_source_path` its location
+/// after the move (they only differ for the moved note itself). When `vault_directory`
+/// is given, relative links to other existing files are also adjusted to the new
+/// source location.
+///
+/// Returns the new content and the number of rewritten links.
+///
+/// # Errors
+/// * Returns an error if a link resolves to the relocated note but can't be rewritten
+fn rewrite_links(
+    content: &str,
+    links: &[Link],
+    source_path: &str,
+    new_source_path: &str,
+    relocation: &Relocation,
+    resolver: &LinkResolver,
+    vault_directory: Option<&Path>,
+) -> Result<(String, usize)> {
+    let source_directory = source_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let new_source_directory = new_source_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let mut new_content = String::with_capacity(content.len());
+    let mut copied = 0;
+    let mut count = 0;
+
+    for link in links {
+        let points_at_note =
+            resolver.resolve(source_path, link) == Some(relocation.old_path.as_str());
+        let destination = &content[link.destination.clone()];
+        let new_destination = match link.kind {
+            LinkKind::WikiLink if points_at_note => {
+                let (target, anchor) = destination
+                    .find('#')
+                    .map_or((destination, ""), |pos| destination.split_at(pos));
+                rewrite_wiki_target(target, relocation)
+                    .with_context(|| format!("Failed to rewrite a link in {}", source_path))?
+                    .map(|new_target| format!("{}{}", new_target, anchor))
+            }
+            LinkKind::WikiLink => None,
+            LinkKind::MarkdownLink => rewrite_markdown_target(
+                destination,
+                points_at_note,
+                source_directory,
+                new_source_directory,
+                relocation,
+                vault_directory,
+            )
+            .filter(|new_target| new_target != destination)
+            .map(|new_target| {
+                // A destination with spaces needs brackets, which an existing pair keeps.
+                let bracketed = content[..link.destination.start].ends_with('<');
+                if !bracketed && new_target.contains(char::is_whitespace) {
+                    format!("<{}>", new_target)
+                } else {
+                    new_target
+                }
+            }),
+        };
+
+        if let Some(new_destination) = new_destination {
+            new_content.push_str(&content[copied..link.destination.start]);
+            new_content.push_str(&new_destination);
+            copied = link.destination.end;
+            count += 1;
+        }
+    }
+    new_content.push_str(&content[copied..]);
+
+    Ok((new_content, count))
+}
+
+/// Helper function to compute the new target of a markdown link, if it changes.
+///
+/// `points_at_note` tells whether the link resolves to the relocated note; other links
+/// are only adjusted when they are relative links inside the moved note.
+fn rewrite_markdown_target(
+    target: &str,
+    points_at_note: bool,
+    source_directory: &str,
+    new_source_directory: &str,
+    relocation: &Relocation,
+    vault_directory: Option<&Path>,
+) -> Option<String> {
+    if target.contains("://") || target.starts_with("mailto:") || target.starts_with('#') {
+        return None;
+    }
+    let (path_part, anchor) = match target.find('#') {
+        Some(pos) => target.split_at(pos),
+        None => (target, ""),
+    };
+    let decoded = percent_decode(path_part);
+    // Characters written as escapes in the original target stay escaped.
+    let upper = path_part.to_ascii_uppercase();
+    let escape = |c: char| {
+        let mut buffer = [0; 4];
+        c.encode_utf8(&mut buffer)
+            .bytes()
+            .map(|byte| format!("%{:02X}", byte))
+            .collect::<String>()
+    };
+    let escaped: HashSet<char> = decoded
+        .chars()
+        .filter(|c| upper.contains(&escape(*c)))
+        .collect();
+    let encode = |path: String| {
+        path.chars()
+            .map(|c| {
+                if escaped.contains(&c) {
+                    escape(c)
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect::<String>()
+    };
+
+    let absolute = decoded.starts_with('/');
+    let from_source = normalize_relative_path(&format!("{}/{}", source_directory, decoded));
+    let from_root = normalize_relative_path(&decoded);
+
+    let new_link = if !points_at_note {
+        match vault_directory {
+            // A relative link inside the moved note to another existing file.
+            Some(vault_directory)
+                if !absolute
+                    && source_directory != new_source_directory
+                    && vault_directory.join(&from_source).is_file() =>
+            {
+                relative_path_between(new_source_directory, &from_source)
+            }
+            _ => return None,
+        }
+    } else if absolute {
+        format!("/{}", relocation.new_path)
+    } else if from_source == relocation.old_path {
+        relative_path_between(new_source_directory, &relocation.new_path)
+    } else if from_root == relocation.old_path {
+        relocation.new_path.clone()
+    } else if !decoded.contains('/') && !decoded.contains('.') {
+        // A bare file name without extension.
+        note_file_stem(&relocation.new_path).to_string()
+    } else {
+        // Any other spelling that resolved to the note, like a path without extension.
+        relative_path_between(new_source_directory, &relocation.new_path)
+    };
+
+    Some(format!("{}{}", encode(new_link), anchor))
+}
+
+/// Helper function to compute the new target of a wikilink (without anchor or alias)
+/// that resolves to the relocated note, or `None` if the link still resolves as written.
+///
+/// # Errors
+/// * Returns an error if the target names the note in a way that can't be rewritten
+fn rewrite_wiki_target(target: &str, relocation: &Relocation) -> Result<Option<String>> {
+    let trimmed = target.trim();
+    let old_stem = note_file_stem(&relocation.old_path);
+    let new_stem = note_file_stem(&relocation.new_path);
+    let cant_rewrite = || {
+        anyhow!(
+            "Can't rewrite the wikilink to '{}' for the note moved to {}",
+            trimmed,
+            relocation.new_path
+        )
+    };
+
+    let new_target = if trimmed.contains('/') {
+        // Path-style wikilink, with or without extension.
+        let path = normalize_relative_path(trimmed);
+        let old_without_ext = relocation
+            .old_path
+            .rsplit_once('.')
+            .map_or(relocation.old_path.as_str(), |(p, _)| p);
+        if path == relocation.old_path {
+            relocation.new_path.clone()
+        } else if path == old_without_ext {
+            relocation
+                .new_path
+                .rsplit_once('.')
+                .map_or(relocation.new_path.clone(), |(p, _)| p.to_string())
+        } else {
+            return Err(cant_rewrite());
+        }
+    } else if target_names(trimmed, &relocation.old_title) {
+        if relocation.new_title == relocation.old_title {
+            return Ok(None);
+        }
+        relocation.new_title.clone()
+    } else if target_names(trimmed, old_stem) {
+        if new_stem == old_stem {
+            return Ok(None);
+        }
+        new_stem.to_string()
+    } else if relocation
+        .aliases
+        .iter()
+        .any(|alias| target_names(trimmed, alias))
+    {
+        // Aliases move with the note.
+        return Ok(None);
+    } else {
+        return Err(cant_rewrite());
+    };
+
+    Ok((new_target != trimmed).then_some(new_target))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::links::check_vault_links;
     use tempfile::tempdir;
 
     #[test]
@@ -385,8 +1048,10 @@ mod tests {
 
         // Verify the content uses the original title
         let content = fs::read_to_string(&note_path)?;
-        assert!(
-            content.contains("title: Test Note: With <invalid> chars?"),
+        let (frontmatter, _) = Frontmatter::from_content(&content)?.unwrap();
+        assert_eq!(
+            frontmatter.title.as_deref(),
+            Some(title),
             "Frontmatter should contain original title"
         );
 
@@ -666,6 +1331,63 @@ End of note.
         Ok(())
     }
 
+    #[test]
+    fn test_update_modification_timestamp_keeps_fields() -> Result<()> {
+        let content = "---\npriority: high\ntitle: Test Note\nmodified_at: 2023-01-01 12:00:00\ntags:\n- a\n---\n\nContent.";
+        let updated = update_modification_timestamp(content)?;
+        assert!(updated.starts_with("---\npriority: high\ntitle: Test Note\nmodified_at: "));
+        assert!(updated.ends_with("tags:\n- a\n---\n\nContent."));
+        assert!(!updated.contains("2023-01-01 12:00:00"));
+
+        // Frontmatter that is not valid YAML is still patched
+        let legacy = "---\ntitle: Old: Note\nmodified_at: 2023-01-01 12:00:00\n---\n\nContent.";
+        let updated = update_modification_timestamp(legacy)?;
+        assert!(updated.contains("title: Old: Note\nmodified_at: "));
+        assert!(!updated.contains("2023-01-01 12:00:00"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_and_set_frontmatter_field() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        let title = "Field Test Note";
+        let note_path = create_note(title, vault_dir, "test")?;
+        let relative_path = "test/Field-Test-Note.md";
+
+        assert_eq!(
+            get_frontmatter_field(relative_path, vault_dir, "status")?,
+            None
+        );
+
+        set_frontmatter_field(title, vault_dir, "status", Value::from("draft"))?;
+        set_frontmatter_field(
+            title,
+            vault_dir,
+            "tags",
+            Value::Sequence(vec![Value::from("rust")]),
+        )?;
+
+        assert_eq!(
+            get_frontmatter_field(relative_path, vault_dir, "status")?,
+            Some(Value::from("draft"))
+        );
+        let frontmatter = read_frontmatter(relative_path, vault_dir)?;
+        assert_eq!(frontmatter.title.as_deref(), Some(title));
+        assert_eq!(frontmatter.tags, vec!["rust"]);
+
+        // Key order from create_note is kept and the new field goes last
+        let content = fs::read_to_string(&note_path)?;
+        let type_pos = content.find("type:").unwrap();
+        let tags_pos = content.find("tags:").unwrap();
+        let status_pos = content.find("status: draft").unwrap();
+        assert!(type_pos < tags_pos && tags_pos < status_pos);
+
+        Ok(())
+    }
+
     #[test]
     fn test_update_note() -> Result<()> {
         // Create a temporary directory for the test vault
@@ -843,4 +1565,146 @@ End of note.
 
         Ok(())
     }
+
+    #[test]
+    fn test_rename_note_rewrites_links() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        let note_path = create_note("Old Name", vault_dir, "notes")?;
+        fs::create_dir_all(vault_dir.join("other"))?;
+        fs::write(
+            vault_dir.join("other/Linker.md"),
+            "---\ntitle: Linker\n---\n\nSee [[Old Name]], [[Old-Name#Intro|intro]], \
+             [rel](../notes/Old-Name.md#intro), [root](/notes/Old-Name.md) and ![[notes/Old-Name]].\n\
+             Unrelated: [[Old Name Two]] and [site](https://example.com/Old-Name.md).\n",
+        )?;
+        fs::write(vault_dir.join("Untouched.md"), "No links here.\n")?;
+
+        let report = rename_note("Old Name", "New: Name", vault_dir)?;
+        assert_eq!(report.old_path, "notes/Old-Name.md");
+        assert_eq!(report.new_path, "notes/New-Name.md");
+        assert_eq!(
+            report.rewritten,
+            vec![RewrittenFile {
+                path: "other/Linker.md".to_string(),
+                links: 5,
+            }]
+        );
+
+        assert!(!note_path.exists());
+        let renamed = fs::read_to_string(vault_dir.join("notes/New-Name.md"))?;
+        let (frontmatter, _) = Frontmatter::from_content(&renamed)?.unwrap();
+        assert_eq!(frontmatter.title.as_deref(), Some("New: Name"));
+
+        let linker = fs::read_to_string(vault_dir.join("other/Linker.md"))?;
+        assert!(linker.contains("See [[New: Name]], [[New-Name#Intro|intro]]"));
+        assert!(linker.contains("[rel](../notes/New-Name.md#intro)"));
+        assert!(linker.contains("[root](/notes/New-Name.md)"));
+        assert!(linker.contains("![[notes/New-Name]]"));
+        assert!(linker.contains("[[Old Name Two]]"));
+        assert!(linker.contains("https://example.com/Old-Name.md"));
+
+        // The new title can be found again
+        assert_eq!(
+            crate::notes::utils::get_file_path("New: Name", vault_dir)?,
+            vault_dir.join("notes/New-Name.md").to_string_lossy()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_note_skips_code_and_other_notes() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        create_note("Old", vault_dir, "notes")?;
+        fs::create_dir_all(vault_dir.join("other"))?;
+        fs::write(
+            vault_dir.join("other/Old.md"),
+            "---\ntitle: Different\n---\nA different note with the same file name.\n",
+        )?;
+        let linker = "See [[Old]] and [x](../notes/Old.md).\n\n```\nexample [[Old]] syntax\n```\n\nInline `[[Old]]` and [y](Old) stay.\n";
+        fs::write(vault_dir.join("other/Linker.md"), linker)?;
+
+        let report = rename_note("Old", "New", vault_dir)?;
+        assert_eq!(report.rewritten[0].links, 2);
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("other/Linker.md"))?,
+            linker
+                .replacen("[[Old]]", "[[New]]", 1)
+                .replace("../notes/Old.md", "../notes/New.md")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_note_keeps_link_spelling() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        fs::write(
+            vault_dir.join("Éclair Notes.md"),
+            "---\ntitle: Éclair Notes\n---\n## Some Heading\n",
+        )?;
+        let linker = "[[éclair notes]], [w](<Éclair Notes.md>), [a](<Éclair Notes.md#Some Heading>) and [e](%C3%89clair%20Notes.md#some-heading).\n";
+        fs::write(vault_dir.join("Linker.md"), linker)?;
+
+        let report = rename_note("Éclair Notes", "Éclair Plans", vault_dir)?;
+        assert_eq!(report.new_path, "Éclair-Plans.md");
+        assert_eq!(report.rewritten[0].links, 4);
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("Linker.md"))?,
+            "[[Éclair Plans]], [w](<Éclair-Plans.md>), [a](<Éclair-Plans.md#Some Heading>) and [e](%C3%89clair-Plans.md#some-heading).\n"
+        );
+        assert!(check_vault_links(vault_dir)?.broken_links.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_note_adjusts_relative_links() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        create_note("Mover", vault_dir, "a")?;
+        append_to_note(
+            "Mover",
+            vault_dir,
+            "Links to [sibling](Sibling.md) and [[Sibling]].",
+        )?;
+        fs::write(
+            vault_dir.join("a/Sibling.md"),
+            "Back to [mover](Mover.md) and [[Mover]].",
+        )?;
+
+        let report = move_note("Mover", "b/c", vault_dir)?;
+        assert_eq!(report.new_path, "b/c/Mover.md");
+        assert_eq!(
+            report.rewritten,
+            vec![
+                RewrittenFile {
+                    path: "a/Sibling.md".to_string(),
+                    links: 1,
+                },
+                RewrittenFile {
+                    path: "b/c/Mover.md".to_string(),
+                    links: 1,
+                },
+            ]
+        );
+
+        let moved = fs::read_to_string(vault_dir.join("b/c/Mover.md"))?;
+        assert!(moved.contains("[sibling](../../a/Sibling.md) and [[Sibling]]"));
+        let sibling = fs::read_to_string(vault_dir.join("a/Sibling.md"))?;
+        assert_eq!(sibling, "Back to [mover](../b/c/Mover.md) and [[Mover]].");
+
+        // Moving onto an existing file fails without changing anything
+        create_note("Blocker", vault_dir, "x")?;
+        fs::write(vault_dir.join("b/c/Blocker.md"), "---\ntitle: Other\n---\n")?;
+        assert!(move_note("Blocker", "b/c", vault_dir).is_err());
+        assert!(vault_dir.join("x/Blocker.md").exists());
+
+        Ok(())
+    }
 }
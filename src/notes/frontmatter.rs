@@ -0,0 +1,586 @@
+// src/notes/frontmatter.rs
+
+use anyhow::{Context, Result, anyhow};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::{Mapping, Value};
+
+/// Format used for the `created_on` field.
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Format used for the `modified_at` field.
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// The frontmatter keys that have a typed field on [`Frontmatter`], in the order
+/// `create_note` writes them.
+const KNOWN_KEYS: [&str; 7] = [
+    "title",
+    "created_on",
+    "modified_at",
+    "type",
+    "context",
+    "tags",
+    "aliases",
+];
+
+/// The YAML frontmatter of a note.
+///
+/// The fields written by `create_note` are available as typed fields, and every other
+/// key is kept in `extra`. A frontmatter parsed with [`Frontmatter::from_yaml`] remembers
+/// its original mapping and text, so serializing it again keeps the original key order
+/// and writes unchanged keys back exactly as they were written, comments included.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Frontmatter {
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    pub created_on: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    pub modified_at: Option<String>,
+    #[serde(rename = "type", default, deserialize_with = "deserialize_scalar")]
+    pub note_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_scalar")]
+    pub context: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_list")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_list")]
+    pub aliases: Vec<String>,
+    /// All keys without a typed field, in their original order
+    #[serde(flatten)]
+    pub extra: Mapping,
+    /// The mapping this frontmatter was parsed from, used to keep order and formatting
+    #[serde(skip)]
+    source: Mapping,
+    /// The YAML text this frontmatter was parsed from, split by top-level key
+    #[serde(skip)]
+    source_text: Option<SourceText>,
+}
+
+/// The text of parsed frontmatter, split into one piece per top-level key.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct SourceText {
+    /// Comments and blank lines before the first key
+    preamble: String,
+    /// Each key with its lines, from the key line up to the next key
+    entries: Vec<(Value, String)>,
+}
+
+impl Frontmatter {
+    /// Creates the frontmatter for a new note with the given title and timestamps.
+    ///
+    /// The `type`, `context` and `tags` keys are included with empty values so that
+    /// they show up in the note ready to be filled in.
+    pub fn new(title: &str, created_on: &str, modified_at: &str) -> Self {
+        let mut frontmatter = Frontmatter {
+            title: Some(title.to_string()),
+            created_on: Some(created_on.to_string()),
+            modified_at: Some(modified_at.to_string()),
+            ..Default::default()
+        };
+        for key in [
+            "title",
+            "created_on",
+            "modified_at",
+            "type",
+            "context",
+            "tags",
+        ] {
+            let value = frontmatter.get(key).unwrap_or(Value::Null);
+            frontmatter
+                .source
+                .insert(Value::String(key.to_string()), value);
+        }
+        frontmatter
+    }
+
+    /// Parses frontmatter from YAML text (without the `---` delimiters).
+    ///
+    /// # Errors
+    /// * Returns an error if the text is not valid YAML or is not a mapping
+    /// * Returns an error if a known field has an unsupported type (e.g. a mapping as title)
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let value: Value =
+            serde_yaml::from_str(yaml).context("Failed to parse frontmatter YAML")?;
+        let mut frontmatter = Self::from_value(value)?;
+        frontmatter.source_text = SourceText::split(yaml).filter(|text| {
+            text.entries.len() == frontmatter.source.len()
+                && text
+                    .entries
+                    .iter()
+                    .zip(frontmatter.source.keys())
+                    .all(|((key, _), source_key)| key == source_key)
+        });
+        Ok(frontmatter)
+    }
+
+    /// Builds frontmatter from an already parsed YAML value.
+    ///
+    /// # Errors
+    /// * Returns an error if the value is not a mapping (`null` is treated as empty)
+    /// * Returns an error if a known field has an unsupported type
+    pub fn from_value(value: Value) -> Result<Self> {
+        let mapping = match value {
+            Value::Mapping(mapping) => mapping,
+            Value::Null => Mapping::new(),
+            other => {
+                return Err(anyhow!("Frontmatter must be a mapping, found {:?}", other));
+            }
+        };
+        let mut frontmatter: Frontmatter = serde_yaml::from_value(Value::Mapping(mapping.clone()))
+            .context("Failed to read frontmatter fields")?;
+        frontmatter.source = mapping;
+        Ok(frontmatter)
+    }
+
+    /// Splits note content into its frontmatter and body.
+    ///
+    /// The frontmatter is the YAML text between an opening `---` line (optionally
+    /// preceded by blank lines) and the next line that consists solely of `---`.
+    /// Returns `(yaml, body)` where `body` is everything after the closing delimiter
+    /// line, or `None` if the content has no complete frontmatter block.
+    pub fn split(content: &str) -> Option<(&str, &str)> {
+        let start = content.len() - content.trim_start().len();
+        let rest = &content[start..];
+        let first_line_end = rest.find('\n')?;
+        if rest[..first_line_end].trim() != "---" {
+            return None;
+        }
+
+        let yaml_start = start + first_line_end + 1;
+        let mut offset = yaml_start;
+        for line in content[yaml_start..].split_inclusive('\n') {
+            if line.trim() == "---" {
+                let yaml = &content[yaml_start..offset];
+                let body = &content[offset + line.len()..];
+                return Some((yaml, body));
+            }
+            offset += line.len();
+        }
+        None
+    }
+
+    /// Parses the frontmatter of note content.
+    ///
+    /// # Returns
+    /// * `Result<Option<(Frontmatter, &str)>>` - The frontmatter and the body after it,
+    ///   or `None` if the content has no frontmatter
+    ///
+    /// # Errors
+    /// * Returns an error if the frontmatter block exists but can't be parsed
+    pub fn from_content(content: &str) -> Result<Option<(Self, &str)>> {
+        match Self::split(content) {
+            Some((yaml, body)) => Ok(Some((Self::from_yaml(yaml)?, body))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the current value of a field, whether it is a typed field or an extra key.
+    ///
+    /// Empty typed fields (`None` or an empty list) are returned as `None`.
+    pub fn get(&self, key: &str) -> Option<Value> {
+        match key {
+            "title" => self.title.clone().map(Value::String),
+            "created_on" => self.created_on.clone().map(Value::String),
+            "modified_at" => self.modified_at.clone().map(Value::String),
+            "type" => self.note_type.clone().map(Value::String),
+            "context" => self.context.clone().map(Value::String),
+            "tags" => list_value(&self.tags),
+            "aliases" => list_value(&self.aliases),
+            _ => self.extra.get(key).cloned(),
+        }
+    }
+
+    /// Sets a field. Known keys are converted into their typed field; setting
+    /// `Value::Null` clears the field.
+    ///
+    /// # Errors
+    /// * Returns an error if the value can't be converted into a known field's type
+    pub fn set(&mut self, key: &str, value: Value) -> Result<()> {
+        match key {
+            "title" => self.title = scalar_from_value(key, value)?,
+            "created_on" => self.created_on = scalar_from_value(key, value)?,
+            "modified_at" => self.modified_at = scalar_from_value(key, value)?,
+            "type" => self.note_type = scalar_from_value(key, value)?,
+            "context" => self.context = scalar_from_value(key, value)?,
+            "tags" => self.tags = list_from_value(key, value)?,
+            "aliases" => self.aliases = list_from_value(key, value)?,
+            _ => {
+                self.extra.insert(Value::String(key.to_string()), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a field. Known fields are cleared, extra keys are dropped.
+    ///
+    /// Returns the previous value, if any.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let previous = self.get(key);
+        if KNOWN_KEYS.contains(&key) {
+            // Clearing can't fail for a null value.
+            let _ = self.set(key, Value::Null);
+        } else {
+            self.extra.remove(key);
+        }
+        self.source.remove(key);
+        previous
+    }
+
+    /// Sets `modified_at` to the given time.
+    pub fn touch(&mut self, now: NaiveDateTime) {
+        self.modified_at = Some(now.format(TIMESTAMP_FORMAT).to_string());
+    }
+
+    /// Parses `created_on` as a date, accepting both plain dates and timestamps.
+    pub fn created_date(&self) -> Option<NaiveDate> {
+        self.created_on.as_deref().and_then(parse_date)
+    }
+
+    /// Parses `modified_at` as a timestamp, accepting plain dates as midnight.
+    pub fn modified_datetime(&self) -> Option<NaiveDateTime> {
+        let value = self.modified_at.as_deref()?;
+        NaiveDateTime::parse_from_str(value.trim(), TIMESTAMP_FORMAT)
+            .ok()
+            .or_else(|| parse_date(value).and_then(|d| d.and_hms_opt(0, 0, 0)))
+    }
+
+    /// Converts the frontmatter into an ordered YAML mapping.
+    ///
+    /// Keys from the parsed source come first, in their original order, and keep their
+    /// original value if the field was not changed. New keys are appended after them:
+    /// typed fields in the order `create_note` uses, then extra keys.
+    pub fn to_mapping(&self) -> Mapping {
+        let mut mapping = Mapping::new();
+
+        for (key, source_value) in &self.source {
+            match key.as_str() {
+                Some(name) if KNOWN_KEYS.contains(&name) => {
+                    let value = if self.known_matches_source(name, source_value) {
+                        source_value.clone()
+                    } else {
+                        self.get(name).unwrap_or(Value::Null)
+                    };
+                    mapping.insert(key.clone(), value);
+                }
+                _ => {
+                    // Extra keys removed from `extra` are dropped.
+                    if let Some(value) = self.extra.get(key) {
+                        mapping.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        for name in KNOWN_KEYS {
+            if !self.source.contains_key(name)
+                && let Some(value) = self.get(name)
+            {
+                mapping.insert(Value::String(name.to_string()), value);
+            }
+        }
+        for (key, value) in &self.extra {
+            if !self.source.contains_key(key) {
+                mapping.insert(key.clone(), value.clone());
+            }
+        }
+
+        mapping
+    }
+
+    /// Serializes the frontmatter to YAML text (without the `---` delimiters).
+    ///
+    /// Keys that are unchanged since parsing are written back as they were in the
+    /// source text, with their comments and formatting; changed and new keys are
+    /// serialized. Empty values are written as a bare `key:` line, matching the notes
+    /// created by `create_note`.
+    ///
+    /// # Errors
+    /// * Returns an error if a value can't be serialized
+    pub fn to_yaml(&self) -> Result<String> {
+        let mut yaml = String::new();
+        if let Some(source_text) = &self.source_text {
+            yaml.push_str(&source_text.preamble);
+        }
+        for (key, value) in self.to_mapping() {
+            if self.source.get(&key) == Some(&value)
+                && let Some(text) = self.source_text.as_ref().and_then(|t| t.entry(&key))
+            {
+                yaml.push_str(text);
+                continue;
+            }
+
+            let is_null = value.is_null();
+            let mut entry = Mapping::new();
+            entry.insert(key, value);
+            let text = serde_yaml::to_string(&entry).context("Failed to serialize frontmatter")?;
+            match text.strip_suffix(" null\n") {
+                Some(bare) if is_null => {
+                    yaml.push_str(bare);
+                    yaml.push('\n');
+                }
+                _ => yaml.push_str(&text),
+            }
+        }
+        Ok(yaml)
+    }
+
+    /// Renders a complete note with this frontmatter followed by the given body.
+    ///
+    /// # Errors
+    /// * Returns an error if the frontmatter can't be serialized
+    pub fn render(&self, body: &str) -> Result<String> {
+        Ok(format!("---\n{}---\n{}", self.to_yaml()?, body))
+    }
+
+    /// Helper function to check whether a typed field still holds the value it was parsed from.
+    fn known_matches_source(&self, name: &str, source_value: &Value) -> bool {
+        match name {
+            "tags" => lenient_list(source_value).as_ref() == Some(&self.tags),
+            "aliases" => lenient_list(source_value).as_ref() == Some(&self.aliases),
+            _ => {
+                let current = match name {
+                    "title" => &self.title,
+                    "created_on" => &self.created_on,
+                    "modified_at" => &self.modified_at,
+                    "type" => &self.note_type,
+                    _ => &self.context,
+                };
+                lenient_scalar(source_value).as_ref() == Some(current)
+            }
+        }
+    }
+}
+
+impl SourceText {
+    /// Helper function to split YAML text into its top-level entries.
+    ///
+    /// Returns `None` if an entry can't be parsed on its own (for example a flow
+    /// collection whose lines continue at the start of a line).
+    fn split(yaml: &str) -> Option<Self> {
+        let mut source_text = SourceText::default();
+        let mut current: Option<String> = None;
+        for line in yaml.split_inclusive('\n') {
+            // Top-level sequence items (`- item`) and comments continue the current entry.
+            if line.starts_with(|c: char| !c.is_whitespace() && c != '#' && c != '-') {
+                if let Some(text) = current.replace(line.to_string()) {
+                    source_text.entries.push(parse_entry(text)?);
+                }
+            } else {
+                match current.as_mut() {
+                    Some(text) => text.push_str(line),
+                    None => source_text.preamble.push_str(line),
+                }
+            }
+        }
+        if let Some(text) = current {
+            source_text.entries.push(parse_entry(text)?);
+        }
+        Some(source_text)
+    }
+
+    /// Helper function to get the source text of a key.
+    fn entry(&self, key: &Value) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// Helper function to parse the text of one top-level entry into its key.
+fn parse_entry(mut text: String) -> Option<(Value, String)> {
+    let mapping: Mapping = serde_yaml::from_str(&text).ok()?;
+    let key = match mapping.len() {
+        1 => mapping.into_iter().next()?.0,
+        _ => return None,
+    };
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    Some((key, text))
+}
+
+impl Serialize for Frontmatter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_mapping().serialize(serializer)
+    }
+}
+
+/// Parses a date in `YYYY-MM-DD` form, also accepting a timestamp and keeping only its date.
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, TIMESTAMP_FORMAT)
+                .ok()
+                .map(|dt| dt.date())
+        })
+}
+
+/// Helper function to convert a list field into a YAML value, or `None` if it is empty.
+fn list_value(items: &[String]) -> Option<Value> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(Value::Sequence(
+            items.iter().cloned().map(Value::String).collect(),
+        ))
+    }
+}
+
+/// Helper function to read a scalar as a string. Returns `Some(None)` for null and
+/// `None` for values that are not scalars.
+fn lenient_scalar(value: &Value) -> Option<Option<String>> {
+    match value {
+        Value::Null => Some(None),
+        Value::String(s) => Some(Some(s.clone())),
+        Value::Bool(b) => Some(Some(b.to_string())),
+        Value::Number(n) => Some(Some(n.to_string())),
+        _ => None,
+    }
+}
+
+/// Helper function to read a list of strings. A single scalar is treated as a
+/// one-element list and null as an empty list. Returns `None` for other values.
+fn lenient_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Sequence(seq) => seq
+            .iter()
+            .map(|item| lenient_scalar(item).map(|s| s.unwrap_or_default()))
+            .filter(|item| item.as_ref().is_none_or(|s| !s.is_empty()))
+            .collect(),
+        other => lenient_scalar(other).map(|s| s.into_iter().collect()),
+    }
+}
+
+fn scalar_from_value(key: &str, value: Value) -> Result<Option<String>> {
+    lenient_scalar(&value).ok_or_else(|| anyhow!("Frontmatter field '{}' must be a scalar", key))
+}
+
+fn list_from_value(key: &str, value: Value) -> Result<Vec<String>> {
+    lenient_list(&value)
+        .ok_or_else(|| anyhow!("Frontmatter field '{}' must be a list of strings", key))
+}
+
+fn deserialize_scalar<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    lenient_scalar(&value).ok_or_else(|| serde::de::Error::custom("expected a scalar value"))
+}
+
+fn deserialize_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let value = Value::deserialize(deserializer)?;
+    lenient_list(&value).ok_or_else(|| serde::de::Error::custom("expected a list of strings"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_and_extra_fields() -> Result<()> {
+        let yaml = "title: My Note\ncreated_on: 2023-05-15\npriority: high\ntype:\ntags:\n  - rust\n  - cli\naliases: Other Name\n";
+        let frontmatter = Frontmatter::from_yaml(yaml)?;
+
+        assert_eq!(frontmatter.title.as_deref(), Some("My Note"));
+        assert_eq!(frontmatter.created_on.as_deref(), Some("2023-05-15"));
+        assert_eq!(frontmatter.note_type, None);
+        assert_eq!(frontmatter.tags, vec!["rust", "cli"]);
+        assert_eq!(frontmatter.aliases, vec!["Other Name"]);
+        assert_eq!(
+            frontmatter.get("priority"),
+            Some(Value::String("high".to_string()))
+        );
+        assert_eq!(
+            frontmatter.created_date(),
+            NaiveDate::from_ymd_opt(2023, 5, 15)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_keeps_order_and_unknown_fields() -> Result<()> {
+        let yaml = "priority: high\ntitle: My Note\nnested:\n  key: value\ntype:\naliases: Other Name\ntags:\n- rust\n";
+        let frontmatter = Frontmatter::from_yaml(yaml)?;
+        assert_eq!(frontmatter.to_yaml()?, yaml);
+
+        // Changing one field leaves the others untouched and in place
+        let mut frontmatter = frontmatter;
+        frontmatter.title = Some("Renamed".to_string());
+        frontmatter.tags.push("cli".to_string());
+        frontmatter.set("status", Value::String("draft".to_string()))?;
+        assert_eq!(
+            frontmatter.to_yaml()?,
+            "priority: high\ntitle: Renamed\nnested:\n  key: value\ntype:\naliases: Other Name\ntags:\n- rust\n- cli\nstatus: draft\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_keeps_formatting_and_comments() -> Result<()> {
+        let yaml = "# Written by hand\ntitle: My Note # the title\ntags: [a, b] # topic\naliases:\n  - Pee\n  - Other # second\nmodified_at: '2020-01-01 00:00:00'\nnested: {key: value}\n";
+        let mut frontmatter = Frontmatter::from_yaml(yaml)?;
+        assert_eq!(frontmatter.to_yaml()?, yaml);
+
+        // Only the changed key is serialized again
+        frontmatter.touch(
+            NaiveDate::from_ymd_opt(2025, 3, 1)
+                .unwrap()
+                .and_hms_opt(9, 30, 0)
+                .unwrap(),
+        );
+        frontmatter.remove("nested");
+        assert_eq!(
+            frontmatter.to_yaml()?,
+            "# Written by hand\ntitle: My Note # the title\ntags: [a, b] # topic\naliases:\n  - Pee\n  - Other # second\nmodified_at: 2025-03-01 09:30:00\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_frontmatter() -> Result<()> {
+        let frontmatter = Frontmatter::new("Test: Note", "2023-01-01", "2023-01-01 12:00:00");
+        let yaml = frontmatter.to_yaml()?;
+        assert_eq!(
+            yaml,
+            "title: 'Test: Note'\ncreated_on: 2023-01-01\nmodified_at: 2023-01-01 12:00:00\ntype:\ncontext:\ntags:\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_and_render() -> Result<()> {
+        let content = "---\ntitle: Test\n---\n\nBody with --- inside\n---\n";
+        let (yaml, body) = Frontmatter::split(content).unwrap();
+        assert_eq!(yaml, "title: Test\n");
+        assert_eq!(body, "\nBody with --- inside\n---\n");
+
+        let (frontmatter, body) = Frontmatter::from_content(content)?.unwrap();
+        assert_eq!(frontmatter.render(body)?, content);
+
+        assert!(Frontmatter::split("No frontmatter").is_none());
+        assert!(Frontmatter::split("---\ntitle: Incomplete\n").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_get_and_remove() -> Result<()> {
+        let mut frontmatter = Frontmatter::from_yaml("title: A\ncustom: 1\n")?;
+        frontmatter.set("tags", Value::String("solo".to_string()))?;
+        assert_eq!(frontmatter.tags, vec!["solo"]);
+        assert!(
+            frontmatter
+                .set("title", Value::Sequence(Vec::new()))
+                .is_err()
+        );
+
+        assert_eq!(frontmatter.remove("custom"), Some(Value::Number(1.into())));
+        assert_eq!(frontmatter.to_yaml()?, "title: A\ntags:\n- solo\n");
+        Ok(())
+    }
+}
@@ -1,2 +1,5 @@
 pub mod crud;
+pub mod frontmatter;
+pub mod sections;
+pub mod transclusion;
 pub mod utils;
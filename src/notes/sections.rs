@@ -0,0 +1,520 @@
+// src/notes/sections.rs
+
+use crate::links::unfenced_lines;
+use crate::notes::crud::update_modification_timestamp;
+use crate::notes::frontmatter::Frontmatter;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// A heading of a note with the content under it, as returned by `get_outline`.
+///
+/// A section runs from its heading up to the next heading of the same or a higher
+/// level, so it includes its subsections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section {
+    /// Heading level, 1 for `#` to 6 for `######`
+    pub level: usize,
+    /// Heading text without the `#` markers
+    pub title: String,
+    /// 1-based line of the heading
+    pub line: usize,
+    /// 1-based last line of the section
+    pub end_line: usize,
+    /// Byte range of the section in the note, frontmatter included
+    pub range: Range<usize>,
+    /// The headings directly below this one
+    pub children: Vec<Section>,
+}
+
+/// Parses the heading tree of note content.
+///
+/// Only ATX headings (`## Heading`) outside of code blocks and frontmatter count.
+/// A heading that skips a level (a `###` right under a `#`) is nested under the
+/// closest heading above it with a lower level.
+///
+/// # Arguments
+/// * `content` - The content of the note, with or without frontmatter
+///
+/// # Returns
+/// * `Vec<Section>` - The top-level sections with their subsections
+///
+/// # Examples
+/// ```
+/// use notemancy_core::notes::sections::parse_outline;
+///
+/// let outline = parse_outline("# Project\n## Goals\nShip it.\n## Decisions\n");
+/// assert_eq!(outline[0].children[1].title, "Decisions");
+/// ```
+pub fn parse_outline(content: &str) -> Vec<Section> {
+    let body_start = Frontmatter::split(content).map_or(0, |(_, body)| content.len() - body.len());
+
+    let mut headings = Vec::new();
+    for (line, offset, text) in unfenced_lines(content, body_start) {
+        if let Some((level, title)) = heading(text) {
+            headings.push(Section {
+                level,
+                title: title.to_string(),
+                line,
+                end_line: line,
+                range: offset..content.len(),
+                children: Vec::new(),
+            });
+        }
+    }
+
+    for i in 0..headings.len() {
+        let level = headings[i].level;
+        if let Some(next) = headings[i + 1..].iter().find(|h| h.level <= level) {
+            headings[i].range.end = next.range.start;
+        }
+        let section = &content[headings[i].range.clone()];
+        headings[i].end_line =
+            headings[i].line + section.matches('\n').count() - usize::from(section.ends_with('\n'));
+    }
+
+    nest(&mut headings.into_iter().peekable(), 0)
+}
+
+/// Reads the heading tree of a note.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Vec<Section>>` - The top-level sections with their subsections
+///
+/// # Errors
+/// * Returns an error if the note can't be read
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::sections::get_outline;
+///
+/// for section in get_outline("Project.md", Path::new("/path/to/vault"))? {
+///     println!("{} (lines {}-{})", section.title, section.line, section.end_line);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn get_outline(relative_path: &str, vault_directory: &Path) -> Result<Vec<Section>> {
+    let file_path = vault_directory.join(relative_path);
+    let content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+    Ok(parse_outline(&content))
+}
+
+/// Reads the content under a heading of a note.
+///
+/// The heading is given with its level (`## Decisions`) to only match headings of that
+/// level, or as plain text (`Decisions`) to match any level. Headings are compared
+/// case-insensitively, and the first match wins.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `heading` - The heading of the section
+///
+/// # Returns
+/// * `Result<String>` - The content of the section without the heading line and
+///   surrounding blank lines, subsections included
+///
+/// # Errors
+/// * Returns an error if the note can't be read
+/// * Returns an error if the note has no such heading
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::sections::read_section;
+///
+/// let decisions = read_section("Project.md", Path::new("/path/to/vault"), "## Decisions")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn read_section(relative_path: &str, vault_directory: &Path, heading: &str) -> Result<String> {
+    let file_path = vault_directory.join(relative_path);
+    let content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+
+    let outline = parse_outline(&content);
+    let section = find_section(&outline, heading)
+        .ok_or_else(|| anyhow!("Heading '{}' not found in {}", heading, file_path.display()))?;
+    Ok(trim_blank_lines(&content[section_body(&content, section)]).to_string())
+}
+
+/// Replaces the content under a heading of a note, keeping the heading, the blank
+/// lines around the content and the rest of the note as they are.
+///
+/// Subsections are part of the content and are replaced as well. The modification
+/// timestamp is updated like `update_note` does.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `heading` - The heading of the section (see `read_section`)
+/// * `content` - The new content of the section, without the heading line
+///
+/// # Returns
+/// * `Result<()>` - Ok if the section was successfully replaced
+///
+/// # Errors
+/// * Returns an error if the note can't be read or written
+/// * Returns an error if the note has no such heading
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::sections::replace_section;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// replace_section("Project.md", vault_dir, "## Status", "On track.")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn replace_section(
+    relative_path: &str,
+    vault_directory: &Path,
+    heading: &str,
+    content: &str,
+) -> Result<()> {
+    edit_section(
+        relative_path,
+        vault_directory,
+        heading,
+        section_body,
+        |_| content.to_string(),
+    )
+}
+
+/// Appends content to the text of a section of a note, before its first subsection.
+///
+/// The modification timestamp is updated like `append_to_note` does.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `heading` - The heading of the section (see `read_section`)
+/// * `content` - The content to append, separated from the section by a blank line
+///
+/// # Returns
+/// * `Result<()>` - Ok if the content was successfully appended
+///
+/// # Errors
+/// * Returns an error if the note can't be read or written
+/// * Returns an error if the note has no such heading
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::sections::append_to_section;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// append_to_section("Project.md", vault_dir, "## Decisions", "- Use SQLite")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn append_to_section(
+    relative_path: &str,
+    vault_directory: &Path,
+    heading: &str,
+    content: &str,
+) -> Result<()> {
+    edit_section(relative_path, vault_directory, heading, own_body, |body| {
+        join_blocks(body, content)
+    })
+}
+
+/// Inserts content right under a heading of a note, before the existing content of
+/// the section.
+///
+/// If the note has no such heading, the heading is added at the end of the note
+/// (as `##` if it is given without a level). The modification timestamp is updated
+/// like `update_note` does.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `heading` - The heading of the section (see `read_section`)
+/// * `content` - The content to insert, separated from the section by a blank line
+///
+/// # Returns
+/// * `Result<()>` - Ok if the content was successfully inserted
+///
+/// # Errors
+/// * Returns an error if the note can't be read or written
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::sections::insert_under_heading;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// insert_under_heading("Journal.md", vault_dir, "## Log", "- 09:00 Standup")?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn insert_under_heading(
+    relative_path: &str,
+    vault_directory: &Path,
+    heading: &str,
+    content: &str,
+) -> Result<()> {
+    let file_path = vault_directory.join(relative_path);
+    let current_content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+
+    if find_section(&parse_outline(&current_content), heading).is_some() {
+        return edit_section(relative_path, vault_directory, heading, own_body, |body| {
+            join_blocks(content, body)
+        });
+    }
+
+    let (level, text) = parse_selector(heading);
+    let new_content = format!(
+        "{}\n\n{} {}\n\n{}\n",
+        current_content.trim_end(),
+        "#".repeat(level.unwrap_or(2)),
+        text,
+        trim_blank_lines(content)
+    );
+    let new_content = update_modification_timestamp(new_content.trim_start())?;
+
+    fs::write(&file_path, new_content).context(format!(
+        "Failed to write updated content to file: {}",
+        file_path.display()
+    ))?;
+
+    Ok(())
+}
+
+/// Helper function to rewrite the content under a heading of a note.
+///
+/// `body` picks the part of the section to edit (`section_body` or `own_body`), and
+/// `edit` gets its text without surrounding blank lines. Only that text is replaced:
+/// the heading line, the blank lines around the text and the rest of the note are kept
+/// byte for byte, apart from the modification timestamp.
+fn edit_section(
+    relative_path: &str,
+    vault_directory: &Path,
+    heading: &str,
+    body: fn(&str, &Section) -> Range<usize>,
+    edit: impl FnOnce(&str) -> String,
+) -> Result<()> {
+    let file_path = vault_directory.join(relative_path);
+    let current_content = fs::read_to_string(&file_path)
+        .context(format!("Failed to read note file: {}", file_path.display()))?;
+
+    let outline = parse_outline(&current_content);
+    let section = find_section(&outline, heading)
+        .ok_or_else(|| anyhow!("Heading '{}' not found in {}", heading, file_path.display()))?;
+    let body = body(&current_content, section);
+    let text = trim_blank_lines(&current_content[body.clone()]);
+    let replacement = edit(text);
+    let replacement = trim_blank_lines(&replacement);
+
+    let new_content = if text.is_empty() {
+        // Nothing to replace: the content goes right under the heading line.
+        let mut new_content = current_content[..body.start].to_string();
+        if !replacement.is_empty() {
+            if !new_content.ends_with('\n') {
+                new_content.push('\n');
+            }
+            new_content.push_str(replacement);
+            new_content.push('\n');
+            if body.end < current_content.len() {
+                new_content.push('\n');
+            }
+        }
+        new_content + &current_content[body.start..]
+    } else {
+        let start = text.as_ptr() as usize - current_content.as_ptr() as usize;
+        let mut end = start + text.len();
+        if replacement.is_empty() {
+            // Drop the line of the text as well, not only its content.
+            let start = start - (start - body.start).min(1);
+            end += usize::from(current_content[end..].starts_with('\n'));
+            format!("{}{}", &current_content[..start], &current_content[end..])
+        } else {
+            format!(
+                "{}{}{}",
+                &current_content[..start],
+                replacement,
+                &current_content[end..]
+            )
+        }
+    };
+
+    let new_content = update_modification_timestamp(&new_content)?;
+
+    fs::write(&file_path, new_content).context(format!(
+        "Failed to write updated content to file: {}",
+        file_path.display()
+    ))?;
+
+    Ok(())
+}
+
+/// Helper function to find a section by heading, with an optional level, in document
+/// order.
+pub(crate) fn find_section<'a>(outline: &'a [Section], heading: &str) -> Option<&'a Section> {
+    let (level, text) = parse_selector(heading);
+    find_heading(outline, level, text)
+}
+
+/// Helper function to search the heading tree depth first.
+fn find_heading<'a>(
+    sections: &'a [Section],
+    level: Option<usize>,
+    text: &str,
+) -> Option<&'a Section> {
+    sections.iter().find_map(|section| {
+        if level.is_none_or(|level| section.level == level)
+            && section.title.eq_ignore_ascii_case(text)
+        {
+            Some(section)
+        } else {
+            find_heading(&section.children, level, text)
+        }
+    })
+}
+
+/// Helper function to split a heading like `## Decisions` into its level and text.
+fn parse_selector(heading_selector: &str) -> (Option<usize>, &str) {
+    match heading(heading_selector.trim()) {
+        Some((level, text)) => (Some(level), text),
+        None => (None, heading_selector.trim()),
+    }
+}
+
+/// Helper function to get the byte range of a section after its heading line.
+fn section_body(content: &str, section: &Section) -> Range<usize> {
+    let start = content[section.range.clone()]
+        .find('\n')
+        .map_or(section.range.end, |i| section.range.start + i + 1);
+    start..section.range.end
+}
+
+/// Helper function to get the byte range of the text of a section between its heading
+/// line and its first subsection.
+fn own_body(content: &str, section: &Section) -> Range<usize> {
+    let body = section_body(content, section);
+    let end = section
+        .children
+        .first()
+        .map_or(body.end, |child| child.range.start);
+    body.start..end
+}
+
+/// Helper function to drop leading blank lines and trailing whitespace, keeping the
+/// indentation of the first line.
+fn trim_blank_lines(text: &str) -> &str {
+    let text = text.trim_end();
+    let first = text.len() - text.trim_start().len();
+    let start = text[..first].rfind('\n').map_or(0, |i| i + 1);
+    &text[start..]
+}
+
+/// Helper function to join two blocks of markdown with a blank line.
+fn join_blocks(first: &str, second: &str) -> String {
+    let (first, second) = (trim_blank_lines(first), trim_blank_lines(second));
+    match (first.is_empty(), second.is_empty()) {
+        (true, _) => second.to_string(),
+        (_, true) => first.to_string(),
+        _ => format!("{}\n\n{}", first, second),
+    }
+}
+
+/// Helper function to get the level and text of an ATX heading line.
+pub(crate) fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_end();
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    ((1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])))
+        .then(|| (level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+/// Helper function to nest a flat list of headings under the closest heading above
+/// them with a lower level.
+fn nest(
+    headings: &mut std::iter::Peekable<std::vec::IntoIter<Section>>,
+    level: usize,
+) -> Vec<Section> {
+    let mut sections = Vec::new();
+    while let Some(mut section) = headings.next_if(|section| section.level > level) {
+        section.children = nest(headings, section.level);
+        sections.push(section);
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_outline() {
+        let content = "---\ntitle: Plan\n---\n# Plan\nIntro\n\n### Deep\n```\n# not a heading\n```\n## Goals\nShip.\n## Decisions ##\n- A\n# Appendix";
+        let outline = parse_outline(content);
+
+        assert_eq!(outline.len(), 2);
+        let plan = &outline[0];
+        assert_eq!((plan.level, plan.title.as_str()), (1, "Plan"));
+        assert_eq!((plan.line, plan.end_line), (4, 14));
+        let children: Vec<_> = plan
+            .children
+            .iter()
+            .map(|s| (s.title.as_str(), s.line, s.end_line))
+            .collect();
+        assert_eq!(
+            children,
+            vec![("Deep", 7, 10), ("Goals", 11, 12), ("Decisions", 13, 14)]
+        );
+        assert_eq!(
+            &content[plan.children[1].range.clone()],
+            "## Goals\nShip.\n"
+        );
+        assert_eq!((outline[1].line, outline[1].end_line), (15, 15));
+
+        assert_eq!(
+            find_section(&outline, "decisions").map(|s| s.line),
+            Some(13)
+        );
+        assert!(find_section(&outline, "### Goals").is_none());
+    }
+
+    #[test]
+    fn test_section_edits() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        let path = vault_dir.join("Project.md");
+        fs::write(
+            &path,
+            "---\ntitle: Project\nmodified_at: 2020-01-01 00:00:00\n---\n\n# Project\n\n## Status\n\nDraft.\n\n## Decisions\n- Use Rust\n\n### Rejected\n- Go\n\n## Notes\nKeep me.\n",
+        )?;
+
+        assert_eq!(
+            read_section("Project.md", vault_dir, "## Decisions")?,
+            "- Use Rust\n\n### Rejected\n- Go"
+        );
+        assert!(read_section("Project.md", vault_dir, "# Decisions").is_err());
+
+        replace_section("Project.md", vault_dir, "Status", "On track.\n")?;
+        // Appended content goes before the subsections, not into the last one.
+        append_to_section("Project.md", vault_dir, "## Decisions", "- Ship weekly")?;
+        replace_section("Project.md", vault_dir, "### Rejected", "")?;
+        insert_under_heading("Project.md", vault_dir, "## Notes", "First!")?;
+        insert_under_heading("Project.md", vault_dir, "Log", "- created")?;
+
+        let content = fs::read_to_string(&path)?;
+        let body = content.split_once("---\n\n").unwrap().1;
+        assert_eq!(
+            body,
+            "# Project\n\n## Status\n\nOn track.\n\n## Decisions\n- Use Rust\n\n- Ship weekly\n\n### Rejected\n\n## Notes\nFirst!\n\nKeep me.\n\n## Log\n\n- created\n"
+        );
+        assert!(!content.contains("2020-01-01"));
+
+        assert!(replace_section("Project.md", vault_dir, "Missing", "x").is_err());
+        Ok(())
+    }
+}
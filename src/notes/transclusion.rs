@@ -0,0 +1,409 @@
+// src/notes/transclusion.rs
+
+use crate::index::VaultIndex;
+use crate::links::{Link, LinkAnchor, LinkResolver, extract_links, unfenced_lines};
+use crate::notes::crud::strip_frontmatter;
+use crate::notes::sections::{find_section, heading, parse_outline};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// The number of nested embeds `render_note` expands unless told otherwise.
+pub const DEFAULT_EMBED_DEPTH: usize = 8;
+
+/// A piece of a rendered note and where it was copied from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceSpan {
+    /// Byte range in the rendered content
+    pub output: Range<usize>,
+    /// Path of the original note relative to the vault directory
+    pub path: String,
+    /// Byte range in the original note, frontmatter included
+    pub source: Range<usize>,
+    /// Number of embeds between the rendered note and the original note (0 for the
+    /// rendered note itself)
+    pub depth: usize,
+}
+
+/// Why an embed was left as written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedIssueKind {
+    /// The embedded note doesn't exist
+    MissingNote,
+    /// The embedded note has no such heading or block
+    MissingAnchor,
+    /// The embed would include a note (or section) that is already being expanded
+    Cycle,
+    /// The embed is nested deeper than the depth limit
+    DepthLimit,
+}
+
+/// An embed that couldn't be expanded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmbedIssue {
+    pub kind: EmbedIssueKind,
+    /// Path of the note containing the embed, relative to the vault directory
+    pub path: String,
+    /// 1-based line of the embed in that note
+    pub line: usize,
+    /// The embed target as written, with its anchor (`Note#Heading`)
+    pub target: String,
+}
+
+/// A note with its embeds expanded, as returned by `render_note`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenderedNote {
+    /// The content of the note without frontmatter, with every embed replaced by the
+    /// embedded content
+    pub content: String,
+    /// Where each piece of `content` comes from, in order and covering all of it
+    pub source_map: Vec<SourceSpan>,
+    /// The embeds left as written, in order of appearance
+    pub issues: Vec<EmbedIssue>,
+}
+
+impl RenderedNote {
+    /// Finds the original note and byte offset of a byte offset in the rendered content.
+    pub fn source_of(&self, offset: usize) -> Option<(&str, usize)> {
+        self.source_map
+            .iter()
+            .find(|span| span.output.contains(&offset))
+            .map(|span| {
+                (
+                    span.path.as_str(),
+                    span.source.start + offset - span.output.start,
+                )
+            })
+    }
+}
+
+/// Renders a note with its embeds (`![[Note]]`, `![[Note#Heading]]`, `![[Note#^block]]`
+/// and `![](Note.md)`) expanded, recursively.
+///
+/// Embedded notes are included without their frontmatter, like `read_note` returns
+/// them; a heading embed includes the heading and everything up to the next heading of
+/// the same or a higher level, and a block embed includes the paragraph or list item
+/// marked with `^block`. Embeds of attachments (like images) are left as written.
+///
+/// An embed is left as written, and reported in `issues`, if its note or anchor doesn't
+/// exist, if it would include a note or section that contains it (a cycle), or if it is
+/// nested more than `max_depth` embeds deep.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+/// * `max_depth` - The maximum number of nested embeds to expand (see `DEFAULT_EMBED_DEPTH`)
+///
+/// # Returns
+/// * `Result<RenderedNote>` - The rendered content with its source map
+///
+/// # Errors
+/// * Returns an error if the note or an embedded note can't be read
+/// * Returns an error if the vault can't be walked
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::notes::transclusion::{DEFAULT_EMBED_DEPTH, render_note};
+///
+/// let rendered = render_note("Weekly.md", Path::new("/path/to/vault"), DEFAULT_EMBED_DEPTH)?;
+/// println!("{}", rendered.content);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn render_note(
+    relative_path: &str,
+    vault_directory: &Path,
+    max_depth: usize,
+) -> Result<RenderedNote> {
+    let mut index = VaultIndex::load(vault_directory)?;
+    index.refresh()?;
+    let mut renderer = Renderer {
+        vault_directory,
+        resolver: LinkResolver::from_index(&index),
+        contents: HashMap::new(),
+        chain: Vec::new(),
+        max_depth,
+        rendered: RenderedNote::default(),
+    };
+
+    let relative_path = relative_path.replace('\\', "/");
+    let content = renderer.content(&relative_path)?;
+    let body = strip_frontmatter(&content)?;
+    let start = content.len() - body.len();
+    renderer.chain.push((relative_path.clone(), None));
+    renderer.expand(&relative_path, start..content.len())?;
+    Ok(renderer.rendered)
+}
+
+/// The state of a rendering: the notes read so far and the embeds being expanded.
+struct Renderer<'a> {
+    vault_directory: &'a Path,
+    resolver: LinkResolver,
+    contents: HashMap<String, String>,
+    /// The notes and anchors being expanded, outermost first
+    chain: Vec<(String, Option<LinkAnchor>)>,
+    max_depth: usize,
+    rendered: RenderedNote,
+}
+
+impl Renderer<'_> {
+    /// Helper function to read a note once.
+    fn content(&mut self, path: &str) -> Result<String> {
+        if let Some(content) = self.contents.get(path) {
+            return Ok(content.clone());
+        }
+        let file_path = self.vault_directory.join(path);
+        let content = fs::read_to_string(&file_path)
+            .with_context(|| format!("Failed to read note file: {}", file_path.display()))?;
+        self.contents.insert(path.to_string(), content.clone());
+        Ok(content)
+    }
+
+    /// Helper function to copy a range of a note to the output, expanding its embeds.
+    fn expand(&mut self, path: &str, range: Range<usize>) -> Result<()> {
+        let content = self.content(path)?;
+        let depth = self.chain.len() - 1;
+        let embeds: Vec<Link> = extract_links(&content)
+            .into_iter()
+            .filter(|link| link.embed && !link.is_external() && !is_attachment(&link.target))
+            .filter(|link| range.start <= link.span.start && link.span.end <= range.end)
+            .collect();
+
+        let mut copied = range.start;
+        for link in embeds {
+            let Some((target, section)) = self.embedded_section(path, &link, depth)? else {
+                continue;
+            };
+            self.copy(path, copied..link.span.start, depth, &content);
+            self.chain.push((target.clone(), link.anchor.clone()));
+            self.expand(&target, section)?;
+            self.chain.pop();
+            copied = link.span.end;
+        }
+        self.copy(path, copied..range.end, depth, &content);
+        Ok(())
+    }
+
+    /// Helper function to find the note and range an embed includes, or report why it
+    /// can't be expanded.
+    fn embedded_section(
+        &mut self,
+        path: &str,
+        link: &Link,
+        depth: usize,
+    ) -> Result<Option<(String, Range<usize>)>> {
+        let issue = |kind| EmbedIssue {
+            kind,
+            path: path.to_string(),
+            line: link.line,
+            target: match &link.anchor {
+                None => link.target.clone(),
+                Some(LinkAnchor::Heading(heading)) => format!("{}#{}", link.target, heading),
+                Some(LinkAnchor::Block(block)) => format!("{}#^{}", link.target, block),
+            },
+        };
+
+        let Some(target) = self.resolver.resolve(path, link).map(str::to_string) else {
+            self.rendered
+                .issues
+                .push(issue(EmbedIssueKind::MissingNote));
+            return Ok(None);
+        };
+        if self
+            .chain
+            .iter()
+            .any(|(p, anchor)| *p == target && (link.anchor.is_none() || *anchor == link.anchor))
+        {
+            self.rendered.issues.push(issue(EmbedIssueKind::Cycle));
+            return Ok(None);
+        }
+        if depth >= self.max_depth {
+            self.rendered.issues.push(issue(EmbedIssueKind::DepthLimit));
+            return Ok(None);
+        }
+
+        let content = self.content(&target)?;
+        let body_start = content.len() - strip_frontmatter(&content)?.len();
+        let section = match &link.anchor {
+            None => Some(body_start..content.len()),
+            Some(LinkAnchor::Heading(heading)) => heading_section(&content, heading),
+            Some(LinkAnchor::Block(block)) => block_section(&content, body_start, block),
+        };
+        match section {
+            // The embed keeps its own line break, so trailing ones are left out.
+            Some(section) => {
+                let end = section.start + content[section.clone()].trim_end().len();
+                Ok(Some((target, section.start..end)))
+            }
+            None => {
+                self.rendered
+                    .issues
+                    .push(issue(EmbedIssueKind::MissingAnchor));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Helper function to copy a range of a note to the output as is.
+    fn copy(&mut self, path: &str, range: Range<usize>, depth: usize, content: &str) {
+        if range.is_empty() {
+            return;
+        }
+        let start = self.rendered.content.len();
+        self.rendered.content.push_str(&content[range.clone()]);
+        self.rendered.source_map.push(SourceSpan {
+            output: start..self.rendered.content.len(),
+            path: path.to_string(),
+            source: range,
+            depth,
+        });
+    }
+}
+
+/// Helper function to tell embeds of files other than notes apart.
+fn is_attachment(target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or_default();
+    name.rsplit_once('.').is_some_and(|(_, extension)| {
+        !extension.eq_ignore_ascii_case("md")
+            && !extension.eq_ignore_ascii_case("markdown")
+            && !extension.contains(' ')
+    })
+}
+
+/// Helper function to find the range of a heading and its content, up to the next
+/// heading of the same or a higher level. For a nested anchor (`Setup#Linux`), the
+/// last heading is looked up. Headings are compared case-insensitively.
+fn heading_section(content: &str, anchor: &str) -> Option<Range<usize>> {
+    let wanted = anchor.rsplit('#').next().unwrap_or(anchor).trim();
+    find_section(&parse_outline(content), wanted).map(|section| section.range.clone())
+}
+
+/// Helper function to find the range of the paragraph or list item marked with
+/// `^block`, without the marker.
+fn block_section(content: &str, body_start: usize, block: &str) -> Option<Range<usize>> {
+    let marker = format!("^{}", block);
+    let lines: Vec<(usize, &str)> = unfenced_lines(content, body_start)
+        .map(|(_, line_start, line)| (line_start, line))
+        .collect();
+    let index = lines.iter().position(|(_, line)| {
+        let line = line.trim_end();
+        line.strip_suffix(marker.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.ends_with([' ', '\t']))
+    })?;
+    let (line_start, line) = lines[index];
+    let end = line_start + line.trim_end().len() - marker.len();
+
+    // A list item stands alone; a paragraph starts after the previous blank line.
+    let is_list_item = |line: &str| {
+        let line = line.trim_start();
+        line.starts_with("- ")
+            || line.starts_with("* ")
+            || line.starts_with("+ ")
+            || line
+                .split_once(". ")
+                .is_some_and(|(n, _)| n.chars().all(|c| c.is_ascii_digit()))
+    };
+    let mut start = index;
+    if !is_list_item(line) {
+        while start > 0
+            && !lines[start - 1].1.trim().is_empty()
+            && heading(lines[start - 1].1).is_none()
+            && !is_list_item(lines[start - 1].1)
+        {
+            start -= 1;
+        }
+    }
+    let start = lines[start].0;
+    let end = start + content[start..end].trim_end().len();
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_note() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("Main.md"),
+            "---\ntitle: Main\n---\n\nIntro\n![[Setup#Install]]\nQuote: ![[Ideas#^key]]\n![[Missing]] ![[diagram.png]]\n",
+        )?;
+        fs::write(
+            vault_dir.join("Setup.md"),
+            "---\ntitle: Setup\n---\n# Setup\n## Install\nRun it.\n### Linux\n![[Main]]\n## Use\nLater.\n",
+        )?;
+        fs::write(
+            vault_dir.join("Ideas.md"),
+            "First line\nof the idea ^key\n\n- item ^other\n",
+        )?;
+
+        let rendered = render_note("Main.md", vault_dir, DEFAULT_EMBED_DEPTH)?;
+        assert_eq!(
+            rendered.content,
+            "Intro\n## Install\nRun it.\n### Linux\n![[Main]]\nQuote: First line\nof the idea\n![[Missing]] ![[diagram.png]]\n"
+        );
+        let issues: Vec<_> = rendered
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.path.as_str(), i.line, i.target.as_str()))
+            .collect();
+        assert_eq!(
+            issues,
+            vec![
+                (EmbedIssueKind::Cycle, "Setup.md", 8, "Main"),
+                (EmbedIssueKind::MissingNote, "Main.md", 8, "Missing"),
+            ]
+        );
+
+        // The source map covers the output and points back into the original files.
+        let mut covered = 0;
+        for span in &rendered.source_map {
+            assert_eq!(span.output.start, covered);
+            covered = span.output.end;
+        }
+        assert_eq!(covered, rendered.content.len());
+        let offset = rendered.content.find("Run it").unwrap();
+        let (path, source) = rendered.source_of(offset).unwrap();
+        assert_eq!(path, "Setup.md");
+        assert_eq!(
+            &fs::read_to_string(vault_dir.join(path))?[source..source + 6],
+            "Run it"
+        );
+        let offset = rendered.content.find("of the idea").unwrap();
+        assert_eq!(rendered.source_of(offset).map(|(p, _)| p), Some("Ideas.md"));
+        assert!(rendered.source_map.iter().any(|s| s.depth == 1));
+
+        // Without expanding, every embed is a depth-limit issue.
+        let shallow = render_note("Main.md", vault_dir, 0)?;
+        assert!(shallow.content.contains("![[Setup#Install]]"));
+        assert_eq!(shallow.issues[0].kind, EmbedIssueKind::DepthLimit);
+
+        fs::write(
+            vault_dir.join("Loop.md"),
+            "---\ntitle: Loop\n---\nA ![[Loop#B]]\n# B\n![[Loop#B]]\n",
+        )?;
+        let rendered = render_note("Loop.md", vault_dir, DEFAULT_EMBED_DEPTH)?;
+        // A section may embed another section of its note, but not itself.
+        assert_eq!(
+            rendered.content,
+            "A # B\n![[Loop#B]]\n# B\n# B\n![[Loop#B]]\n"
+        );
+        assert_eq!(rendered.issues.len(), 2);
+        assert!(
+            rendered
+                .issues
+                .iter()
+                .all(|i| i.kind == EmbedIssueKind::Cycle)
+        );
+
+        assert!(render_note("Nope.md", vault_dir, DEFAULT_EMBED_DEPTH).is_err());
+        Ok(())
+    }
+}
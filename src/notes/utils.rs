@@ -1,11 +1,14 @@
 // src/notes/utils.rs
 
+use crate::index::VaultIndex;
+use crate::notes::frontmatter::Frontmatter;
 use anyhow::{Context, Result, anyhow};
 use regex::Regex;
 use serde_json::{Map as JsonMap, Value as JsonValue};
 use serde_yaml;
 use std::fs;
 use std::path::{Path, PathBuf};
+#[cfg(feature = "external-tools")]
 use std::process::Command;
 
 /// Sanitizes a title string to ensure it's a valid filename for Windows, Linux, and macOS.
@@ -119,7 +122,11 @@ pub fn yaml_to_json(yaml: serde_yaml::Value) -> JsonValue {
 
 /// Lists all markdown files in the vault directory.
 ///
-/// This function uses the `fd` command to find all markdown files in the vault directory.
+/// The vault is walked natively, skipping hidden files and directories (for example
+/// `.git` or `.obsidian`), so no external binaries are required. When the crate is built
+/// with the `external-tools` feature, `fd` is tried first and the native walk is used as
+/// a fallback if `fd` is not installed.
+///
 /// If `relative` is true, paths are returned relative to the vault directory.
 /// If `relative` is false, absolute paths are returned.
 ///
@@ -128,10 +135,10 @@ pub fn yaml_to_json(yaml: serde_yaml::Value) -> JsonValue {
 /// * `relative` - Whether to return relative paths (true) or absolute paths (false)
 ///
 /// # Returns
-/// * `Result<Vec<String>>` - A list of paths to markdown files
+/// * `Result<Vec<String>>` - A list of paths to markdown files, sorted by path
 ///
 /// # Errors
-/// * Returns an error if the `fd` command fails
+/// * Returns an error if the vault directory can't be walked
 ///
 /// # Examples
 /// ```
@@ -145,9 +152,133 @@ pub fn yaml_to_json(yaml: serde_yaml::Value) -> JsonValue {
 /// let absolute_paths = list_all_notes(vault_dir, false);
 /// ```
 pub fn list_all_notes(vault_directory: &Path, relative: bool) -> Result<Vec<String>> {
+    #[cfg(feature = "external-tools")]
+    if let Ok(paths) = list_all_notes_fd(vault_directory, relative) {
+        return Ok(paths);
+    }
+
+    let paths = walk_markdown_files(vault_directory)?
+        .into_iter()
+        .map(|path| {
+            if relative {
+                relative_note_path(&path, vault_directory)
+            } else {
+                path.to_string_lossy().to_string()
+            }
+        })
+        .collect();
+
+    Ok(paths)
+}
+
+/// Recursively collects the absolute paths of all markdown files (`.md` or `.markdown`)
+/// in the vault directory.
+///
+/// Hidden files and directories are skipped, mirroring the defaults of `fd` and `rg`.
+/// The returned paths are sorted so that results are deterministic across platforms.
+///
+/// # Arguments
+/// * `vault_directory` - The absolute path to the vault directory
+///
+/// # Returns
+/// * `Result<Vec<PathBuf>>` - The absolute paths of all markdown files in the vault
+///
+/// # Errors
+/// * Returns an error if the vault directory does not exist or can't be read
+pub fn walk_markdown_files(vault_directory: &Path) -> Result<Vec<PathBuf>> {
+    if !vault_directory.is_dir() {
+        return Err(anyhow!(
+            "Vault directory does not exist: {}",
+            vault_directory.display()
+        ));
+    }
+
+    let walker = walkdir::WalkDir::new(vault_directory)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry.file_name()));
+
+    let mut file_paths = Vec::new();
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if entry.file_type().is_file() && is_markdown_file(entry.path()) {
+            file_paths.push(entry.into_path());
+        }
+    }
+
+    Ok(file_paths)
+}
+
+/// Converts an absolute note path into a vault-relative path using forward slashes.
+pub(crate) fn relative_note_path(path: &Path, vault_directory: &Path) -> String {
+    let relative = path.strip_prefix(vault_directory).unwrap_or(path);
+    relative
+        .to_string_lossy()
+        .replace('\\', "/")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Resolves `.` and `..` segments in a vault-relative path.
+///
+/// `..` segments that would leave the vault are dropped.
+pub(crate) fn normalize_relative_path(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Computes the relative path from a vault-relative directory to a vault-relative file,
+/// e.g. from `a/b` to `a/c/Note.md` gives `../c/Note.md`.
+pub(crate) fn relative_path_between(from_directory: &str, to_path: &str) -> String {
+    let from: Vec<&str> = from_directory
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    let to: Vec<&str> = to_path.split('/').filter(|s| !s.is_empty()).collect();
+
+    // The last segment of `to` is the file name and never part of the common prefix.
+    let common = from
+        .iter()
+        .zip(&to[..to.len().saturating_sub(1)])
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut segments = vec![".."; from.len() - common];
+    segments.extend(&to[common..]);
+    segments.join("/")
+}
+
+/// Returns the file name of a vault-relative path without its extension.
+pub(crate) fn note_file_stem(relative_path: &str) -> &str {
+    let name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
+
+/// Returns true if the path has a markdown extension (`.md` or `.markdown`).
+fn is_markdown_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
+/// Returns true for dotfiles and dot-directories.
+fn is_hidden(file_name: &std::ffi::OsStr) -> bool {
+    file_name.to_string_lossy().starts_with('.')
+}
+
+/// Lists all markdown files in the vault directory using the external `fd` command.
+#[cfg(feature = "external-tools")]
+fn list_all_notes_fd(vault_directory: &Path, relative: bool) -> Result<Vec<String>> {
     // Use fd to find all markdown files in the vault directory
     let output = Command::new("fd")
-        .args(&[
+        .args([
             ".md$|.markdown$", // Match markdown file extensions
             vault_directory.to_str().unwrap_or("."),
             "--type",
@@ -167,25 +298,19 @@ pub fn list_all_notes(vault_directory: &Path, relative: bool) -> Result<Vec<Stri
     let stdout = String::from_utf8(output.stdout).context("Failed to parse fd command output")?;
 
     // Split by newlines to get all file paths
-    let file_paths: Vec<String> = stdout
+    let mut file_paths: Vec<String> = stdout
         .trim()
         .split('\n')
         .filter(|s| !s.is_empty())
         .map(|s| s.to_string())
         .collect();
+    file_paths.sort();
 
     // If relative paths are requested, convert absolute paths to relative
     if relative {
         let relative_paths = file_paths
             .into_iter()
-            .map(|path| {
-                let path_buf = PathBuf::from(&path);
-                match path_buf.strip_prefix(vault_directory) {
-                    Ok(rel_path) => rel_path.to_string_lossy().to_string(),
-                    Err(_) => path, // Fallback to original path if stripping prefix fails
-                }
-            })
-            .map(|p| p.trim_start_matches('/').to_string())
+            .map(|path| relative_note_path(Path::new(&path), vault_directory))
             .collect();
 
         Ok(relative_paths)
@@ -244,7 +369,7 @@ pub fn list_all_notes_alt(vault_directory: &Path, relative: bool) -> Result<Vec<
 ///
 /// # Returns
 /// * `Result<bool>` - True if the title is unique (no matching file exists),
-///                   False if it is not unique (a file with that name already exists)
+///   False if it is not unique (a file with that name already exists)
 ///
 /// # Errors
 /// * Returns an error if the file listing operation fails
@@ -268,13 +393,13 @@ pub fn check_unique_title(title: &str, vault_directory: &Path) -> Result<bool> {
     // Check if any file matches the sanitized title
     for file_path in all_files {
         let path = PathBuf::from(&file_path);
-        if let Some(file_stem) = path.file_stem() {
-            if let Some(file_stem_str) = file_stem.to_str() {
-                // Sanitize the file stem to ensure consistent comparison
-                let sanitized_file_stem = sanitize_title(file_stem_str);
-                if sanitized_file_stem == sanitized_title {
-                    return Ok(false); // Not unique - a matching file exists
-                }
+        if let Some(file_stem) = path.file_stem()
+            && let Some(file_stem_str) = file_stem.to_str()
+        {
+            // Sanitize the file stem to ensure consistent comparison
+            let sanitized_file_stem = sanitize_title(file_stem_str);
+            if sanitized_file_stem == sanitized_title {
+                return Ok(false); // Not unique - a matching file exists
             }
         }
     }
@@ -283,10 +408,32 @@ pub fn check_unique_title(title: &str, vault_directory: &Path) -> Result<bool> {
     Ok(true)
 }
 
-/// Gets the absolute file path for a markdown file with the given title using fd.
+/// Checks if a title is unique using a `VaultIndex` instead of listing the vault.
+///
+/// This performs the same sanitized file name comparison as `check_unique_title`.
+///
+/// # Arguments
+/// * `title` - The title to check
+/// * `index` - A refreshed index of the vault
+///
+/// # Returns
+/// * `bool` - True if no note in the index has a file name matching the title
+pub fn check_unique_title_indexed(title: &str, index: &VaultIndex) -> bool {
+    let sanitized_title = sanitize_title(title);
+    !index.entries().any(|entry| {
+        Path::new(&entry.path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|stem| sanitize_title(stem) == sanitized_title)
+    })
+}
+
+/// Gets the absolute file path for a markdown file with the given title.
 ///
-/// This function sanitizes the input title and searches for a file with that name
-/// (with .md or .markdown extension) in the vault directory using the fd command.
+/// The vault is first searched for a note whose frontmatter contains a `title:` line
+/// matching the title. If none is found, the search falls back to a file whose name is
+/// the sanitized title (with .md or .markdown extension). Both searches are performed
+/// natively; with the `external-tools` feature enabled, `rg` and `fd` are tried first.
 ///
 /// # Arguments
 /// * `title` - The title to search for
@@ -296,7 +443,7 @@ pub fn check_unique_title(title: &str, vault_directory: &Path) -> Result<bool> {
 /// * `Result<String>` - The absolute path to the file if found
 ///
 /// # Errors
-/// * Returns an error if the fd command fails or if no matching file is found
+/// * Returns an error if the vault can't be walked or if no matching file is found
 ///
 /// # Examples
 /// ```
@@ -308,13 +455,83 @@ pub fn check_unique_title(title: &str, vault_directory: &Path) -> Result<bool> {
 /// let file_path = get_file_path("My Note", vault_dir);
 /// ```
 pub fn get_file_path(title: &str, vault_directory: &Path) -> Result<String> {
+    #[cfg(feature = "external-tools")]
+    if let Ok(path) = get_file_path_external(title, vault_directory) {
+        return Ok(path);
+    }
+
+    let files = walk_markdown_files(vault_directory)?;
+
+    // Match a line starting with "title:" followed by the title, like "title: My Note".
+    let title_re = Regex::new(&format!("(?m)^title:\\s*{}", regex_escape(title)))
+        .context("Failed to build title search pattern")?;
+    for path in &files {
+        // Files that are not valid UTF-8 can't contain a matching title line.
+        // Titles that needed YAML quoting only match once the frontmatter is parsed.
+        if let Ok(content) = fs::read_to_string(path)
+            && (title_re.is_match(&content)
+                || extract_frontmatter_title(&content).as_deref() == Some(title))
+        {
+            return Ok(path.to_string_lossy().to_string());
+        }
+    }
+
+    // Fallback: search by sanitized filename. Like fd's smart case, the match is
+    // case-insensitive unless the sanitized title contains uppercase characters.
+    let sanitized = sanitize_title(title);
+    let case_insensitive = !sanitized.chars().any(|c| c.is_uppercase());
+    let name_re =
+        regex::RegexBuilder::new(&format!("^{}\\.(md|markdown)$", regex_escape(&sanitized)))
+            .case_insensitive(case_insensitive)
+            .build()
+            .context("Failed to build file name search pattern")?;
+    for path in &files {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name_re.is_match(&file_name) {
+            return Ok(path.to_string_lossy().to_string());
+        }
+    }
+
+    Err(anyhow!("No markdown file found with title: {}", title))
+}
+
+/// Gets the absolute file path for a note with the given title using a `VaultIndex`
+/// instead of searching the vault.
+///
+/// Like `get_file_path`, a note whose frontmatter title matches is preferred, and a
+/// note whose file name is the sanitized title is the fallback (see
+/// `VaultIndex::find_by_title`).
+///
+/// # Arguments
+/// * `title` - The title to search for
+/// * `index` - A refreshed index of the vault
+///
+/// # Returns
+/// * `Result<String>` - The absolute path to the file if found
+///
+/// # Errors
+/// * Returns an error if no note with the given title is in the index
+pub fn get_file_path_indexed(title: &str, index: &VaultIndex) -> Result<String> {
+    let entry = index
+        .find_by_title(title)
+        .ok_or_else(|| anyhow!("No markdown file found with title: {}", title))?;
+    Ok(index
+        .vault_directory()
+        .join(&entry.path)
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Gets the absolute file path for a note with the given title using `rg` and `fd`.
+#[cfg(feature = "external-tools")]
+fn get_file_path_external(title: &str, vault_directory: &Path) -> Result<String> {
     // Build a regex pattern that matches a line starting with "title:" followed by the title.
     // This will match lines like "title: My Note" (allowing any whitespace after the colon).
     let rg_pattern = format!("^title:\\s*{}", regex_escape(title));
 
     // Use ripgrep to search for markdown files that contain the title in their frontmatter.
     let rg_output = Command::new("rg")
-        .args(&[
+        .args([
             "--files-with-matches", // Only return the filenames with a match
             "--glob",
             "*.md", // Search markdown files
@@ -344,7 +561,7 @@ pub fn get_file_path(title: &str, vault_directory: &Path) -> Result<String> {
     let sanitized = sanitize_title(title);
     let fd_pattern = format!("^{}\\.(md|markdown)$", regex_escape(&sanitized));
     let fd_output = Command::new("fd")
-        .args(&[
+        .args([
             &fd_pattern,
             vault_directory.to_str().unwrap_or("."),
             "--type",
@@ -406,12 +623,10 @@ pub fn get_title(file_path: &Path) -> Result<String> {
     }
 
     // Fall back to using the filename without extension
-    if let Some(file_stem) = file_path.file_stem() {
-        if let Some(file_stem_str) = file_stem.to_str() {
-            // Convert hyphens back to spaces for a more natural title
-            let title = file_stem_str.replace('-', " ");
-            return Ok(title);
-        }
+    if let Some(file_name) = file_path.file_name()
+        && let Some(file_name_str) = file_name.to_str()
+    {
+        return Ok(fallback_title(file_name_str));
     }
 
     // If even the filename can't be used, return an error
@@ -421,8 +636,21 @@ pub fn get_title(file_path: &Path) -> Result<String> {
     ))
 }
 
+/// Returns the title of a note that has none in its frontmatter, as `get_title` does:
+/// the file name without extension, with hyphens turned back into spaces for a more
+/// natural title.
+pub(crate) fn fallback_title(relative_path: &str) -> String {
+    note_file_stem(relative_path).replace('-', " ")
+}
+
 /// Helper function to extract title from YAML frontmatter.
+///
+/// Frontmatter that isn't valid YAML falls back to reading the raw `title:` line.
 fn extract_frontmatter_title(content: &str) -> Option<String> {
+    if let Ok(Some((frontmatter, _))) = Frontmatter::from_content(content) {
+        return frontmatter.title;
+    }
+
     // Check if the content starts with YAML frontmatter (---)
     if !content.starts_with("---") {
         return None;
@@ -435,9 +663,9 @@ fn extract_frontmatter_title(content: &str) -> Option<String> {
     // Look for a line starting with "title:" in the frontmatter
     for line in frontmatter.lines() {
         let line = line.trim();
-        if line.starts_with("title:") {
+        if let Some(value) = line.strip_prefix("title:") {
             // Extract the title value (everything after "title:")
-            let title = line[6..].trim().to_string();
+            let title = value.trim().to_string();
             return Some(title);
         }
     }
@@ -493,8 +721,8 @@ mod tests {
         let yaml_int = serde_yaml::Value::Number(serde_yaml::Number::from(42));
         assert_eq!(yaml_to_json(yaml_int).as_i64().unwrap(), 42);
 
-        let yaml_float = serde_yaml::Value::Number(serde_yaml::Number::from(3.14));
-        assert!(yaml_to_json(yaml_float).as_f64().unwrap() - 3.14 < 0.001);
+        let yaml_float = serde_yaml::Value::Number(serde_yaml::Number::from(2.5));
+        assert!(yaml_to_json(yaml_float).as_f64().unwrap() - 2.5 < 0.001);
 
         let yaml_string = serde_yaml::Value::String("test".to_string());
         assert_eq!(yaml_to_json(yaml_string).as_str().unwrap(), "test");
@@ -526,7 +754,7 @@ mod tests {
         let json_obj = json_map.as_object().unwrap();
 
         assert_eq!(json_obj.get("key").unwrap().as_str().unwrap(), "value");
-        assert_eq!(json_obj.get("1").unwrap().as_bool().unwrap(), true);
+        assert!(json_obj.get("1").unwrap().as_bool().unwrap());
     }
 
     #[test]
@@ -573,17 +801,7 @@ mod tests {
     }
 
     #[test]
-    // #[ignore] // Skip if fd is not available
     fn test_list_all_notes() -> Result<()> {
-        // Check if fd is available
-        match Command::new("fd").arg("--version").output() {
-            Ok(_) => {} // fd is available
-            Err(_) => {
-                eprintln!("Skipping test_list_all_notes because fd command is not available");
-                return Ok(());
-            }
-        }
-
         // Create a temporary directory for testing
         let temp_dir = tempdir()?;
         let vault_directory = temp_dir.path();
@@ -662,16 +880,51 @@ mod tests {
     }
 
     #[test]
-    fn test_get_file_path() -> Result<()> {
-        // Check if fd is available
-        match Command::new("fd").arg("--version").output() {
-            Ok(_) => {} // fd is available
-            Err(_) => {
-                eprintln!("Skipping test_get_file_path because fd command is not available");
-                return Ok(());
-            }
+    fn test_check_unique_title_indexed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_directory = temp_dir.path();
+        fs::create_dir_all(vault_directory.join("project"))?;
+        fs::write(
+            vault_directory.join("project/Existing-Note.md"),
+            "existing note content",
+        )?;
+
+        let index = VaultIndex::open(vault_directory)?;
+        assert!(!check_unique_title_indexed("Existing Note", &index));
+        assert!(!check_unique_title_indexed("Existing.Note", &index));
+        assert!(check_unique_title_indexed("Non Existent Note", &index));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_path_indexed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_directory = temp_dir.path();
+        fs::create_dir_all(vault_directory.join("project"))?;
+        fs::write(
+            vault_directory.join("project/Renamed-File.md"),
+            "---\ntitle: 'Titled: Note'\n---\nBody",
+        )?;
+        fs::write(
+            vault_directory.join("project/Plain-Note.md"),
+            "No frontmatter",
+        )?;
+
+        let index = VaultIndex::open(vault_directory)?;
+        for title in ["Titled: Note", "Plain Note"] {
+            assert_eq!(
+                get_file_path_indexed(title, &index)?,
+                get_file_path(title, vault_directory)?
+            );
         }
+        assert!(get_file_path_indexed("Missing Note", &index).is_err());
 
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_file_path() -> Result<()> {
         // Create a temporary directory for testing
         let temp_dir = tempdir()?;
         let vault_directory = temp_dir.path();
@@ -796,4 +1049,24 @@ This frontmatter has no title field."#;
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_title_quoted() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        let file_path = vault_dir.join("Meeting-Notes.md");
+
+        fs::write(
+            &file_path,
+            "---\ntitle: 'Meeting: Notes'\ntags:\n---\n\nQuoted because of the colon.",
+        )?;
+
+        assert_eq!(get_title(&file_path)?, "Meeting: Notes");
+        assert_eq!(
+            get_file_path("Meeting: Notes", vault_dir)?,
+            file_path.to_string_lossy()
+        );
+
+        Ok(())
+    }
 }
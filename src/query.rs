@@ -1,89 +1,27 @@
 // src/query.rs
 
-use crate::query_parser::{build_jq_expression, parse_query};
-use anyhow::{Context, Result, anyhow};
-use serde_json::Value;
+use crate::query_native::query_notes_native;
+use anyhow::Result;
 use std::path::Path;
-use std::process::Command;
 
 /// Runs a DSL query against the YAML frontmatter of markdown notes in the vault.
 ///
-/// The DSL query is parsed and converted into a jq expression (using our query_parser module).
 /// For example, a DSL query like:
 ///
 /// ```ignore
 /// not tag = "CLI" and date >= "2025-03-01"
 /// ```
 ///
-/// will be converted into a jq filter that checks that the note’s tags array does not contain "CLI"
-/// (by producing an expression like `(.tags | index("CLI") == null)`) and that the date field meets the condition.
+/// selects the notes whose tags do not contain "CLI" and whose date is on or after
+/// 2025-03-01.
 ///
-/// Then, a shell pipeline is executed that:
-/// 1. Uses `fd` to list all markdown files (glob "*.md") in the vault directory.
-/// 2. For each file, uses `sed` to extract its YAML frontmatter.
-/// 3. If frontmatter exists, pipes it into `yq` (which converts it to JSON) and uses `jq` to add a "path"
-///    field (with any leading "./" removed).
-/// 4. Aggregates all JSON objects into an array using `jq -s` and applies a final jq filter that selects
-///    only those note objects matching the DSL query, mapping each note to only its `path` and `title`.
+/// The query is always evaluated natively by `query_native::query_notes_native`, so
+/// results don't depend on which binaries are installed, even with the
+/// `external-tools` feature.
 ///
 /// Returns a vector of `(relative_path, title)` pairs for the matched notes.
-///
-/// Note: This implementation requires that the following CLI tools be installed:
-///   - fd
-///   - sed
-///   - yq (version that supports `eval -o=json`)
-///   - jq
 pub fn query_notes(vault_directory: &Path, query: &str) -> Result<Vec<(String, String)>> {
-    // Parse the DSL query and build a jq expression.
-    let ast = parse_query(query)?;
-    let jq_expr = build_jq_expression(&ast);
-    // Build a jq filter that selects note objects and maps them to {path, title}.
-    let filter = format!(
-        "map(select({})) | map({{path: .path, title: .title}})",
-        jq_expr
-    );
-    let full_filter = format!("{{notes: {}}}", filter);
-
-    // Build the shell pipeline.
-    let cmd = format!(
-        "fd --glob '*.md' . | while read file; do \
-  clean=$(echo \"$file\" | sed 's|^\\./||'); \
-  front=$(sed -n '/^---$/,/^---$/p' \"$file\"); \
-  if [ -n \"$front\" ]; then \
-    echo \"$front\" | yq eval -o=json - | jq --arg path \"$clean\" '. + {{path: $path}}'; \
-  fi; \
-done | jq -s '{}'",
-        full_filter
-    );
-
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(&cmd)
-        .current_dir(vault_directory)
-        .output()
-        .context("Failed to execute query pipeline")?;
-    if !output.status.success() {
-        return Err(anyhow!(
-            "Query pipeline failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    // Parse the output JSON; expect an object with a "notes" array.
-    let json: Value =
-        serde_json::from_str(&stdout).context("Failed to parse JSON output from query pipeline")?;
-    let mut results = Vec::new();
-    if let Some(notes) = json.get("notes").and_then(|n| n.as_array()) {
-        for note in notes {
-            if let (Some(path), Some(title)) = (
-                note.get("path").and_then(|v| v.as_str()),
-                note.get("title").and_then(|v| v.as_str()),
-            ) {
-                results.push((path.to_string(), title.to_string()));
-            }
-        }
-    }
-    Ok(results)
+    query_notes_native(vault_directory, query)
 }
 
 #[cfg(test)]
@@ -217,4 +155,29 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_query_notes_canonical_tags() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        create_markdown_note(vault_dir, "note1.md", "Note One", "2025-03-01", &["CLI"])?;
+        create_markdown_note(vault_dir, "note2.md", "Note Two", "2025-03-10", &["rust"])?;
+        fs::write(
+            vault_dir.join("note3.md"),
+            "---\ntitle: Note Three\n---\nUsing the #terminal today.\n",
+        )?;
+        fs::write(
+            vault_dir.join(crate::tags::TAG_POLICY_FILE),
+            "fold_case: true\naliases:\n  cli: [terminal]\n",
+        )?;
+
+        // Tag conditions match canonical tags, inline hashtags included.
+        let mut paths: Vec<_> = query_notes(vault_dir, r#"tag = "cli""#)?
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["note1.md".to_string(), "note3.md".to_string()]);
+        Ok(())
+    }
 }
@@ -0,0 +1,764 @@
+// src/query_compiler.rs
+
+use crate::clock::{Clock, SystemClock};
+use crate::index::{IndexEntry, VaultIndex};
+use crate::query_native::{
+    AggregateRow, EvalContext, NoteMetadata, QueryRow, TextMatches, aggregate_notes,
+    canonical_tag_value, collect_text_matches, compare_field, load_query_notes, lookup_path,
+    sort_and_project, text_condition,
+};
+use crate::query_parser::{Expr, Query, Value, parse_statement};
+use crate::tags::TagPolicy;
+use anyhow::{Result, anyhow};
+use rayon::prelude::*;
+use serde_yaml::Value as YamlValue;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A DSL statement parsed and compiled once, to be run many times.
+///
+/// Compiling resolves every field of the filter to an accessor (tags, title, path or a
+/// frontmatter path), so running the query doesn't re-parse it or re-inspect field names
+/// per note. On each run, the predicate is optimized with the tag counts of the notes:
+/// conditions that can't match any tag are folded away (a query requiring an unused tag
+/// returns without looking at a single note), and the children of `and`/`or` are
+/// reordered so that the most selective conditions are evaluated first. Against a
+/// `VaultIndex`, conditions on tags, title and path are checked on the index entries
+/// before the full note metadata is built.
+///
+/// A `CompiledQuery` is `Send + Sync`, so it can be cached and shared between threads.
+///
+/// # Examples
+/// ```no_run
+/// use notemancy_core::index::VaultIndex;
+/// use notemancy_core::query_compiler::CompiledQuery;
+/// use std::path::Path;
+///
+/// let query = CompiledQuery::compile("tag = meeting and status != done order by title")?;
+/// let index = VaultIndex::open(Path::new("/path/to/vault"))?;
+/// for row in query.run_indexed(&index)? {
+///     println!("{}: {}", row.path, row.title);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct CompiledQuery {
+    source: String,
+    statement: Query,
+    predicate: Option<Predicate>,
+}
+
+/// How a field of the filter is read from a note, resolved at compile time.
+#[derive(Debug, Clone, PartialEq)]
+enum Accessor {
+    Tags,
+    Title,
+    Path,
+    /// A frontmatter key or dotted path
+    Field(String),
+}
+
+/// The compiled form of a filter, with nested `and`/`or` flattened.
+#[derive(Debug, Clone)]
+enum Predicate {
+    Compare {
+        accessor: Accessor,
+        op: String,
+        value: Value,
+    },
+    /// A full-text condition on the note body, looked up in `EvalContext::text_matches`
+    Text {
+        phrase: bool,
+        text: String,
+    },
+    And(Vec<Predicate>),
+    Or(Vec<Predicate>),
+    Not(Box<Predicate>),
+    /// A condition known to hold or not for every note
+    Const(bool),
+}
+
+/// The predicate of one run, optimized for the notes at hand.
+struct Plan {
+    /// Conditions on tags, title and path only, checked before anything else
+    prefilter: Vec<Predicate>,
+    /// The rest of the predicate
+    predicate: Predicate,
+    context: EvalContext,
+}
+
+/// Number of notes using each tag, used to estimate how selective a condition is.
+#[derive(Default)]
+struct TagStats<'a> {
+    notes: usize,
+    /// Counts by canonical tag
+    counts: HashMap<Cow<'a, str>, usize>,
+}
+
+/// The parts of a note a predicate reads, so that cheap conditions can be checked on
+/// index entries without building the note metadata.
+trait NoteView {
+    fn path(&self) -> &str;
+    fn title(&self) -> &str;
+    fn tags(&self) -> &[String];
+    fn field(&self, field: &str) -> Option<YamlValue>;
+}
+
+impl NoteView for NoteMetadata {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    fn field(&self, field: &str) -> Option<YamlValue> {
+        lookup_path(&self.frontmatter, field)
+    }
+}
+
+impl NoteView for IndexEntry {
+    fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Only built-in fields are evaluated on index entries (see `Predicate::is_cheap`).
+    fn field(&self, _field: &str) -> Option<YamlValue> {
+        None
+    }
+}
+
+impl CompiledQuery {
+    /// Parses and compiles a DSL statement.
+    ///
+    /// # Arguments
+    /// * `query` - The DSL statement, with the clauses described for `query_native::run_query`
+    ///
+    /// # Returns
+    /// * `Result<CompiledQuery>` - The compiled statement
+    ///
+    /// # Errors
+    /// * Returns an error if the statement can't be parsed
+    /// * Returns an error if a `body` or `text` condition uses an operator other than
+    ///   `:` and `contains`
+    pub fn compile(query: &str) -> Result<Self> {
+        let statement = parse_statement(query)?;
+        let predicate = statement.filter.as_ref().map(compile_expr).transpose()?;
+        Ok(CompiledQuery {
+            source: query.to_string(),
+            statement,
+            predicate,
+        })
+    }
+
+    /// Returns the statement the query was compiled from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the parsed statement.
+    pub fn statement(&self) -> &Query {
+        &self.statement
+    }
+
+    /// Runs the query against the notes of the vault, like `query_native::run_query`.
+    ///
+    /// # Errors
+    /// * Returns an error if the statement is an aggregate
+    /// * Returns an error if the vault can't be read
+    pub fn run(&self, vault_directory: &Path) -> Result<Vec<QueryRow>> {
+        self.run_with_clock(vault_directory, &SystemClock)
+    }
+
+    /// Runs the query like `run`, resolving relative dates against the given clock.
+    pub fn run_with_clock(
+        &self,
+        vault_directory: &Path,
+        clock: &dyn Clock,
+    ) -> Result<Vec<QueryRow>> {
+        self.ensure_aggregate(false)?;
+        let notes = self.filter_vault(vault_directory, clock)?;
+        Ok(sort_and_project(&self.statement, notes))
+    }
+
+    /// Runs the query against a `VaultIndex`, like `query_native::run_query_indexed`.
+    ///
+    /// # Errors
+    /// * Returns an error if the statement is an aggregate
+    /// * Returns an error if the query has full-text conditions and the search index
+    ///   can't be opened
+    pub fn run_indexed(&self, index: &VaultIndex) -> Result<Vec<QueryRow>> {
+        self.run_indexed_with_clock(index, &SystemClock)
+    }
+
+    /// Runs the query like `run_indexed`, resolving relative dates against the given clock.
+    pub fn run_indexed_with_clock(
+        &self,
+        index: &VaultIndex,
+        clock: &dyn Clock,
+    ) -> Result<Vec<QueryRow>> {
+        self.ensure_aggregate(false)?;
+        let notes = self.filter_index(index, clock)?;
+        Ok(sort_and_project(&self.statement, notes))
+    }
+
+    /// Runs an aggregate query against the notes of the vault, like
+    /// `query_native::run_aggregate_query`.
+    ///
+    /// # Errors
+    /// * Returns an error if the statement has no `group by` or aggregate
+    /// * Returns an error if the vault can't be read
+    pub fn run_aggregate(&self, vault_directory: &Path) -> Result<Vec<AggregateRow>> {
+        self.ensure_aggregate(true)?;
+        let notes = self.filter_vault(vault_directory, &SystemClock)?;
+        Ok(aggregate_notes(&self.statement, &notes))
+    }
+
+    /// Runs an aggregate query against a `VaultIndex`, like
+    /// `query_native::run_aggregate_query_indexed`.
+    ///
+    /// # Errors
+    /// * Returns an error if the statement has no `group by` or aggregate
+    pub fn run_aggregate_indexed(&self, index: &VaultIndex) -> Result<Vec<AggregateRow>> {
+        self.ensure_aggregate(true)?;
+        let notes = self.filter_index(index, &SystemClock)?;
+        Ok(aggregate_notes(&self.statement, &notes))
+    }
+
+    /// Helper function to check that the statement is run with the right method.
+    fn ensure_aggregate(&self, aggregate: bool) -> Result<()> {
+        match (aggregate, self.statement.is_aggregate()) {
+            (false, true) => Err(anyhow!(
+                "The statement groups notes; run it with run_aggregate"
+            )),
+            (true, false) => Err(anyhow!(
+                "The statement has no 'group by' or aggregate; run it with run"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Helper function to load the notes of the vault and keep the matching ones.
+    fn filter_vault(&self, vault_directory: &Path, clock: &dyn Clock) -> Result<Vec<NoteMetadata>> {
+        let notes = load_query_notes(vault_directory)?;
+        let tags = notes.iter().map(|note| note.tags.as_slice());
+        let Some(plan) = self.plan(tags, vault_directory, clock)? else {
+            return Ok(notes);
+        };
+        Ok(notes
+            .into_par_iter()
+            .filter(|note| plan.matches(note))
+            .collect())
+    }
+
+    /// Helper function to keep the matching notes of an index, checking the prefilter
+    /// on the entries before converting them.
+    fn filter_index(&self, index: &VaultIndex, clock: &dyn Clock) -> Result<Vec<NoteMetadata>> {
+        let tags = index.entries().map(|entry| entry.tags.as_slice());
+        let Some(plan) = self.plan(tags, index.vault_directory(), clock)? else {
+            return Ok(index.note_metadata());
+        };
+        let entries: Vec<&IndexEntry> = index.entries().collect();
+        Ok(entries
+            .into_par_iter()
+            .filter(|entry| {
+                plan.prefilter
+                    .iter()
+                    .all(|p| p.evaluate(*entry, &plan.context))
+            })
+            .filter_map(IndexEntry::note_metadata)
+            .filter(|note| plan.predicate.evaluate(note, &plan.context))
+            .collect())
+    }
+
+    /// Helper function to optimize the predicate for a run. Returns `None` if the
+    /// statement has no filter.
+    fn plan<'a>(
+        &self,
+        tags: impl Iterator<Item = &'a [String]>,
+        vault_directory: &Path,
+        clock: &dyn Clock,
+    ) -> Result<Option<Plan>> {
+        let Some(predicate) = &self.predicate else {
+            return Ok(None);
+        };
+        let today = clock.today();
+        let tag_policy = TagPolicy::load(vault_directory)?;
+        let predicate = predicate.with_tag_policy(&tag_policy);
+        let stats = if predicate.uses_tags() {
+            TagStats::collect(tags, &tag_policy)
+        } else {
+            TagStats::default()
+        };
+        let (predicate, _) = predicate.optimize(&stats, today);
+
+        // Full-text conditions are only searched if they survived the optimization.
+        let text_matches = match (&self.statement.filter, predicate.uses_text()) {
+            (Some(filter), true) => collect_text_matches(filter, vault_directory)?,
+            _ => TextMatches::new(),
+        };
+
+        let (prefilter, predicate) = match predicate {
+            Predicate::And(children) => {
+                let (cheap, rest): (Vec<_>, Vec<_>) =
+                    children.into_iter().partition(Predicate::is_cheap);
+                let rest = match rest.len() {
+                    0 => Predicate::Const(true),
+                    1 => rest.into_iter().next().unwrap_or(Predicate::Const(true)),
+                    _ => Predicate::And(rest),
+                };
+                (cheap, rest)
+            }
+            predicate if predicate.is_cheap() => (vec![predicate], Predicate::Const(true)),
+            predicate => (Vec::new(), predicate),
+        };
+        Ok(Some(Plan {
+            prefilter,
+            predicate,
+            context: EvalContext {
+                text_matches,
+                today,
+                tag_policy,
+            },
+        }))
+    }
+}
+
+impl Plan {
+    /// Checks a note against the prefilter and the rest of the predicate.
+    fn matches(&self, note: &NoteMetadata) -> bool {
+        self.prefilter
+            .iter()
+            .all(|p| p.evaluate(note, &self.context))
+            && self.predicate.evaluate(note, &self.context)
+    }
+}
+
+/// Helper function to compile a filter expression into a predicate.
+fn compile_expr(expr: &Expr) -> Result<Predicate> {
+    Ok(match expr {
+        Expr::Condition { field, op, value } => {
+            let lower = field.to_lowercase();
+            if lower == "body" || lower == "text" {
+                let phrase = text_condition(field, op).ok_or_else(|| {
+                    anyhow!(
+                        "The '{}' field only supports ':' and 'contains', found '{}'",
+                        field,
+                        op
+                    )
+                })?;
+                return Ok(Predicate::Text {
+                    phrase,
+                    text: value.to_string(),
+                });
+            }
+            let accessor = match lower.as_str() {
+                "tag" | "tags" => Accessor::Tags,
+                "title" => Accessor::Title,
+                "path" => Accessor::Path,
+                _ => Accessor::Field(field.clone()),
+            };
+            Predicate::Compare {
+                accessor,
+                op: op.clone(),
+                value: value.clone(),
+            }
+        }
+        Expr::And(lhs, rhs) => {
+            let mut children = Vec::new();
+            for child in [compile_expr(lhs)?, compile_expr(rhs)?] {
+                match child {
+                    Predicate::And(nested) => children.extend(nested),
+                    child => children.push(child),
+                }
+            }
+            Predicate::And(children)
+        }
+        Expr::Or(lhs, rhs) => {
+            let mut children = Vec::new();
+            for child in [compile_expr(lhs)?, compile_expr(rhs)?] {
+                match child {
+                    Predicate::Or(nested) => children.extend(nested),
+                    child => children.push(child),
+                }
+            }
+            Predicate::Or(children)
+        }
+        Expr::Not(inner) => Predicate::Not(Box::new(compile_expr(inner)?)),
+    })
+}
+
+impl Predicate {
+    /// Evaluates the predicate on a note, with the semantics of `query_native::evaluate_expr`.
+    fn evaluate<N: NoteView>(&self, note: &N, context: &EvalContext) -> bool {
+        match self {
+            Predicate::Compare {
+                accessor,
+                op,
+                value,
+            } => match accessor {
+                Accessor::Tags => compare_tags(
+                    &context.tag_policy.canonical_tags(note.tags()),
+                    op,
+                    value,
+                    context.today,
+                ),
+                Accessor::Title => compare_text(note.title(), op, value, context.today),
+                Accessor::Path => compare_text(note.path(), op, value, context.today),
+                Accessor::Field(field) => {
+                    compare_field(note.field(field).as_ref(), op, value, context.today)
+                }
+            },
+            Predicate::Text { phrase, text } => context
+                .text_matches
+                .get(&(*phrase, text.clone()))
+                .is_some_and(|paths| paths.contains(note.path())),
+            Predicate::And(children) => children.iter().all(|c| c.evaluate(note, context)),
+            Predicate::Or(children) => children.iter().any(|c| c.evaluate(note, context)),
+            Predicate::Not(inner) => !inner.evaluate(note, context),
+            Predicate::Const(value) => *value,
+        }
+    }
+
+    /// Returns true if the predicate only reads tags, title and path, which index
+    /// entries have without building the note metadata.
+    fn is_cheap(&self) -> bool {
+        match self {
+            Predicate::Compare { accessor, .. } => !matches!(accessor, Accessor::Field(_)),
+            Predicate::Text { .. } => false,
+            Predicate::And(children) | Predicate::Or(children) => {
+                children.iter().all(Predicate::is_cheap)
+            }
+            Predicate::Not(inner) => inner.is_cheap(),
+            Predicate::Const(_) => true,
+        }
+    }
+
+    /// Resolves the values of the tag conditions to canonical tags, with the semantics of
+    /// `query_native::canonical_tag_value`.
+    fn with_tag_policy(&self, policy: &TagPolicy) -> Predicate {
+        match self {
+            Predicate::Compare {
+                accessor: Accessor::Tags,
+                op,
+                value,
+            } => Predicate::Compare {
+                accessor: Accessor::Tags,
+                op: op.clone(),
+                value: canonical_tag_value(value, op, policy),
+            },
+            Predicate::And(children) => {
+                Predicate::And(children.iter().map(|c| c.with_tag_policy(policy)).collect())
+            }
+            Predicate::Or(children) => {
+                Predicate::Or(children.iter().map(|c| c.with_tag_policy(policy)).collect())
+            }
+            Predicate::Not(inner) => Predicate::Not(Box::new(inner.with_tag_policy(policy))),
+            other => other.clone(),
+        }
+    }
+
+    /// Returns true if the predicate has a condition on tags.
+    fn uses_tags(&self) -> bool {
+        match self {
+            Predicate::Compare { accessor, .. } => *accessor == Accessor::Tags,
+            Predicate::Text { .. } | Predicate::Const(_) => false,
+            Predicate::And(children) | Predicate::Or(children) => {
+                children.iter().any(Predicate::uses_tags)
+            }
+            Predicate::Not(inner) => inner.uses_tags(),
+        }
+    }
+
+    /// Returns true if the predicate has a full-text condition.
+    fn uses_text(&self) -> bool {
+        match self {
+            Predicate::Text { .. } => true,
+            Predicate::Compare { .. } | Predicate::Const(_) => false,
+            Predicate::And(children) | Predicate::Or(children) => {
+                children.iter().any(Predicate::uses_text)
+            }
+            Predicate::Not(inner) => inner.uses_text(),
+        }
+    }
+
+    /// Folds the conditions decided by the tag counts and orders the children of
+    /// `and`/`or` by selectivity.
+    ///
+    /// Returns the optimized predicate and the estimated fraction of notes it matches.
+    fn optimize(&self, stats: &TagStats, today: chrono::NaiveDate) -> (Predicate, f64) {
+        match self {
+            Predicate::Compare {
+                accessor: Accessor::Tags,
+                op,
+                value,
+            } => {
+                // A note matches if any of its tags does, so the notes using the
+                // matching tags bound the number of matches.
+                let negated = op == "!=";
+                let tag_op = if negated { "=" } else { op.as_str() };
+                let tagged: usize = stats
+                    .counts
+                    .iter()
+                    .filter(|(tag, _)| compare_tags(&[tag.to_string()], tag_op, value, today))
+                    .map(|(_, count)| count)
+                    .sum();
+                match (tagged, negated) {
+                    (0, negated) => (Predicate::Const(negated), if negated { 1.0 } else { 0.0 }),
+                    (tagged, negated) => {
+                        let fraction = (tagged as f64 / stats.notes.max(1) as f64).min(1.0);
+                        let fraction = if negated { 1.0 - fraction } else { fraction };
+                        (self.clone(), fraction)
+                    }
+                }
+            }
+            Predicate::Compare { accessor, op, .. } => {
+                let fraction = match (accessor, op.as_str()) {
+                    (Accessor::Title | Accessor::Path, "=" | "==") => 0.01,
+                    (_, "=" | "==" | "in") => 0.2,
+                    (_, "!=") => 0.8,
+                    _ => 0.5,
+                };
+                (self.clone(), fraction)
+            }
+            Predicate::Text { .. } => (self.clone(), 0.1),
+            Predicate::Const(value) => (self.clone(), if *value { 1.0 } else { 0.0 }),
+            Predicate::Not(inner) => match inner.optimize(stats, today) {
+                (Predicate::Const(value), _) => {
+                    (Predicate::Const(!value), if value { 0.0 } else { 1.0 })
+                }
+                (inner, fraction) => (Predicate::Not(Box::new(inner)), 1.0 - fraction),
+            },
+            Predicate::And(children) => {
+                let mut optimized = Vec::new();
+                for child in children {
+                    match child.optimize(stats, today) {
+                        (Predicate::Const(false), _) => return (Predicate::Const(false), 0.0),
+                        (Predicate::Const(true), _) => {}
+                        child => optimized.push(child),
+                    }
+                }
+                optimized.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+                let fraction = optimized.iter().map(|(_, f)| f).product();
+                match optimized.len() {
+                    0 => (Predicate::Const(true), 1.0),
+                    1 => optimized.remove(0),
+                    _ => (
+                        Predicate::And(optimized.into_iter().map(|(c, _)| c).collect()),
+                        fraction,
+                    ),
+                }
+            }
+            Predicate::Or(children) => {
+                let mut optimized = Vec::new();
+                for child in children {
+                    match child.optimize(stats, today) {
+                        (Predicate::Const(true), _) => return (Predicate::Const(true), 1.0),
+                        (Predicate::Const(false), _) => {}
+                        child => optimized.push(child),
+                    }
+                }
+                optimized.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+                let fraction = optimized.iter().map(|(_, f)| f).sum::<f64>().min(1.0);
+                match optimized.len() {
+                    0 => (Predicate::Const(false), 0.0),
+                    1 => optimized.remove(0),
+                    _ => (
+                        Predicate::Or(optimized.into_iter().map(|(c, _)| c).collect()),
+                        fraction,
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl<'a> TagStats<'a> {
+    /// Counts the notes using each tag, in its canonical form.
+    fn collect(tags: impl Iterator<Item = &'a [String]>, policy: &TagPolicy) -> Self {
+        let mut stats = TagStats::default();
+        for note_tags in tags {
+            stats.notes += 1;
+            for tag in note_tags {
+                let tag = if policy.is_identity() {
+                    Cow::Borrowed(tag.as_str())
+                } else {
+                    Cow::Owned(policy.canonical(tag))
+                };
+                *stats.counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        stats
+    }
+}
+
+/// Helper function to compare the tags of a note, with the list semantics of
+/// `query_native::compare_field`.
+fn compare_tags(tags: &[String], op: &str, value: &Value, today: chrono::NaiveDate) -> bool {
+    let any = |op: &str| tags.iter().any(|tag| compare_text(tag, op, value, today));
+    if op == "!=" { !any("=") } else { any(op) }
+}
+
+/// Helper function to compare a text field (a tag, the title or the path).
+fn compare_text(text: &str, op: &str, value: &Value, today: chrono::NaiveDate) -> bool {
+    compare_field(Some(&YamlValue::String(text.to_string())), op, value, today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::query_native::{run_aggregate_query, run_query_with_clock};
+    use chrono::NaiveDate;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_vault(vault_dir: &Path) -> Result<()> {
+        let notes = [
+            (
+                "a.md",
+                "Alpha",
+                "[rust, cli]",
+                "status: done\npriority: 1\ndue: 2025-03-10",
+            ),
+            (
+                "b.md",
+                "Beta",
+                "[rust]",
+                "status: open\npriority: 3\ndue: 2025-03-14",
+            ),
+            (
+                "c.md",
+                "Gamma",
+                "[python]",
+                "status: open\npriority: 2\ndue: 2025-04-01",
+            ),
+            ("d/e.md", "Delta", "[]", "status: open\npriority: 5"),
+        ];
+        for (path, title, tags, extra) in notes {
+            let path = vault_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(
+                path,
+                format!(
+                    "---\ntitle: {}\ntags: {}\n{}\n---\nBody of {}\n",
+                    title, tags, extra, title
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compiled_query_matches_interpreter() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        write_vault(vault_dir)?;
+        let index = VaultIndex::open(vault_dir)?;
+        let clock = FixedClock::on(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+
+        let queries = [
+            "tag = rust",
+            "tag != rust",
+            "tag = unknown",
+            "tag != unknown and status = open",
+            "tag = unknown or priority >= 3",
+            "not (tag = rust and status = done) order by priority desc",
+            "path glob \"d/*\" or title = Alpha",
+            "due = this_week and TAGS in (rust, python)",
+            "select title, priority where status = open order by priority limit 2 offset 1",
+        ];
+        for query in queries {
+            let compiled = CompiledQuery::compile(query)?;
+            let expected = run_query_with_clock(vault_dir, query, &clock)?;
+            assert_eq!(
+                compiled.run_with_clock(vault_dir, &clock)?,
+                expected,
+                "{}",
+                query
+            );
+            assert_eq!(
+                compiled.run_indexed_with_clock(&index, &clock)?,
+                expected,
+                "{}",
+                query
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_optimize() -> Result<()> {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+        let tags = [
+            vec!["rust".to_string()],
+            vec!["rust".to_string(), "rare".to_string()],
+            vec![],
+        ];
+        let stats = TagStats::collect(tags.iter().map(Vec::as_slice), &TagPolicy::default());
+
+        // A required tag nobody uses makes the whole conjunction false.
+        let compiled = CompiledQuery::compile("status = open and tag = unknown")?;
+        let (predicate, _) = compiled.predicate.as_ref().unwrap().optimize(&stats, today);
+        assert!(matches!(predicate, Predicate::Const(false)));
+
+        // The most selective condition is evaluated first, and decided ones are dropped.
+        let compiled =
+            CompiledQuery::compile("tag = rust and (tag = rare or tag = unknown) and tag != x")?;
+        let (predicate, fraction) = compiled.predicate.as_ref().unwrap().optimize(&stats, today);
+        let Predicate::And(children) = predicate else {
+            panic!("expected a conjunction, got {:?}", predicate);
+        };
+        assert_eq!(children.len(), 2);
+        assert!(matches!(
+            &children[0],
+            Predicate::Compare { value: Value::String(tag), .. } if tag == "rare"
+        ));
+        assert!(fraction < 0.5);
+        assert!(children.iter().all(Predicate::is_cheap));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compiled_query_errors() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        write_vault(vault_dir)?;
+
+        assert!(CompiledQuery::compile("tag = ").is_err());
+        assert!(CompiledQuery::compile("body = rust").is_err());
+
+        let grouped = CompiledQuery::compile("select tag, count() group by tag")?;
+        assert!(grouped.run(vault_dir).is_err());
+        assert_eq!(
+            grouped.run_aggregate(vault_dir)?,
+            run_aggregate_query(vault_dir, grouped.source())?
+        );
+        assert!(
+            CompiledQuery::compile("tag = rust")?
+                .run_aggregate(vault_dir)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compiled_query_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledQuery>();
+    }
+}
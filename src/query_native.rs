@@ -1,12 +1,26 @@
 // src/query_native.rs
 
-use crate::query_parser::{Expr, parse_query};
-use anyhow::{Context, Result, anyhow};
+use crate::clock::{Clock, SystemClock};
+use crate::index::VaultIndex;
+use crate::notes::frontmatter::{DATE_FORMAT, Frontmatter, TIMESTAMP_FORMAT};
+use crate::notes::utils::{relative_note_path, walk_markdown_files};
+use crate::query_parser::{
+    AggregateFunction, Column, DateUnit, Expr, OrderBy, Query, QueryError, RelativeDate, Value,
+    parse_statement,
+};
+use crate::search::SearchIndex;
+use crate::tags::{TagPolicy, note_tags};
+use crate::utils::closest_match;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime};
 use rayon::prelude::*;
-use serde_yaml::Value as YamlValue;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value as YamlValue};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
-use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::path::Path;
 
 /// Struct to hold note metadata extracted from the YAML frontmatter.
 #[derive(Debug)]
@@ -14,168 +28,1210 @@ pub struct NoteMetadata {
     pub path: String,
     pub title: String,
     pub date: Option<String>,
+    /// Tags of the frontmatter, then inline `#hashtags` of the body
     pub tags: Vec<String>,
+    pub frontmatter: Frontmatter,
 }
 
-/// Extracts the YAML frontmatter as a String from the given content.
-/// The frontmatter is defined as the text between the first two lines that consist solely of "---".
-fn extract_frontmatter_string(content: &str) -> Option<String> {
-    let mut lines = content.lines();
-    if let Some(first_line) = lines.next() {
-        if first_line.trim() == "---" {
-            let mut frontmatter_lines = Vec::new();
-            for line in lines {
-                if line.trim() == "---" {
-                    return Some(frontmatter_lines.join("\n"));
-                }
-                frontmatter_lines.push(line);
-            }
+impl NoteMetadata {
+    /// Builds the metadata for a note from its parsed frontmatter.
+    /// The title falls back to the file stem when the frontmatter has none.
+    pub fn from_frontmatter(path: String, file_stem: &str, frontmatter: Frontmatter) -> Self {
+        let title = frontmatter
+            .title
+            .clone()
+            .unwrap_or_else(|| file_stem.to_string());
+        let date = frontmatter
+            .extra
+            .get("date")
+            .and_then(|d| d.as_str())
+            .map(|d| d.to_string());
+        NoteMetadata {
+            path,
+            title,
+            date,
+            tags: frontmatter.tags.clone(),
+            frontmatter,
         }
     }
-    None
 }
 
 /// Loads all markdown files (with extensions "md" or "markdown") from the given vault directory,
 /// extracts their YAML frontmatter, and returns a vector of NoteMetadata.
+/// Notes without frontmatter are skipped, and so are hidden files and directories (like
+/// `.trash`), as in `list_all_notes` and the vault index.
+///
+/// Loading stops at the first note that can't be read or parsed; use
+/// `load_notes_tolerant` to load the rest of the vault and report the broken notes.
 pub fn load_notes(vault_directory: &Path) -> Result<Vec<NoteMetadata>> {
     let mut notes = Vec::new();
-    // Walk through the directory recursively using WalkDir.
-    for entry in WalkDir::new(vault_directory)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.is_file() {
-            // Check for markdown file extension.
-            if let Some(ext) = path.extension() {
-                if ext != "md" && ext != "markdown" {
-                    continue;
-                }
-            } else {
-                continue;
-            }
-            // Compute the relative path from the vault.
-            let relative_path = path
-                .strip_prefix(vault_directory)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
-            // Read the file content.
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read file {:?}", path))?;
-            // Extract frontmatter.
-            if let Some(front_str) = extract_frontmatter_string(&content) {
-                // Parse the frontmatter YAML.
-                let yaml: YamlValue = serde_yaml::from_str(&front_str)
-                    .with_context(|| format!("Failed to parse YAML in file {:?}", path))?;
-                if let YamlValue::Mapping(map) = yaml {
-                    // Get the title field, falling back to the file stem if missing.
-                    let title = if let Some(YamlValue::String(t)) =
-                        map.get(&YamlValue::String("title".to_string()))
-                    {
-                        t.clone()
-                    } else {
-                        path.file_stem()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string()
-                    };
-                    // Get the date field.
-                    let date = if let Some(YamlValue::String(d)) =
-                        map.get(&YamlValue::String("date".to_string()))
-                    {
-                        Some(d.clone())
-                    } else {
-                        None
-                    };
-                    // Get the tags field as a Vec<String>.
-                    let tags = if let Some(YamlValue::Sequence(seq)) =
-                        map.get(&YamlValue::String("tags".to_string()))
-                    {
-                        seq.iter()
-                            .filter_map(|v| {
-                                if let YamlValue::String(s) = v {
-                                    Some(s.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect()
-                    } else {
-                        Vec::new()
-                    };
-                    notes.push(NoteMetadata {
-                        path: relative_path,
-                        title,
-                        date,
-                        tags,
-                    });
-                }
-            }
+    for path in walk_markdown_files(vault_directory)? {
+        match load_note_file(vault_directory, &path) {
+            Ok(Some(note)) => notes.push(note),
+            Ok(None) => {}
+            Err(diagnostic) => return Err(anyhow!(diagnostic)),
         }
     }
     Ok(notes)
 }
 
+/// What kept a note from being loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadErrorKind {
+    /// The file couldn't be read
+    Unreadable,
+    /// The file isn't valid UTF-8
+    InvalidEncoding,
+    /// The frontmatter isn't valid YAML
+    MalformedYaml,
+    /// The frontmatter is valid YAML but not a mapping, or has fields of the wrong type
+    InvalidFrontmatter,
+}
+
+impl fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LoadErrorKind::Unreadable => "unreadable file",
+            LoadErrorKind::InvalidEncoding => "invalid UTF-8",
+            LoadErrorKind::MalformedYaml => "malformed YAML",
+            LoadErrorKind::InvalidFrontmatter => "invalid frontmatter",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A note that couldn't be loaded, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoadDiagnostic {
+    /// Path of the note relative to the vault directory
+    pub path: String,
+    /// The 1-based line of the file where the problem is, when known
+    pub line: Option<usize>,
+    pub kind: LoadErrorKind,
+    /// The underlying error message
+    pub message: String,
+}
+
+impl fmt::Display for LoadDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.path, line)?,
+            None => write!(f, "{}: ", self.path)?,
+        }
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for LoadDiagnostic {}
+
+/// The result of loading a vault with `load_notes_tolerant`.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// The notes that were loaded, including the ones without frontmatter
+    pub notes: Vec<NoteMetadata>,
+    /// The notes that couldn't be loaded, sorted by path
+    pub diagnostics: Vec<LoadDiagnostic>,
+}
+
+impl LoadReport {
+    /// Returns true if every note of the vault was loaded.
+    pub fn is_clean(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// Loads all markdown files of the vault like `load_notes`, without stopping at broken notes.
+///
+/// Notes that can't be read or whose frontmatter can't be parsed are reported in the
+/// diagnostics of the returned report, with the line of the problem when known. Notes
+/// without frontmatter are loaded with an empty frontmatter, titled after their file name.
+///
+/// # Arguments
+/// * `vault_directory` - The root directory of the vault
+///
+/// # Returns
+/// * `Result<LoadReport>` - The loaded notes and the diagnostics of the broken ones
+///
+/// # Errors
+/// * Returns an error if the vault directory does not exist
+pub fn load_notes_tolerant(vault_directory: &Path) -> Result<LoadReport> {
+    let mut report = LoadReport::default();
+    for path in walk_markdown_files(vault_directory)? {
+        match load_note_file(vault_directory, &path) {
+            Ok(Some(note)) => report.notes.push(note),
+            Ok(None) => {
+                let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                report.notes.push(NoteMetadata::from_frontmatter(
+                    relative_note_path(&path, vault_directory),
+                    &file_stem,
+                    Frontmatter::default(),
+                ));
+            }
+            Err(diagnostic) => report.diagnostics.push(diagnostic),
+        }
+    }
+    report.diagnostics.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+/// Helper function to load the notes a query runs against: the notes with frontmatter,
+/// skipping the ones that can't be loaded (see `load_notes_tolerant` for reporting them).
+pub(crate) fn load_query_notes(vault_directory: &Path) -> Result<Vec<NoteMetadata>> {
+    Ok(walk_markdown_files(vault_directory)?
+        .par_iter()
+        .filter_map(|path| load_note_file(vault_directory, path).ok().flatten())
+        .collect())
+}
+
+/// Helper function to load the metadata of one note.
+///
+/// Returns `Ok(None)` for notes without frontmatter, or whose frontmatter is empty.
+fn load_note_file(
+    vault_directory: &Path,
+    path: &Path,
+) -> std::result::Result<Option<NoteMetadata>, LoadDiagnostic> {
+    let relative_path = relative_note_path(path, vault_directory);
+    let diagnostic = |line: Option<usize>, kind: LoadErrorKind, message: String| LoadDiagnostic {
+        path: relative_path.clone(),
+        line,
+        kind,
+        message,
+    };
+
+    let bytes =
+        fs::read(path).map_err(|e| diagnostic(None, LoadErrorKind::Unreadable, e.to_string()))?;
+    let content = String::from_utf8(bytes).map_err(|e| {
+        let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+        let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+        diagnostic(
+            Some(line),
+            LoadErrorKind::InvalidEncoding,
+            e.utf8_error().to_string(),
+        )
+    })?;
+
+    let Some((front_str, _)) = Frontmatter::split(&content) else {
+        return Ok(None);
+    };
+    // Lines of the YAML are counted from the line after the opening `---`.
+    let yaml_offset = content[..content.len() - content.trim_start().len()]
+        .matches('\n')
+        .count()
+        + 1;
+    let yaml: YamlValue = serde_yaml::from_str(front_str).map_err(|e| {
+        diagnostic(
+            e.location().map(|location| location.line() + yaml_offset),
+            LoadErrorKind::MalformedYaml,
+            e.to_string(),
+        )
+    })?;
+    match yaml {
+        YamlValue::Null => Ok(None),
+        YamlValue::Mapping(_) => {
+            let frontmatter = Frontmatter::from_value(yaml).map_err(|e| {
+                diagnostic(
+                    Some(yaml_offset),
+                    LoadErrorKind::InvalidFrontmatter,
+                    format!("{:#}", e),
+                )
+            })?;
+            let file_stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let mut note = NoteMetadata::from_frontmatter(relative_path, &file_stem, frontmatter);
+            note.tags = note_tags(&content);
+            Ok(Some(note))
+        }
+        _ => Err(diagnostic(
+            Some(yaml_offset + 1),
+            LoadErrorKind::InvalidFrontmatter,
+            "The frontmatter is not a mapping".to_string(),
+        )),
+    }
+}
+
 /// Evaluates the DSL AST expression on a NoteMetadata record.
-/// Supported fields include "tag", "date", and "title". Date comparisons assume ISO 8601 strings.
-fn evaluate_expr(note: &NoteMetadata, expr: &Expr) -> bool {
+///
+/// Any frontmatter key can be queried, including dotted paths into nested mappings
+/// (`project.status`). `tag` is an alias for `tags`, and `title` and `path` refer to
+/// the note's resolved title and relative path.
+///
+/// Comparisons are typed: booleans and numbers are compared as such, dates and
+/// datetimes are parsed with chrono (a plain date compares against the date part of
+/// a datetime), and everything else is compared as a string. For list fields a
+/// condition holds if any element matches, and `!=` holds if no element is equal.
+/// Missing fields only satisfy `!=`.
+///
+/// Relative dates (`today`, `-7d`, `last_month`) are resolved against `context.today`.
+///
+/// Tags are compared in their canonical form under `context.tag_policy`; the filter's
+/// tag values are expected to be resolved already (see `canonical_tag_filter`).
+///
+/// `body` and `text` conditions match the note content through the full-text index;
+/// their results are looked up in `context.text_matches`.
+fn evaluate_expr(note: &NoteMetadata, expr: &Expr, context: &EvalContext) -> bool {
     match expr {
-        Expr::Condition { field, op, value } => match field.to_lowercase().as_str() {
-            "tag" => {
-                if op == "=" {
-                    note.tags.iter().any(|t| t == value)
-                } else if op == "!=" {
-                    !note.tags.iter().any(|t| t == value)
-                } else {
-                    false
+        Expr::Condition { field, op, value } => match text_condition(field, op) {
+            Some(phrase) => context
+                .text_matches
+                .get(&(phrase, value.to_string()))
+                .is_some_and(|paths| paths.contains(&note.path)),
+            None => compare_field(
+                condition_value(note, field, &context.tag_policy).as_ref(),
+                op,
+                value,
+                context.today,
+            ),
+        },
+        Expr::And(lhs, rhs) => {
+            evaluate_expr(note, lhs, context) && evaluate_expr(note, rhs, context)
+        }
+        Expr::Or(lhs, rhs) => {
+            evaluate_expr(note, lhs, context) || evaluate_expr(note, rhs, context)
+        }
+        Expr::Not(inner) => !evaluate_expr(note, inner, context),
+    }
+}
+
+/// What a query is evaluated against, besides the notes themselves.
+pub(crate) struct EvalContext {
+    pub(crate) text_matches: TextMatches,
+    /// The day relative dates are resolved from
+    pub(crate) today: NaiveDate,
+    /// How tags are resolved to their canonical form
+    pub(crate) tag_policy: TagPolicy,
+}
+
+/// Paths of the notes matching each full-text condition, keyed by (phrase, text).
+pub(crate) type TextMatches = HashMap<(bool, String), BTreeSet<String>>;
+
+/// Helper function to recognize full-text conditions on the note body.
+///
+/// Returns `Some(true)` for phrase matches (`body contains "..."`), `Some(false)` for
+/// matches on all words (`text: ...` or `body: ...`) and `None` for other conditions.
+pub(crate) fn text_condition(field: &str, op: &str) -> Option<bool> {
+    match (field.to_lowercase().as_str(), op) {
+        ("body" | "text", ":") => Some(false),
+        ("body" | "text", "contains" | "icontains") => Some(true),
+        _ => None,
+    }
+}
+
+/// Helper function to run the full-text conditions of a query against the search index.
+///
+/// The index is only opened (and refreshed) if the query has such conditions.
+pub(crate) fn collect_text_matches(expr: &Expr, vault_directory: &Path) -> Result<TextMatches> {
+    fn collect(expr: &Expr, conditions: &mut Vec<(bool, String)>) -> Result<()> {
+        match expr {
+            Expr::Condition { field, op, value } => {
+                let lower = field.to_lowercase();
+                if lower == "body" || lower == "text" {
+                    let phrase = text_condition(field, op).ok_or_else(|| {
+                        anyhow!(
+                            "The '{}' field only supports ':' and 'contains', found '{}'",
+                            field,
+                            op
+                        )
+                    })?;
+                    conditions.push((phrase, value.to_string()));
                 }
+                Ok(())
             }
-            "date" => {
-                if let Some(note_date) = &note.date {
-                    match op.as_str() {
-                        "=" => note_date == value,
-                        "!=" => note_date != value,
-                        ">=" => note_date >= value,
-                        "<=" => note_date <= value,
-                        ">" => note_date > value,
-                        "<" => note_date < value,
-                        _ => false,
-                    }
-                } else {
-                    false
-                }
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                collect(lhs, conditions)?;
+                collect(rhs, conditions)
             }
-            "title" => match op.as_str() {
-                "=" => note.title == *value,
-                "!=" => note.title != *value,
-                _ => false,
-            },
+            Expr::Not(inner) => collect(inner, conditions),
+        }
+    }
+
+    let mut conditions = Vec::new();
+    collect(expr, &mut conditions)?;
+    if conditions.is_empty() {
+        return Ok(TextMatches::new());
+    }
+
+    let index = SearchIndex::open(vault_directory)?;
+    Ok(conditions
+        .into_iter()
+        .map(|(phrase, text)| {
+            let paths = index.matching_paths(&text, phrase);
+            ((phrase, text), paths)
+        })
+        .collect())
+}
+
+/// Helper function to check if a query field refers to the tags of a note.
+pub(crate) fn is_tag_field(field: &str) -> bool {
+    matches!(field.to_lowercase().as_str(), "tag" | "tags")
+}
+
+/// Helper function to resolve the values of the tag conditions of a filter to
+/// canonical tags (see `canonical_tag_value`).
+fn canonical_tag_filter(expr: &Expr, policy: &TagPolicy) -> Expr {
+    match expr {
+        Expr::Condition { field, op, value } if is_tag_field(field) => Expr::Condition {
+            field: field.clone(),
+            op: op.clone(),
+            value: canonical_tag_value(value, op, policy),
+        },
+        Expr::Condition { .. } => expr.clone(),
+        Expr::And(lhs, rhs) => Expr::And(
+            Box::new(canonical_tag_filter(lhs, policy)),
+            Box::new(canonical_tag_filter(rhs, policy)),
+        ),
+        Expr::Or(lhs, rhs) => Expr::Or(
+            Box::new(canonical_tag_filter(lhs, policy)),
+            Box::new(canonical_tag_filter(rhs, policy)),
+        ),
+        Expr::Not(inner) => Expr::Not(Box::new(canonical_tag_filter(inner, policy))),
+    }
+}
+
+/// Helper function to resolve the value of a tag condition to canonical tags.
+///
+/// Tags compared with `=`, `!=` and `in` are resolved through the aliases; the text of
+/// other string operators is only normalized. Patterns are left as written and match
+/// the canonical tags.
+pub(crate) fn canonical_tag_value(value: &Value, op: &str, policy: &TagPolicy) -> Value {
+    match value {
+        Value::String(tag) if matches!(op, "=" | "==" | "!=" | "in") => {
+            Value::String(policy.canonical(tag))
+        }
+        Value::String(text) => Value::String(policy.normalize(text)),
+        Value::List(values) => Value::List(
+            values
+                .iter()
+                .map(|value| canonical_tag_value(value, op, policy))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Helper function to look up the value a condition compares, with the tags of the
+/// note in their canonical form.
+fn condition_value(note: &NoteMetadata, field: &str, policy: &TagPolicy) -> Option<YamlValue> {
+    if !is_tag_field(field) || policy.is_identity() {
+        return field_value(note, field);
+    }
+    Some(YamlValue::Sequence(
+        note.tags
+            .iter()
+            .map(|tag| YamlValue::String(policy.canonical(tag)))
+            .collect(),
+    ))
+}
+
+/// Helper function to look up the value of a query field for a note.
+fn field_value(note: &NoteMetadata, field: &str) -> Option<YamlValue> {
+    match field.to_lowercase().as_str() {
+        "tag" | "tags" => Some(YamlValue::Sequence(
+            note.tags.iter().cloned().map(YamlValue::String).collect(),
+        )),
+        "title" => Some(YamlValue::String(note.title.clone())),
+        "path" => Some(YamlValue::String(note.path.clone())),
+        _ => lookup_path(&note.frontmatter, field),
+    }
+}
+
+/// Helper function to follow a dotted path into the frontmatter.
+///
+/// A key that itself contains dots is matched as a whole first. Numeric segments
+/// index into lists.
+pub(crate) fn lookup_path(frontmatter: &Frontmatter, field: &str) -> Option<YamlValue> {
+    if let Some(value) = frontmatter.get(field) {
+        return Some(value);
+    }
+
+    let mut segments = field.split('.');
+    let mut current = frontmatter.get(segments.next()?)?;
+    for segment in segments {
+        current = match current {
+            YamlValue::Mapping(map) => map.get(segment)?.clone(),
+            YamlValue::Sequence(seq) => seq.get(segment.parse::<usize>().ok()?)?.clone(),
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+/// Helper function to apply a comparison to a (possibly missing or list) field value.
+pub(crate) fn compare_field(
+    field: Option<&YamlValue>,
+    op: &str,
+    value: &Value,
+    today: NaiveDate,
+) -> bool {
+    match field {
+        None | Some(YamlValue::Null) => op == "!=",
+        Some(YamlValue::Sequence(items)) => {
+            if op == "!=" {
+                !items
+                    .iter()
+                    .any(|item| compare_scalar(item, "=", value, today))
+            } else {
+                items
+                    .iter()
+                    .any(|item| compare_scalar(item, op, value, today))
+            }
+        }
+        Some(scalar) => compare_scalar(scalar, op, value, today),
+    }
+}
+
+/// Helper function to apply a comparison to a single value.
+fn compare_scalar(field: &YamlValue, op: &str, value: &Value, today: NaiveDate) -> bool {
+    if let Value::Relative(relative) = value
+        && matches!(op, "=" | "==" | "!=" | ">" | ">=" | "<" | "<=")
+    {
+        return compare_relative(field, op, relative, today);
+    }
+    match op {
+        ":" => compare_scalar(field, "icontains", value, today),
+        "in" => match value {
+            Value::List(values) => values.iter().any(|v| compare_scalar(field, "=", v, today)),
+            other => compare_scalar(field, "=", other, today),
+        },
+        "contains" | "starts_with" | "ends_with" | "icontains" | "istarts_with" | "iends_with" => {
+            let Some(text) = scalar_text(field) else {
+                return false;
+            };
+            let needle = value.to_string();
+            let (text, needle) = if op.starts_with('i') {
+                (text.to_lowercase(), needle.to_lowercase())
+            } else {
+                (text, needle)
+            };
+            match op.trim_start_matches('i') {
+                "contains" => text.contains(&needle),
+                "starts_with" => text.starts_with(&needle),
+                _ => text.ends_with(&needle),
+            }
+        }
+        "~" | "~*" | "glob" | "iglob" => match (scalar_text(field), value) {
+            (Some(text), Value::Pattern(pattern)) => pattern.is_match(&text),
             _ => false,
         },
-        Expr::And(lhs, rhs) => evaluate_expr(note, lhs) && evaluate_expr(note, rhs),
-        Expr::Or(lhs, rhs) => evaluate_expr(note, lhs) || evaluate_expr(note, rhs),
-        Expr::Not(inner) => !evaluate_expr(note, inner),
+        _ => {
+            let Some(ordering) = scalar_ordering(field, value) else {
+                return op == "!=";
+            };
+            match op {
+                "=" | "==" => ordering == Ordering::Equal,
+                "!=" => ordering != Ordering::Equal,
+                ">" => ordering == Ordering::Greater,
+                ">=" => ordering != Ordering::Less,
+                "<" => ordering == Ordering::Less,
+                "<=" => ordering != Ordering::Greater,
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Helper function to compare a field value with the range of days of a relative date.
+fn compare_relative(
+    field: &YamlValue,
+    op: &str,
+    relative: &RelativeDate,
+    today: NaiveDate,
+) -> bool {
+    let Some(date) = scalar_text(field).and_then(|text| parse_temporal(&text)) else {
+        return op == "!=";
+    };
+    let date = date.date();
+    let (start, end) = relative.resolve(today);
+    match op {
+        "=" | "==" => start <= date && date <= end,
+        "!=" => date < start || end < date,
+        ">" => date > end,
+        ">=" => date >= start,
+        "<" => date < start,
+        _ => date <= end,
+    }
+}
+
+/// Helper function to read a scalar field value as text.
+fn scalar_text(field: &YamlValue) -> Option<String> {
+    match field {
+        YamlValue::String(s) => Some(s.clone()),
+        YamlValue::Number(n) => Some(n.to_string()),
+        YamlValue::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Helper function to order a field value against a query value, using the most
+/// specific type both sides can be read as. Returns `None` for values that can't be
+/// compared (like mappings).
+fn scalar_ordering(field: &YamlValue, value: &Value) -> Option<Ordering> {
+    let field_text = scalar_text(field)?;
+    let value_text = value.to_string();
+
+    if let (YamlValue::Bool(a), Value::Bool(b)) = (field, value) {
+        return Some(a.cmp(b));
+    }
+    if let (Some(a), Some(b)) = (parse_number(field, &field_text), parse_number_value(value)) {
+        return a.partial_cmp(&b);
+    }
+    if let (Some(a), Some(b)) = (parse_temporal(&field_text), parse_temporal(&value_text)) {
+        return Some(a.compare(&b));
+    }
+    Some(field_text.as_str().cmp(value_text.as_str()))
+}
+
+/// Helper function to read a field value as a number.
+fn parse_number(field: &YamlValue, text: &str) -> Option<f64> {
+    match field {
+        YamlValue::Number(n) => n.as_f64(),
+        YamlValue::String(_) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Helper function to read a query value as a number.
+fn parse_number_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => Some(*n),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// A date or a date with time, parsed from a frontmatter or query value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Temporal {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl Temporal {
+    /// Compares two values, at date granularity if either side has no time.
+    fn compare(&self, other: &Temporal) -> Ordering {
+        match (self, other) {
+            (Temporal::DateTime(a), Temporal::DateTime(b)) => a.cmp(b),
+            _ => self.date().cmp(&other.date()),
+        }
+    }
+
+    fn date(&self) -> NaiveDate {
+        match self {
+            Temporal::Date(d) => *d,
+            Temporal::DateTime(dt) => dt.date(),
+        }
     }
+
+    /// Returns the value as a datetime, at midnight for a plain date.
+    fn datetime(&self) -> NaiveDateTime {
+        match self {
+            Temporal::Date(d) => d.and_time(NaiveTime::MIN),
+            Temporal::DateTime(dt) => *dt,
+        }
+    }
+}
+
+/// Helper function to parse a date (`2025-03-01`) or datetime (`2025-03-01 10:30:00`,
+/// ISO 8601 with `T`, or RFC 3339 with an offset).
+fn parse_temporal(text: &str) -> Option<Temporal> {
+    let text = text.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, DATE_FORMAT) {
+        return Some(Temporal::Date(date));
+    }
+    for format in [
+        TIMESTAMP_FORMAT,
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(Temporal::DateTime(datetime));
+        }
+    }
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|datetime| Temporal::DateTime(datetime.naive_utc()))
+}
+
+/// A note matched by a query, with the fields of its `select` projection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryRow {
+    pub path: String,
+    pub title: String,
+    /// The selected fields in `select` order (missing fields are null), or the whole
+    /// frontmatter when the query has no `select`.
+    pub fields: Mapping,
+}
+
+/// Runs a DSL statement against the notes of the vault and returns structured rows.
+///
+/// On top of the filter (see `evaluate_expr`), the statement may project fields with
+/// `select`, sort with `order by <field> [asc|desc]` and page with `limit` and `offset`
+/// (see `query_parser::parse_statement`).
+///
+/// Notes that can't be read or whose frontmatter can't be parsed are left out rather
+/// than failing the query; `load_notes_tolerant` reports them.
+///
+/// # Arguments
+/// * `vault_directory` - The root directory of the vault
+/// * `query` - The DSL statement, e.g.
+///   `select title, modified_at where tag = meeting order by modified_at desc limit 20`
+///
+/// # Returns
+/// * `Result<Vec<QueryRow>>` - The matched notes in order. Notes are sorted by the
+///   `order by` keys (with notes missing a key last), then by path.
+///
+/// # Errors
+/// * Returns an error if the statement can't be parsed or the vault can't be read
+pub fn run_query(vault_directory: &Path, query: &str) -> Result<Vec<QueryRow>> {
+    run_query_with_clock(vault_directory, query, &SystemClock)
+}
+
+/// Runs a DSL statement like `run_query`, resolving relative dates (`today`, `-7d`,
+/// `this_week`) against the given clock instead of the system clock.
+pub fn run_query_with_clock(
+    vault_directory: &Path,
+    query: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<QueryRow>> {
+    let statement = parse_statement(query)?;
+    let notes = load_query_notes(vault_directory)?;
+    execute_statement(&statement, notes, vault_directory, clock)
+}
+
+/// Runs a DSL statement against a `VaultIndex` instead of rescanning the vault.
+///
+/// See `run_query` for the supported clauses and the order of the rows.
+pub fn run_query_indexed(index: &VaultIndex, query: &str) -> Result<Vec<QueryRow>> {
+    run_query_indexed_with_clock(index, query, &SystemClock)
+}
+
+/// Runs a DSL statement like `run_query_indexed`, resolving relative dates against the
+/// given clock instead of the system clock.
+pub fn run_query_indexed_with_clock(
+    index: &VaultIndex,
+    query: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<QueryRow>> {
+    let statement = parse_statement(query)?;
+    execute_statement(
+        &statement,
+        index.note_metadata(),
+        index.vault_directory(),
+        clock,
+    )
 }
 
 /// Executes a native query on the vault directory using our DSL parser and evaluator.
 /// It loads all note metadata natively and uses Rayon to filter them in parallel.
-/// Returns a vector of (relative_path, title) pairs for notes that match the query.
+/// Returns a vector of (relative_path, title) pairs for notes that match the query,
+/// ordered as described for `run_query`.
 pub fn query_notes_native(vault_directory: &Path, query: &str) -> Result<Vec<(String, String)>> {
-    let ast = parse_query(query)?;
-    let notes = load_notes(vault_directory)?;
-    let results: Vec<(String, String)> = notes
+    Ok(run_query(vault_directory, query)?
+        .into_iter()
+        .map(|row| (row.path, row.title))
+        .collect())
+}
+
+/// Executes a native query against a `VaultIndex` instead of rescanning the vault.
+///
+/// The index must have been refreshed (for example with `VaultIndex::open`) for the
+/// results to reflect the current state of the vault.
+/// Returns a vector of (relative_path, title) pairs for notes that match the query.
+pub fn query_notes_indexed(index: &VaultIndex, query: &str) -> Result<Vec<(String, String)>> {
+    Ok(run_query_indexed(index, query)?
+        .into_iter()
+        .map(|row| (row.path, row.title))
+        .collect())
+}
+
+/// Fields every note has, on top of its frontmatter keys.
+const BUILTIN_FIELDS: [&str; 6] = ["tag", "tags", "title", "path", "body", "text"];
+
+/// Checks a DSL statement for problems, for editors to underline.
+///
+/// A statement that can't be parsed yields its parse error. Otherwise every field that
+/// no note in the vault has is reported, with a "did you mean" hint when a known field
+/// is close. Unknown fields are not an error when running a query (they are missing
+/// from every note), so they are only reported here.
+///
+/// # Arguments
+/// * `vault_directory` - The root directory of the vault
+/// * `query` - The DSL statement to check
+///
+/// # Returns
+/// * `Result<Vec<QueryError>>` - The problems found, in source order. Empty if the
+///   statement is fine.
+///
+/// # Errors
+/// * Returns an error if the vault can't be read
+pub fn diagnose_query(vault_directory: &Path, query: &str) -> Result<Vec<QueryError>> {
+    let statement = match parse_statement(query) {
+        Ok(statement) => statement,
+        Err(error) => return error.downcast::<QueryError>().map(|error| vec![error]),
+    };
+    let notes = load_query_notes(vault_directory)?;
+    let mut known: BTreeSet<String> = BTreeSet::new();
+    for note in &notes {
+        known.extend(
+            note.frontmatter
+                .to_mapping()
+                .keys()
+                .filter_map(|key| key.as_str())
+                .map(str::to_string),
+        );
+    }
+
+    Ok(statement
+        .fields
+        .into_iter()
+        .filter_map(|(field, span)| {
+            let root = field.split('.').next().unwrap_or(&field);
+            if known.contains(&field)
+                || known.contains(root)
+                || BUILTIN_FIELDS.contains(&field.to_lowercase().as_str())
+            {
+                return None;
+            }
+            let candidates = known.iter().map(String::as_str).chain(BUILTIN_FIELDS);
+            let error = QueryError::new(format!("Unknown field '{}'", field), span)
+                .with_expected(&["field name"]);
+            Some(match closest_match(root, candidates) {
+                Some(suggestion) => error.with_hint(format!("did you mean '{}'?", suggestion)),
+                None => error,
+            })
+        })
+        .collect())
+}
+
+/// A group of notes from an aggregate statement, e.g. `select tag, count() group by tag`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AggregateRow {
+    /// The values of the `group by` columns, in order. A list field puts a note in one
+    /// group per element, and notes without the field are grouped under null.
+    pub group: Vec<YamlValue>,
+    /// The `select` columns by name (like `tag`, `month(created_on)` or `count()`), in
+    /// `select` order. Without `select`, the group columns followed by `count()`.
+    pub values: Mapping,
+}
+
+/// Runs an aggregate DSL statement against the notes of the vault.
+///
+/// The statement groups the notes matching its filter by the `group by` columns and
+/// computes `count()`, `count(field)`, `min(field)`, `max(field)` and `avg(field)` for
+/// each group. Dates can be bucketed with `year()`, `month()`, `week()` and `day()`.
+/// Without `group by`, the aggregates are computed over all matching notes.
+///
+/// # Arguments
+/// * `vault_directory` - The root directory of the vault
+/// * `query` - The DSL statement, e.g.
+///   `select type, month(created_on), count() group by type, month(created_on)`
+///
+/// # Returns
+/// * `Result<Vec<AggregateRow>>` - One row per group, sorted by the `order by` columns
+///   and then by the group values
+///
+/// # Errors
+/// * Returns an error if the statement can't be parsed or has no `group by` or aggregate
+/// * Returns an error if the vault can't be read
+pub fn run_aggregate_query(vault_directory: &Path, query: &str) -> Result<Vec<AggregateRow>> {
+    run_aggregate_query_with_clock(vault_directory, query, &SystemClock)
+}
+
+/// Runs an aggregate DSL statement like `run_aggregate_query`, resolving relative dates
+/// against the given clock instead of the system clock.
+pub fn run_aggregate_query_with_clock(
+    vault_directory: &Path,
+    query: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<AggregateRow>> {
+    let statement = parse_statement(query)?;
+    let notes = load_query_notes(vault_directory)?;
+    execute_aggregate(&statement, notes, vault_directory, clock)
+}
+
+/// Runs an aggregate DSL statement against a `VaultIndex` instead of rescanning the vault.
+///
+/// See `run_aggregate_query` for the supported clauses.
+pub fn run_aggregate_query_indexed(index: &VaultIndex, query: &str) -> Result<Vec<AggregateRow>> {
+    run_aggregate_query_indexed_with_clock(index, query, &SystemClock)
+}
+
+/// Runs an aggregate DSL statement like `run_aggregate_query_indexed`, resolving relative
+/// dates against the given clock instead of the system clock.
+pub fn run_aggregate_query_indexed_with_clock(
+    index: &VaultIndex,
+    query: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<AggregateRow>> {
+    let statement = parse_statement(query)?;
+    execute_aggregate(
+        &statement,
+        index.note_metadata(),
+        index.vault_directory(),
+        clock,
+    )
+}
+
+/// Helper function to keep the notes matching the filter of a statement.
+fn filter_notes(
+    statement: &Query,
+    notes: Vec<NoteMetadata>,
+    vault_directory: &Path,
+    clock: &dyn Clock,
+) -> Result<Vec<NoteMetadata>> {
+    let Some(filter) = &statement.filter else {
+        return Ok(notes);
+    };
+    let tag_policy = TagPolicy::load(vault_directory)?;
+    let filter = canonical_tag_filter(filter, &tag_policy);
+    let context = EvalContext {
+        text_matches: collect_text_matches(&filter, vault_directory)?,
+        today: clock.today(),
+        tag_policy,
+    };
+    Ok(notes
+        .into_par_iter()
+        .filter(|note| evaluate_expr(note, &filter, &context))
+        .collect())
+}
+
+/// Helper function to filter, sort, page and project notes for a parsed statement.
+fn execute_statement(
+    statement: &Query,
+    notes: Vec<NoteMetadata>,
+    vault_directory: &Path,
+    clock: &dyn Clock,
+) -> Result<Vec<QueryRow>> {
+    if statement.is_aggregate() {
+        return Err(anyhow!(
+            "The statement groups notes; run it with run_aggregate_query"
+        ));
+    }
+    let matched = filter_notes(statement, notes, vault_directory, clock)?;
+    Ok(sort_and_project(statement, matched))
+}
+
+/// Helper function to sort, page and project the notes matched by a statement.
+pub(crate) fn sort_and_project(statement: &Query, notes: Vec<NoteMetadata>) -> Vec<QueryRow> {
+    let mut matched: Vec<(SortKeys, NoteMetadata)> = notes
         .into_par_iter()
-        .filter(|note| evaluate_expr(note, &ast))
-        .map(|note| (note.path, note.title))
+        .map(|note| {
+            let keys = statement
+                .order_by
+                .iter()
+                .map(|key| column_value(&note, &key.column).and_then(|v| SortKey::from_value(&v)))
+                .collect();
+            (keys, note)
+        })
         .collect();
-    Ok(results)
+
+    matched.sort_by(|(a_keys, a), (b_keys, b)| {
+        compare_sort_keys(a_keys, b_keys, &statement.order_by).then_with(|| a.path.cmp(&b.path))
+    });
+
+    matched
+        .into_iter()
+        .map(|(_, note)| note)
+        .skip(statement.offset.unwrap_or(0))
+        .take(statement.limit.unwrap_or(usize::MAX))
+        .map(|note| project_row(note, &statement.select))
+        .collect()
+}
+
+/// Helper function to group, aggregate, sort and page notes for a parsed statement.
+fn execute_aggregate(
+    statement: &Query,
+    notes: Vec<NoteMetadata>,
+    vault_directory: &Path,
+    clock: &dyn Clock,
+) -> Result<Vec<AggregateRow>> {
+    if !statement.is_aggregate() {
+        return Err(anyhow!(
+            "The statement has no 'group by' or aggregate; run it with run_query"
+        ));
+    }
+    let matched = filter_notes(statement, notes, vault_directory, clock)?;
+    Ok(aggregate_notes(statement, &matched))
+}
+
+/// Helper function to group the notes matched by a statement and compute its aggregates.
+pub(crate) fn aggregate_notes(statement: &Query, notes: &[NoteMetadata]) -> Vec<AggregateRow> {
+    // Group the notes, keeping groups in order of first appearance.
+    let mut groups: Vec<(Vec<YamlValue>, Vec<&NoteMetadata>)> = Vec::new();
+    let mut group_positions: HashMap<Vec<YamlValue>, usize> = HashMap::new();
+    if statement.group_by.is_empty() {
+        groups.push((Vec::new(), notes.iter().collect()));
+    } else {
+        for note in notes {
+            let mut keys: Vec<Vec<YamlValue>> = vec![Vec::new()];
+            for column in &statement.group_by {
+                let values = match column_value(note, column) {
+                    Some(YamlValue::Sequence(items)) if !items.is_empty() => items,
+                    None | Some(YamlValue::Sequence(_)) => vec![YamlValue::Null],
+                    Some(value) => vec![value],
+                };
+                keys = keys
+                    .into_iter()
+                    .flat_map(|key| {
+                        values.iter().map(move |value| {
+                            let mut key = key.clone();
+                            key.push(value.clone());
+                            key
+                        })
+                    })
+                    .collect();
+            }
+            for key in keys {
+                let position = *group_positions.entry(key.clone()).or_insert_with(|| {
+                    groups.push((key, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[position].1.push(note);
+            }
+        }
+    }
+
+    let select: Vec<Column> = if statement.select.is_empty() {
+        let mut columns = statement.group_by.clone();
+        columns.push(Column::Aggregate {
+            function: AggregateFunction::Count,
+            field: None,
+        });
+        columns
+    } else {
+        statement.select.clone()
+    };
+
+    let mut rows: Vec<(SortKeys, SortKeys, AggregateRow)> = groups
+        .into_iter()
+        .map(|(group, members)| {
+            let column_result =
+                |column: &Column| match statement.group_by.iter().position(|c| c == column) {
+                    Some(i) => group[i].clone(),
+                    None => aggregate_value(&members, column),
+                };
+            let values: Mapping = select
+                .iter()
+                .map(|column| (YamlValue::String(column.to_string()), column_result(column)))
+                .collect();
+            let order_keys = statement
+                .order_by
+                .iter()
+                .map(|order| SortKey::from_value(&column_result(&order.column)))
+                .collect();
+            let group_keys = group.iter().map(SortKey::from_value).collect();
+            (order_keys, group_keys, AggregateRow { group, values })
+        })
+        .collect();
+
+    let group_order: Vec<OrderBy> = statement
+        .group_by
+        .iter()
+        .map(|column| OrderBy {
+            column: column.clone(),
+            descending: false,
+        })
+        .collect();
+    rows.sort_by(|(a_order, a_group, _), (b_order, b_group, _)| {
+        compare_sort_keys(a_order, b_order, &statement.order_by)
+            .then_with(|| compare_sort_keys(a_group, b_group, &group_order))
+    });
+
+    rows.into_iter()
+        .map(|(_, _, row)| row)
+        .skip(statement.offset.unwrap_or(0))
+        .take(statement.limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Helper function to compute an aggregate column over the notes of a group.
+fn aggregate_value(notes: &[&NoteMetadata], column: &Column) -> YamlValue {
+    let Column::Aggregate { function, field } = column else {
+        return YamlValue::Null;
+    };
+    let Some(field) = field else {
+        return YamlValue::from(notes.len() as u64);
+    };
+    let values: Vec<YamlValue> = notes
+        .iter()
+        .filter_map(|note| field_value(note, field))
+        .flat_map(|value| match value {
+            YamlValue::Sequence(items) => items,
+            value => vec![value],
+        })
+        .filter(|value| !value.is_null())
+        .collect();
+
+    match function {
+        AggregateFunction::Count => YamlValue::from(
+            notes
+                .iter()
+                .filter(|note| field_value(note, field).is_some_and(|v| !v.is_null()))
+                .count() as u64,
+        ),
+        AggregateFunction::Min | AggregateFunction::Max => values
+            .into_iter()
+            .filter_map(|value| SortKey::from_value(&value).map(|key| (key, value)))
+            .reduce(|best, candidate| {
+                let ordering = candidate.0.compare(&best.0);
+                let better = match function {
+                    AggregateFunction::Min => ordering.is_lt(),
+                    _ => ordering.is_gt(),
+                };
+                if better { candidate } else { best }
+            })
+            .map_or(YamlValue::Null, |(_, value)| value),
+        AggregateFunction::Avg => {
+            let numbers: Vec<f64> = values
+                .iter()
+                .filter_map(|value| match SortKey::from_value(value) {
+                    Some(SortKey::Number(n)) => Some(n),
+                    _ => None,
+                })
+                .collect();
+            if numbers.is_empty() {
+                YamlValue::Null
+            } else {
+                YamlValue::from(numbers.iter().sum::<f64>() / numbers.len() as f64)
+            }
+        }
+    }
+}
+
+/// Helper function to compare two lists of `order by` keys. Missing keys sort last in
+/// both directions.
+fn compare_sort_keys(
+    a: &[Option<SortKey>],
+    b: &[Option<SortKey>],
+    order_by: &[OrderBy],
+) -> Ordering {
+    a.iter()
+        .zip(b)
+        .zip(order_by)
+        .map(|((a_key, b_key), order)| match (a_key, b_key) {
+            (Some(a_key), Some(b_key)) if order.descending => b_key.compare(a_key),
+            (Some(a_key), Some(b_key)) => a_key.compare(b_key),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Helper function to read a field or date bucket column of a note.
+fn column_value(note: &NoteMetadata, column: &Column) -> Option<YamlValue> {
+    match column {
+        Column::Field(field) => field_value(note, field),
+        Column::Bucket { unit, field } => match field_value(note, field)? {
+            YamlValue::Sequence(items) => Some(YamlValue::Sequence(
+                items
+                    .iter()
+                    .filter_map(|item| date_bucket(item, *unit))
+                    .collect(),
+            )),
+            value => date_bucket(&value, *unit),
+        },
+        Column::Aggregate { .. } => None,
+    }
+}
+
+/// Helper function to truncate a date or datetime value to a bucket like `2025-03`.
+/// Values that aren't dates have no bucket.
+fn date_bucket(value: &YamlValue, unit: DateUnit) -> Option<YamlValue> {
+    let date = parse_temporal(&scalar_text(value)?)?.date();
+    let bucket = match unit {
+        DateUnit::Year => date.format("%Y").to_string(),
+        DateUnit::Month => date.format("%Y-%m").to_string(),
+        DateUnit::Week => {
+            let week = date.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        DateUnit::Day => date.format(DATE_FORMAT).to_string(),
+    };
+    Some(YamlValue::String(bucket))
+}
+
+/// Helper function to build the row of a note for a `select` projection.
+fn project_row(note: NoteMetadata, select: &[Column]) -> QueryRow {
+    let fields = if select.is_empty() {
+        note.frontmatter.to_mapping()
+    } else {
+        select
+            .iter()
+            .map(|column| {
+                (
+                    YamlValue::String(column.to_string()),
+                    column_value(&note, column).unwrap_or(YamlValue::Null),
+                )
+            })
+            .collect()
+    };
+    QueryRow {
+        path: note.path,
+        title: note.title,
+        fields,
+    }
+}
+
+/// The `order by` keys of a row, in order. Missing values have no key.
+type SortKeys = Vec<Option<SortKey>>;
+
+/// A field value as an `order by` key, read with the same types as comparisons.
+#[derive(Debug, Clone)]
+enum SortKey {
+    Bool(bool),
+    Number(f64),
+    Temporal(NaiveDateTime),
+    Text(String),
+}
+
+impl SortKey {
+    /// Reads a field value as a key. Lists are ordered by their first element, and
+    /// mappings and nulls have no key.
+    fn from_value(value: &YamlValue) -> Option<Self> {
+        match value {
+            YamlValue::Bool(b) => Some(SortKey::Bool(*b)),
+            YamlValue::Number(n) => n.as_f64().map(SortKey::Number),
+            YamlValue::String(s) => Some(
+                s.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .map(SortKey::Number)
+                    .or_else(|| parse_temporal(s).map(|t| SortKey::Temporal(t.datetime())))
+                    .unwrap_or_else(|| SortKey::Text(s.clone())),
+            ),
+            YamlValue::Sequence(items) => items.first().and_then(SortKey::from_value),
+            _ => None,
+        }
+    }
+
+    /// Compares two keys. Keys of different types are ordered booleans first, then
+    /// numbers, dates and text.
+    fn compare(&self, other: &SortKey) -> Ordering {
+        match (self, other) {
+            (SortKey::Bool(a), SortKey::Bool(b)) => a.cmp(b),
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            (SortKey::Temporal(a), SortKey::Temporal(b)) => a.cmp(b),
+            (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            SortKey::Bool(_) => 0,
+            SortKey::Number(_) => 1,
+            SortKey::Temporal(_) => 2,
+            SortKey::Text(_) => 3,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::FixedClock;
     use crate::query; // shell-based query module
     use anyhow::Result;
     use std::fs;
@@ -253,4 +1309,514 @@ mod tests {
         assert_eq!(paths, vec!["note2.md".to_string(), "note3.md".to_string()]);
         Ok(())
     }
+
+    #[test]
+    fn test_query_notes_indexed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        create_markdown_note(vault_dir, "note1.md", "Note One", "2025-03-01", &["CLI"])?;
+        create_markdown_note(vault_dir, "note2.md", "Note Two", "2025-03-15", &["rust"])?;
+        create_markdown_note(vault_dir, "note3.md", "Note Three", "2025-04-01", &["rust"])?;
+        // Hidden directories are skipped with or without the index.
+        fs::create_dir(vault_dir.join(".trash"))?;
+        create_markdown_note(vault_dir, ".trash/old.md", "Old", "2025-03-02", &["rust"])?;
+        // Inline hashtags count as tags, like in `get_notes_by_tag`.
+        fs::write(
+            vault_dir.join("note4.md"),
+            "---\ntitle: Note Four\ndate: 2025-03-20\n---\nAbout #rust and `#cli`.\n",
+        )?;
+
+        let index = VaultIndex::open(vault_dir)?;
+        let query = r#"tag = "rust" and date < "2025-04-01""#;
+        let mut indexed: Vec<_> = query_notes_indexed(&index, query)?;
+        let mut scanned = query_notes_native(vault_dir, query)?;
+        indexed.sort();
+        scanned.sort();
+        assert_eq!(indexed, scanned);
+        assert_eq!(
+            indexed,
+            vec![
+                ("note2.md".to_string(), "Note Two".to_string()),
+                ("note4.md".to_string(), "Note Four".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_notes_frontmatter() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("single.md"),
+            "---\ntitle: 'Single: Tag'\ntags: rust\nstatus: draft\n---\n\nBody",
+        )?;
+
+        let notes = load_notes(vault_dir)?;
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].title, "Single: Tag");
+        assert_eq!(notes[0].tags, vec!["rust".to_string()]);
+        assert_eq!(
+            notes[0].frontmatter.get("status"),
+            Some(YamlValue::from("draft"))
+        );
+
+        let results = query_notes_native(vault_dir, r#"tag = "rust""#)?;
+        assert_eq!(results.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_notes_tolerant() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("good.md"),
+            "---\ntitle: Good\ntags: [rust]\n---\nBody",
+        )?;
+        fs::write(vault_dir.join("plain.md"), "# Just a body\n")?;
+        fs::write(
+            vault_dir.join("conflict.md"),
+            "---\ntitle: Conflict\ntags: [rust\n<<<<<<< HEAD\n---\nBody",
+        )?;
+        fs::write(vault_dir.join("binary.md"), b"---\ntitle: x\n---\n\xff\xfe")?;
+        fs::write(vault_dir.join("scalar.md"), "---\njust text\n---\n")?;
+
+        assert!(load_notes(vault_dir).is_err());
+
+        let report = load_notes_tolerant(vault_dir)?;
+        let mut titles: Vec<_> = report.notes.iter().map(|n| n.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Good", "plain"]);
+        assert!(!report.is_clean());
+
+        let kinds: Vec<_> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.path.as_str(), d.kind, d.line))
+            .collect();
+        assert_eq!(
+            kinds[0],
+            ("binary.md", LoadErrorKind::InvalidEncoding, Some(4))
+        );
+        assert_eq!(kinds[1].0, "conflict.md");
+        assert_eq!(kinds[1].1, LoadErrorKind::MalformedYaml);
+        assert!(kinds[1].2.is_some_and(|line| line >= 3));
+        assert_eq!(
+            kinds[2],
+            ("scalar.md", LoadErrorKind::InvalidFrontmatter, Some(2))
+        );
+
+        // Queries skip the broken notes instead of failing.
+        let results = query_notes_native(vault_dir, "tag = rust")?;
+        assert_eq!(results, vec![("good.md".to_string(), "Good".to_string())]);
+
+        assert!(load_notes_tolerant(&vault_dir.join("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_typed_fields() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("a.md"),
+            "---\ntitle: A\ntype: meeting\npriority: 10\ndraft: false\n\
+             modified_at: 2025-03-10 09:00:00\nproject:\n  status: active\n  owners: [ana, ben]\n---\n",
+        )?;
+        fs::write(
+            vault_dir.join("b.md"),
+            "---\ntitle: B\ntype: journal\npriority: 9\ndraft: true\n\
+             modified_at: 2025-03-09 18:30:00\nproject:\n  status: done\n---\n",
+        )?;
+
+        let run = |query: &str| -> Result<Vec<String>> {
+            let mut titles: Vec<_> = query_notes_native(vault_dir, query)?
+                .into_iter()
+                .map(|(_, title)| title)
+                .collect();
+            titles.sort();
+            Ok(titles)
+        };
+
+        assert_eq!(run(r#"type = "meeting""#)?, vec!["A"]);
+        // Numeric, not lexicographic: 10 > 9
+        assert_eq!(run("priority > 9")?, vec!["A"]);
+        assert_eq!(run("draft = true")?, vec!["B"]);
+        assert_eq!(run("project.status = active")?, vec!["A"]);
+        assert_eq!(run("project.owners = ben")?, vec!["A"]);
+        assert_eq!(run("project.owners != ben")?, vec!["B"]);
+        // A plain date compares against the date part of a datetime
+        assert_eq!(run("modified_at = 2025-03-10")?, vec!["A"]);
+        assert_eq!(run(r#"modified_at < "2025-03-10 08:00:00""#)?, vec!["B"]);
+        assert_eq!(run("missing != x")?, vec!["A", "B"]);
+        assert!(run("missing = x")?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_string_operators() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::create_dir_all(vault_dir.join("projects/web"))?;
+        create_markdown_note(
+            vault_dir,
+            "projects/web/standup.md",
+            "2025-03 Team Meeting",
+            "2025-03-01",
+            &["rust", "cli"],
+        )?;
+        create_markdown_note(
+            vault_dir,
+            "journal.md",
+            "Daily Journal",
+            "2025-03-02",
+            &["life"],
+        )?;
+
+        let run = |query: &str| -> Result<Vec<String>> {
+            let mut paths: Vec<_> = query_notes_native(vault_dir, query)?
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+        let standup = vec!["projects/web/standup.md".to_string()];
+
+        assert_eq!(run(r#"title contains "Meeting""#)?, standup);
+        assert!(run(r#"title contains "meeting""#)?.is_empty());
+        assert_eq!(run(r#"title icontains "meeting""#)?, standup);
+        assert_eq!(run(r#"title starts_with "2025-""#)?, standup);
+        assert_eq!(run(r#"title iends_with "JOURNAL""#)?, vec!["journal.md"]);
+        assert_eq!(run(r#"title ~ "^\d{4}-""#)?, standup);
+        assert_eq!(run(r#"title ~* "^daily""#)?, vec!["journal.md"]);
+        assert_eq!(run(r#"path glob "projects/**""#)?, standup);
+        assert!(run(r#"path glob "projects/*.md""#)?.is_empty());
+        assert_eq!(run(r#"path glob "**/*.md""#)?.len(), 2);
+        assert_eq!(run(r#"tag in ("cli", "go")"#)?, standup);
+        assert_eq!(run(r#"not tag in (rust, life)"#)?, Vec::<String>::new());
+
+        assert!(query_notes_native(vault_dir, r#"title ~ "(""#).is_err());
+        assert!(query_notes_native(vault_dir, r#"tag in "cli""#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_body_text() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("garden.md"),
+            "---\ntitle: Garden\ntags:\n  - home\n---\n\nPlanted tomatoes and basil near the fence.\n",
+        )?;
+        fs::write(
+            vault_dir.join("kitchen.md"),
+            "---\ntitle: Kitchen\ntags:\n  - home\n---\n\nBasil pesto needs fresh tomatoes.\n",
+        )?;
+
+        let run = |query: &str| -> Result<Vec<String>> {
+            let mut paths: Vec<_> = query_notes_native(vault_dir, query)?
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect();
+            paths.sort();
+            Ok(paths)
+        };
+
+        assert_eq!(run("text: tomatoes")?, vec!["garden.md", "kitchen.md"]);
+        assert_eq!(run(r#"text: "basil fence""#)?, vec!["garden.md"]);
+        assert_eq!(
+            run(r#"body contains "fresh tomatoes""#)?,
+            vec!["kitchen.md"]
+        );
+        assert!(run(r#"body contains "tomatoes fresh""#)?.is_empty());
+        assert_eq!(
+            run(r#"tag = home and not body contains "pesto""#)?,
+            vec!["garden.md"]
+        );
+        assert_eq!(run("title: GARD")?, vec!["garden.md"]);
+        assert!(query_notes_native(vault_dir, "body = basil").is_err());
+
+        let index = VaultIndex::open(vault_dir)?;
+        let indexed: Vec<_> = query_notes_indexed(&index, r#"body contains "fresh tomatoes""#)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(indexed, vec!["kitchen.md"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_query_order_limit_select() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        for (file, title, modified_at, priority) in [
+            ("a.md", "Alpha", "2025-03-02 09:00:00", Some(2)),
+            ("b.md", "Beta", "2025-03-01 18:30:00", Some(10)),
+            ("c.md", "Gamma", "2025-03-03 08:00:00", None),
+            ("d.md", "Delta", "2025-03-02 12:00:00", Some(2)),
+        ] {
+            let priority = priority.map_or(String::new(), |p| format!("priority: {}\n", p));
+            fs::write(
+                vault_dir.join(file),
+                format!(
+                    "---\ntitle: {}\nmodified_at: {}\n{}tags:\n  - meeting\n---\n",
+                    title, modified_at, priority
+                ),
+            )?;
+        }
+
+        let paths =
+            |rows: Vec<QueryRow>| -> Vec<String> { rows.into_iter().map(|row| row.path).collect() };
+
+        let rows = run_query(vault_dir, "tag = meeting order by modified_at desc limit 3")?;
+        assert_eq!(paths(rows), vec!["c.md", "d.md", "a.md"]);
+
+        let rows = run_query(vault_dir, "order by modified_at desc offset 3")?;
+        assert_eq!(paths(rows), vec!["b.md"]);
+
+        // Notes without a priority sort last; ties fall back to the path.
+        let rows = run_query(vault_dir, "order by priority desc, title")?;
+        assert_eq!(paths(rows), vec!["b.md", "a.md", "d.md", "c.md"]);
+        let rows = run_query(vault_dir, "order by priority")?;
+        assert_eq!(paths(rows), vec!["a.md", "d.md", "b.md", "c.md"]);
+
+        let rows = run_query(
+            vault_dir,
+            "select title, priority, missing where title != Beta order by title limit 2",
+        )?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].path, "a.md");
+        assert_eq!(rows[0].title, "Alpha");
+        let keys: Vec<_> = rows[0].fields.keys().filter_map(|k| k.as_str()).collect();
+        assert_eq!(keys, vec!["title", "priority", "missing"]);
+        assert_eq!(rows[0].fields["priority"], YamlValue::from(2));
+        assert_eq!(rows[0].fields["missing"], YamlValue::Null);
+        assert_eq!(rows[1].path, "d.md");
+
+        let rows = run_query(vault_dir, "title = Gamma")?;
+        assert!(rows[0].fields.contains_key("modified_at"));
+
+        let index = VaultIndex::open(vault_dir)?;
+        let rows = run_query_indexed(&index, "order by modified_at limit 1")?;
+        assert_eq!(paths(rows), vec!["b.md"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diagnose_query() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("task.md"),
+            "---\ntitle: Task\nstatus: open\nproject:\n  name: core\n---\n",
+        )?;
+
+        let query = "staus = open and project.name = core order by priorty";
+        let problems = diagnose_query(vault_dir, query)?;
+        assert_eq!(problems.len(), 2);
+        assert_eq!(&query[problems[0].span.clone()], "staus");
+        assert_eq!(problems[0].hint.as_deref(), Some("did you mean 'status'?"));
+        assert_eq!(&query[problems[1].span.clone()], "priorty");
+        assert_eq!(problems[1].hint, None);
+
+        assert!(diagnose_query(vault_dir, "status = open and tag = x")?.is_empty());
+
+        let problems = diagnose_query(vault_dir, "status = open limit")?;
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].span, 19..19);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_aggregate_query() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        for (file, note_type, created_on, tags, words) in [
+            ("a.md", "meeting", "2025-02-27", "[rust, cli]", 100),
+            ("b.md", "meeting", "2025-03-01", "[rust]", 300),
+            ("c.md", "journal", "2025-03-02", "[]", 50),
+            ("d.md", "meeting", "2025-03-15", "[cli]", 200),
+        ] {
+            fs::write(
+                vault_dir.join(file),
+                format!(
+                    "---\ntitle: {}\ntype: {}\ncreated_on: {}\ntags: {}\nwords: {}\n---\n",
+                    file, note_type, created_on, tags, words
+                ),
+            )?;
+        }
+
+        let rows = run_aggregate_query(vault_dir, "group by tag")?;
+        let counts: Vec<(YamlValue, YamlValue)> = rows
+            .iter()
+            .map(|row| (row.group[0].clone(), row.values["count()"].clone()))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (YamlValue::from("cli"), YamlValue::from(2)),
+                (YamlValue::from("rust"), YamlValue::from(2)),
+                (YamlValue::Null, YamlValue::from(1)),
+            ]
+        );
+
+        let rows = run_aggregate_query(
+            vault_dir,
+            "select type, month(created_on), count(), min(created_on), max(created_on), avg(words) \
+             where type = meeting group by type, month(created_on) order by count() desc",
+        )?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0].group,
+            vec![YamlValue::from("meeting"), YamlValue::from("2025-03")]
+        );
+        let keys: Vec<_> = rows[0].values.keys().filter_map(|k| k.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "type",
+                "month(created_on)",
+                "count()",
+                "min(created_on)",
+                "max(created_on)",
+                "avg(words)"
+            ]
+        );
+        assert_eq!(rows[0].values["count()"], YamlValue::from(2));
+        assert_eq!(
+            rows[0].values["min(created_on)"],
+            YamlValue::from("2025-03-01")
+        );
+        assert_eq!(
+            rows[0].values["max(created_on)"],
+            YamlValue::from("2025-03-15")
+        );
+        assert_eq!(rows[0].values["avg(words)"], YamlValue::from(250.0));
+        assert_eq!(
+            rows[1].values["month(created_on)"],
+            YamlValue::from("2025-02")
+        );
+
+        let rows = run_aggregate_query(vault_dir, "select count(), count(tags) where words > 60")?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values["count()"], YamlValue::from(3));
+        assert_eq!(rows[0].values["count(tags)"], YamlValue::from(3));
+
+        let rows = run_aggregate_query(vault_dir, "group by week(created_on) limit 1 offset 1")?;
+        assert_eq!(rows[0].group, vec![YamlValue::from("2025-W11")]);
+
+        assert!(run_query(vault_dir, "group by tag").is_err());
+        assert!(run_aggregate_query(vault_dir, "tag = rust").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_relative_dates() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        // Friday 2025-03-14
+        let clock = FixedClock::on(NaiveDate::from_ymd_opt(2025, 3, 14).unwrap());
+        for (file, date) in [
+            ("today.md", "2025-03-14 09:30:00"),
+            ("monday.md", "2025-03-10"),
+            ("last-week.md", "2025-03-05"),
+            ("february.md", "2025-02-20"),
+            ("last-year.md", "2024-12-31"),
+        ] {
+            create_markdown_note(vault_dir, file, file, date, &[])?;
+        }
+
+        let run = |query: &str| -> Result<Vec<String>> {
+            Ok(run_query_with_clock(vault_dir, query, &clock)?
+                .into_iter()
+                .map(|row| row.path)
+                .collect())
+        };
+
+        assert_eq!(run("date = today")?, vec!["today.md"]);
+        assert_eq!(run("date >= -7d")?, vec!["monday.md", "today.md"]);
+        assert_eq!(run("date = this_week")?, vec!["monday.md", "today.md"]);
+        assert_eq!(run("date = last_week")?, vec!["last-week.md"]);
+        assert_eq!(run("date = last_month")?, vec!["february.md"]);
+        assert_eq!(run("date < start_of_year")?, vec!["last-year.md"]);
+        assert_eq!(
+            run("date <= last_month order by date desc")?,
+            vec!["february.md", "last-year.md"]
+        );
+        assert_eq!(run("date > end_of_month")?, Vec::<String>::new());
+        // Quoted literals are still plain strings.
+        assert!(run(r#"date = "today""#)?.is_empty());
+
+        let rows = run_aggregate_query_with_clock(
+            vault_dir,
+            "select count() where date != this_year",
+            &clock,
+        )?;
+        assert_eq!(rows[0].values["count()"], YamlValue::from(1));
+
+        // The indexed variants resolve relative dates against the same clock.
+        let index = VaultIndex::open(vault_dir)?;
+        let paths: Vec<String> = run_query_indexed_with_clock(&index, "date >= -7d", &clock)?
+            .into_iter()
+            .map(|row| row.path)
+            .collect();
+        assert_eq!(paths, vec!["monday.md", "today.md"]);
+        let rows = run_aggregate_query_indexed_with_clock(
+            &index,
+            "select count() where date != this_year",
+            &clock,
+        )?;
+        assert_eq!(rows[0].values["count()"], YamlValue::from(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_tag_policy() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        create_markdown_note(vault_dir, "upper.md", "Upper", "2025-03-01", &["CLI"])?;
+        create_markdown_note(
+            vault_dir,
+            "alias.md",
+            "Alias",
+            "2025-03-02",
+            &["command_line/zsh"],
+        )?;
+        create_markdown_note(vault_dir, "other.md", "Other", "2025-03-03", &["rust"])?;
+
+        let run = |query: &str| -> Result<Vec<String>> {
+            let mut paths: Vec<String> = run_query(vault_dir, query)?
+                .into_iter()
+                .map(|row| row.path)
+                .collect();
+            let compiled: Vec<String> = crate::query_compiler::CompiledQuery::compile(query)?
+                .run(vault_dir)?
+                .into_iter()
+                .map(|row| row.path)
+                .collect();
+            assert_eq!(paths, compiled);
+            paths.sort();
+            Ok(paths)
+        };
+
+        // Without a policy, tags are compared as written.
+        assert_eq!(run("tag = cli")?, Vec::<String>::new());
+
+        fs::write(
+            vault_dir.join(crate::tags::TAG_POLICY_FILE),
+            "fold_case: true\nnormalize_separators: true\naliases:\n  cli: [command-line]\n",
+        )?;
+        assert_eq!(run("tag = cli")?, vec!["upper.md"]);
+        assert_eq!(run(r#"tag = "CLI/zsh""#)?, vec!["alias.md"]);
+        assert_eq!(
+            run(r#"tag in ("Command Line/zsh", Rust)"#)?,
+            vec!["alias.md", "other.md"]
+        );
+        assert_eq!(run("tag != cli")?, vec!["alias.md", "other.md"]);
+        assert_eq!(run("tag starts_with cli")?, vec!["alias.md", "upper.md"]);
+        Ok(())
+    }
 }
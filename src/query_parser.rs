@@ -1,7 +1,12 @@
 // src/query_parser.rs
 
-use anyhow::{Result, anyhow};
-use std::iter::Peekable;
+use crate::utils::closest_match;
+use anyhow::{Context, Result, anyhow};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+use std::ops::Range;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
@@ -13,91 +18,984 @@ pub enum Token {
     Not,
     LParen,
     RParen,
+    Comma,
 }
 
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(s) | Token::Operator(s) => write!(f, "'{}'", s),
+            Token::StringLiteral(s) => write!(f, "\"{}\"", s),
+            Token::And => write!(f, "'and'"),
+            Token::Or => write!(f, "'or'"),
+            Token::Not => write!(f, "'not'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::Comma => write!(f, "','"),
+        }
+    }
+}
+
+/// A token together with the byte range it covers in the query.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Range<usize>,
+}
+
+/// A query that can't be parsed, located in the query text.
+///
+/// The query functions return it inside an `anyhow::Error`; use
+/// `error.downcast_ref::<QueryError>()` to get at the span, e.g. to underline the broken
+/// part of the query in an editor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    /// The byte range of the offending input. At the end of the query, this is the
+    /// empty range after the last character.
+    pub span: Range<usize>,
+    /// What the parser would have accepted at this position, like `'='` or `field name`.
+    pub expected: Vec<String>,
+    /// A suggestion for fixing the query, like `did you mean 'contains'?`.
+    pub hint: Option<String>,
+}
+
+impl QueryError {
+    pub fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        QueryError {
+            message: message.into(),
+            span,
+            expected: Vec::new(),
+            hint: None,
+        }
+    }
+
+    pub fn with_expected(mut self, expected: &[&str]) -> Self {
+        self.expected = expected.iter().map(|e| e.to_string()).collect();
+        self
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for QueryError {}
+
 fn is_operator_char(c: char) -> bool {
-    matches!(c, '=' | '!' | '>' | '<')
+    matches!(c, '=' | '!' | '>' | '<' | '~' | ':')
 }
 
+/// The symbolic comparison operators the evaluator understands.
+const SYMBOL_OPERATORS: [&str; 10] = ["=", "==", "!=", ">", ">=", "<", "<=", "~", "~*", ":"];
+
+/// Operators written as words. They are only treated as operators right after a
+/// field name, so they can still be used as field names themselves.
+const KEYWORD_OPERATORS: [&str; 9] = [
+    "contains",
+    "icontains",
+    "starts_with",
+    "istarts_with",
+    "ends_with",
+    "iends_with",
+    "glob",
+    "iglob",
+    "in",
+];
+
 /// Tokenizes the input DSL query string.
-pub fn tokenize(input: &str) -> Result<Vec<Token>> {
+///
+/// # Errors
+/// * Returns a `QueryError` for an unterminated string literal or a character that
+///   can't start a token
+pub fn tokenize(input: &str) -> Result<Vec<SpannedToken>> {
     let mut tokens = Vec::new();
-    let mut chars = input.chars().peekable();
+    let mut chars = input.char_indices().peekable();
 
-    while let Some(&c) = chars.peek() {
-        if c.is_whitespace() {
+    while let Some(&(start, c)) = chars.peek() {
+        let token = if c.is_whitespace() {
             chars.next();
+            continue;
         } else if c == '(' {
-            tokens.push(Token::LParen);
             chars.next();
+            Token::LParen
         } else if c == ')' {
-            tokens.push(Token::RParen);
             chars.next();
+            Token::RParen
+        } else if c == ',' {
+            chars.next();
+            Token::Comma
         } else if c == '"' {
             chars.next(); // skip opening quote
             let mut literal = String::new();
-            while let Some(&ch) = chars.peek() {
+            let mut terminated = false;
+            for (_, ch) in chars.by_ref() {
                 if ch == '"' {
-                    chars.next(); // skip closing quote
+                    terminated = true;
                     break;
-                } else {
-                    literal.push(ch);
-                    chars.next();
                 }
+                literal.push(ch);
             }
-            tokens.push(Token::StringLiteral(literal));
+            if !terminated {
+                return Err(
+                    QueryError::new("Unterminated string literal", start..input.len())
+                        .with_expected(&["'\"'"])
+                        .into(),
+                );
+            }
+            Token::StringLiteral(literal)
         } else if is_operator_char(c) {
             let mut op = String::new();
             op.push(c);
             chars.next();
-            if let Some(&next_ch) = chars.peek() {
-                if is_operator_char(next_ch) {
-                    op.push(next_ch);
-                    chars.next();
-                }
+            if let Some(&(_, next_ch)) = chars.peek()
+                && (is_operator_char(next_ch) || (c == '~' && next_ch == '*'))
+            {
+                op.push(next_ch);
+                chars.next();
             }
-            tokens.push(Token::Operator(op));
+            Token::Operator(op)
         } else {
             let mut ident = String::new();
-            while let Some(&ch) = chars.peek() {
-                if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+            while let Some(&(_, ch)) = chars.peek() {
+                // Dots allow nested field paths (`project.status`) and decimal numbers,
+                // and signs relative dates like `-7d`.
+                if ch.is_alphanumeric() || matches!(ch, '_' | '-' | '+' | '.') {
                     ident.push(ch);
                     chars.next();
                 } else {
                     break;
                 }
             }
+            if ident.is_empty() {
+                return Err(QueryError::new(
+                    format!("Unexpected character '{}'", c),
+                    start..start + c.len_utf8(),
+                )
+                .into());
+            }
             match ident.to_lowercase().as_str() {
-                "and" => tokens.push(Token::And),
-                "or" => tokens.push(Token::Or),
-                "not" => tokens.push(Token::Not),
-                _ => tokens.push(Token::Identifier(ident)),
+                "and" => Token::And,
+                "or" => Token::Or,
+                "not" => Token::Not,
+                _ => Token::Identifier(ident),
             }
-        }
+        };
+        let end = chars.peek().map_or(input.len(), |&(i, _)| i);
+        tokens.push(SpannedToken {
+            token,
+            span: start..end,
+        });
     }
     Ok(tokens)
 }
 
-#[derive(Debug)]
+/// A literal value on the right-hand side of a condition.
+///
+/// Quoted values are always strings. Unquoted values are read as booleans
+/// (`true`/`false`) or numbers when possible, and as strings otherwise. Strings that
+/// look like dates or numbers are still compared as such when the field is a date
+/// or a number.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    /// The parenthesized values of an `in` condition
+    List(Vec<Value>),
+    /// The compiled pattern of a `~`, `~*`, `glob` or `iglob` condition
+    Pattern(Pattern),
+    /// A date relative to the day the query runs, like `today` or `last_month`
+    Relative(RelativeDate),
+}
+
+/// A date or calendar period relative to the day a query runs.
+///
+/// Every relative date covers a range of days, compared like a date: `=` means within
+/// the range, `<` before its first day, `>` after its last day, and `<=`/`>=` include
+/// it. Weeks start on Monday.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RelativeDate {
+    /// A day counted from today: `today`, `yesterday`, `tomorrow`, or an offset like
+    /// `-7d`, `+2w`, `-1m` or `-1y`
+    Offset { amount: i64, unit: DateUnit },
+    /// A whole calendar period: `this_week`, `last_month`, `next_year`, ...
+    Period { offset: i64, unit: DateUnit },
+    /// The first day of the current period: `start_of_week`, `start_of_month`, ...
+    StartOf(DateUnit),
+    /// The last day of the current period: `end_of_week`, `end_of_month`, ...
+    EndOf(DateUnit),
+}
+
+static RELATIVE_OFFSET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([+-]\d+)([dwmy])$").expect("valid offset regex"));
+
+impl RelativeDate {
+    /// Reads a relative date literal, or returns None if the literal isn't one.
+    pub fn parse(literal: &str) -> Option<Self> {
+        let literal = literal.to_lowercase();
+        let unit_named = |name: &str| DateUnit::ALL.into_iter().find(|u| u.name() == name);
+        match literal.as_str() {
+            "today" => return Some(Self::day_offset(0)),
+            "yesterday" => return Some(Self::day_offset(-1)),
+            "tomorrow" => return Some(Self::day_offset(1)),
+            _ => {}
+        }
+        if let Some(captures) = RELATIVE_OFFSET_RE.captures(&literal) {
+            let amount = captures[1].parse().ok()?;
+            let unit = match &captures[2] {
+                "d" => DateUnit::Day,
+                "w" => DateUnit::Week,
+                "m" => DateUnit::Month,
+                _ => DateUnit::Year,
+            };
+            return Some(RelativeDate::Offset { amount, unit });
+        }
+        let (prefix, unit) = literal.rsplit_once('_')?;
+        let unit = unit_named(unit).filter(|unit| *unit != DateUnit::Day)?;
+        match prefix {
+            "this" => Some(RelativeDate::Period { offset: 0, unit }),
+            "last" => Some(RelativeDate::Period { offset: -1, unit }),
+            "next" => Some(RelativeDate::Period { offset: 1, unit }),
+            "start_of" => Some(RelativeDate::StartOf(unit)),
+            "end_of" => Some(RelativeDate::EndOf(unit)),
+            _ => None,
+        }
+    }
+
+    fn day_offset(amount: i64) -> Self {
+        RelativeDate::Offset {
+            amount,
+            unit: DateUnit::Day,
+        }
+    }
+
+    /// Returns the first and last day covered by the relative date, for a query run on
+    /// `today`. Offsets that leave the calendar range saturate at its ends.
+    pub fn resolve(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match *self {
+            RelativeDate::Offset { amount, unit } => {
+                let day = shift(today, amount, unit);
+                (day, day)
+            }
+            RelativeDate::Period { offset, unit } => {
+                let start = shift(period_start(today, unit), offset, unit);
+                let end = shift(start, 1, unit).pred_opt().unwrap_or(start);
+                (start, end)
+            }
+            RelativeDate::StartOf(unit) => {
+                let start = period_start(today, unit);
+                (start, start)
+            }
+            RelativeDate::EndOf(unit) => {
+                let start = period_start(today, unit);
+                let end = shift(start, 1, unit).pred_opt().unwrap_or(start);
+                (end, end)
+            }
+        }
+    }
+}
+
+/// Helper function to move a date by a number of units.
+fn shift(date: NaiveDate, amount: i64, unit: DateUnit) -> NaiveDate {
+    let months = |count: i64| {
+        let magnitude = Months::new(u32::try_from(count.unsigned_abs()).unwrap_or(u32::MAX));
+        if count >= 0 {
+            date.checked_add_months(magnitude)
+        } else {
+            date.checked_sub_months(magnitude)
+        }
+    };
+    let shifted = match unit {
+        DateUnit::Day => date.checked_add_signed(Duration::days(amount)),
+        DateUnit::Week => date.checked_add_signed(Duration::weeks(amount)),
+        DateUnit::Month => months(amount),
+        DateUnit::Year => months(amount.saturating_mul(12)),
+    };
+    shifted.unwrap_or(if amount < 0 {
+        NaiveDate::MIN
+    } else {
+        NaiveDate::MAX
+    })
+}
+
+/// Helper function to get the first day of the period containing a date.
+fn period_start(date: NaiveDate, unit: DateUnit) -> NaiveDate {
+    match unit {
+        DateUnit::Day => Some(date),
+        DateUnit::Week => {
+            date.checked_sub_signed(Duration::days(date.weekday().num_days_from_monday().into()))
+        }
+        DateUnit::Month => date.with_day(1),
+        DateUnit::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1),
+    }
+    .unwrap_or(date)
+}
+
+impl fmt::Display for RelativeDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let short_unit = |unit: DateUnit| &unit.name()[..1];
+        match *self {
+            RelativeDate::Offset {
+                amount: 0,
+                unit: DateUnit::Day,
+            } => write!(f, "today"),
+            RelativeDate::Offset {
+                amount: -1,
+                unit: DateUnit::Day,
+            } => write!(f, "yesterday"),
+            RelativeDate::Offset {
+                amount: 1,
+                unit: DateUnit::Day,
+            } => write!(f, "tomorrow"),
+            RelativeDate::Offset { amount, unit } => {
+                write!(f, "{:+}{}", amount, short_unit(unit))
+            }
+            RelativeDate::Period { offset, unit } => {
+                let prefix = match offset {
+                    0 => "this",
+                    o if o < 0 => "last",
+                    _ => "next",
+                };
+                write!(f, "{}_{}", prefix, unit.name())
+            }
+            RelativeDate::StartOf(unit) => write!(f, "start_of_{}", unit.name()),
+            RelativeDate::EndOf(unit) => write!(f, "end_of_{}", unit.name()),
+        }
+    }
+}
+
+/// A regular expression from a `~`/`~*` condition, or a glob converted into one.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Compiles a regular expression.
+    ///
+    /// # Errors
+    /// * Returns an error if the pattern is not a valid regular expression
+    pub fn regex(source: &str, case_insensitive: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(source)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("Invalid regular expression: {}", source))?;
+        Ok(Pattern {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    /// Compiles a glob like `projects/**/*.md`.
+    ///
+    /// `*` matches within a path segment, `**` across segments, `?` a single character,
+    /// `[abc]` a character class and `{a,b}` alternatives. The glob must match the
+    /// whole value.
+    ///
+    /// # Errors
+    /// * Returns an error if the glob has an unbalanced `[` or `{`
+    pub fn glob(source: &str, case_insensitive: bool) -> Result<Self> {
+        let regex = RegexBuilder::new(&glob_to_regex(source)?)
+            .case_insensitive(case_insensitive)
+            .build()
+            .with_context(|| format!("Invalid glob pattern: {}", source))?;
+        Ok(Pattern {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    /// Returns the pattern as written in the query.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns true if the pattern matches the text.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+/// Helper function to convert a glob into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> Result<String> {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    let mut in_alternatives = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    // `**/` matches zero or more directories.
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                let mut class = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(ch @ ('\\' | '[')) => {
+                            class.push('\\');
+                            class.push(ch);
+                        }
+                        Some(ch) => class.push(ch),
+                        None => return Err(anyhow!("Unclosed '[' in glob pattern: {}", glob)),
+                    }
+                }
+                let class = match class.strip_prefix('!') {
+                    Some(negated) => format!("^{}", negated),
+                    None => class,
+                };
+                regex.push_str(&format!("[{}]", class));
+            }
+            '{' if !in_alternatives => {
+                in_alternatives = true;
+                regex.push_str("(?:");
+            }
+            ',' if in_alternatives => regex.push('|'),
+            '}' if in_alternatives => {
+                in_alternatives = false;
+                regex.push(')');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if in_alternatives {
+        return Err(anyhow!("Unclosed '{{' in glob pattern: {}", glob));
+    }
+
+    regex.push('$');
+    Ok(regex)
+}
+
+impl Value {
+    /// Reads an unquoted literal as a boolean, relative date, number or string. Quote
+    /// a value like `today` to compare it as a string.
+    pub fn from_unquoted(literal: &str) -> Self {
+        if let Some(relative) = RelativeDate::parse(literal) {
+            return Value::Relative(relative);
+        }
+        match literal.to_lowercase().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => literal
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .map_or_else(|| Value::String(literal.to_string()), Value::Number),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::List(values) => {
+                let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "({})", items.join(", "))
+            }
+            Value::Pattern(pattern) => write!(f, "{}", pattern.source()),
+            Value::Relative(relative) => write!(f, "{}", relative),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Expr {
+    /// A comparison of a frontmatter field, e.g. `project.status = "active"` or
+    /// `title contains "meeting"`. The field may be a dotted path into nested mappings.
+    /// Keyword operators are stored in lowercase.
     Condition {
         field: String,
         op: String,
-        value: String,
+        value: Value,
     },
     And(Box<Expr>, Box<Expr>),
     Or(Box<Expr>, Box<Expr>),
     Not(Box<Expr>),
 }
 
-struct Parser<I: Iterator<Item = Token>> {
-    tokens: Peekable<I>,
+/// A full DSL statement: a filter plus the clauses shaping its results.
+#[derive(Debug, Default)]
+pub struct Query {
+    /// The columns of the `select` projection, in order. Empty without `select`.
+    pub select: Vec<Column>,
+    /// The filter condition. `None` matches every note.
+    pub filter: Option<Expr>,
+    /// The `group by` columns, in order. Empty without `group by`.
+    pub group_by: Vec<Column>,
+    /// The `order by` keys, in order of precedence.
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// Every field name the statement refers to, with its byte span, in source order.
+    pub fields: Vec<(String, Range<usize>)>,
+}
+
+impl Query {
+    /// Returns true if the statement is a filter without any other clause.
+    pub fn is_plain_filter(&self) -> bool {
+        self.filter.is_some()
+            && self.select.is_empty()
+            && self.group_by.is_empty()
+            && self.order_by.is_empty()
+            && self.limit.is_none()
+            && self.offset.is_none()
+    }
+
+    /// Returns true if the statement returns groups of notes rather than notes, because
+    /// it has a `group by` clause or selects an aggregate like `count()`.
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty() || self.select.iter().any(Column::is_aggregate)
+    }
 }
 
-impl<I: Iterator<Item = Token>> Parser<I> {
-    fn new(tokens: I) -> Self {
-        Parser {
-            tokens: tokens.peekable(),
+/// A column of a `select`, `group by` or `order by` clause.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// A field, as in conditions (`tag`, `project.status`)
+    Field(String),
+    /// The date of a field truncated to a unit, e.g. `month(created_on)`
+    Bucket { unit: DateUnit, field: String },
+    /// An aggregate over the notes of a group, e.g. `count()` or `max(modified_at)`
+    Aggregate {
+        function: AggregateFunction,
+        field: Option<String>,
+    },
+}
+
+impl Column {
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Column::Aggregate { .. })
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::Field(field) => write!(f, "{}", field),
+            Column::Bucket { unit, field } => write!(f, "{}({})", unit.name(), field),
+            Column::Aggregate { function, field } => {
+                write!(f, "{}({})", function.name(), field.as_deref().unwrap_or(""))
+            }
+        }
+    }
+}
+
+/// The unit of a date bucket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateUnit {
+    /// `2025`
+    Year,
+    /// `2025-03`
+    Month,
+    /// The ISO week, `2025-W09`
+    Week,
+    /// `2025-03-01`
+    Day,
+}
+
+impl DateUnit {
+    const ALL: [DateUnit; 4] = [
+        DateUnit::Year,
+        DateUnit::Month,
+        DateUnit::Week,
+        DateUnit::Day,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DateUnit::Year => "year",
+            DateUnit::Month => "month",
+            DateUnit::Week => "week",
+            DateUnit::Day => "day",
+        }
+    }
+}
+
+/// An aggregate function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFunction {
+    /// `count()` counts the notes of a group; `count(field)` those that have the field.
+    Count,
+    /// The smallest value, compared like in conditions (dates as dates, numbers as numbers)
+    Min,
+    /// The largest value, compared like in conditions
+    Max,
+    /// The mean of the values that are numbers
+    Avg,
+}
+
+impl AggregateFunction {
+    const ALL: [AggregateFunction; 4] = [
+        AggregateFunction::Count,
+        AggregateFunction::Min,
+        AggregateFunction::Max,
+        AggregateFunction::Avg,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFunction::Count => "count",
+            AggregateFunction::Min => "min",
+            AggregateFunction::Max => "max",
+            AggregateFunction::Avg => "avg",
+        }
+    }
+}
+
+/// One key of an `order by` clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub column: Column,
+    pub descending: bool,
+}
+
+/// Words that start a clause of a statement (`select`, `where`, `group by`, `order by`,
+/// `limit`, `offset`), or give the direction of an `order by` key.
+const CLAUSE_KEYWORDS: [&str; 8] = [
+    "select", "where", "group", "order", "limit", "offset", "asc", "desc",
+];
+
+struct Parser {
+    tokens: Vec<SpannedToken>,
+    position: usize,
+    /// The length of the query, where errors at the end of the input point to.
+    end: usize,
+    /// The field names parsed so far, with their spans.
+    fields: Vec<(String, Range<usize>)>,
+}
+
+impl Parser {
+    fn new(input: &str) -> Result<Self> {
+        Ok(Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            end: input.len(),
+            fields: Vec::new(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.position + n).map(|t| &t.token)
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    /// Returns the span of the next token, or the empty span at the end of the query.
+    fn span(&self) -> Range<usize> {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end..self.end, |t| t.span.clone())
+    }
+
+    /// Builds the error for an unexpected next token. If the token is a word or an
+    /// operator close to one of the expected ones, the error suggests it.
+    fn unexpected(&self, expected: &[&str]) -> anyhow::Error {
+        let found = self
+            .peek()
+            .map_or_else(|| "end of query".to_string(), |t| t.to_string());
+        let mut error = QueryError::new(
+            format!("Expected {}, found {}", describe_expected(expected), found),
+            self.span(),
+        )
+        .with_expected(expected);
+        if let Some(Token::Identifier(word) | Token::Operator(word)) = self.peek() {
+            let candidates = expected
+                .iter()
+                .filter_map(|e| e.strip_prefix('\'')?.strip_suffix('\''));
+            if let Some(suggestion) = closest_match(word, candidates) {
+                error = error.with_hint(format!("did you mean '{}'?", suggestion));
+            }
+        }
+        error.into()
+    }
+
+    fn parse_statement(&mut self) -> Result<Query> {
+        let mut query = Query::default();
+        let mut select_spans = Vec::new();
+        if self.peek_clause("select") {
+            self.advance();
+            (query.select, select_spans) = self.parse_column_list("select")?;
+            if self.peek_clause("where") {
+                self.advance();
+            }
+        }
+        let mut expected = vec![
+            "'group by'",
+            "'order by'",
+            "'limit'",
+            "'offset'",
+            "end of query",
+        ];
+        if !self.at_clauses_end() {
+            query.filter = Some(self.parse_expression()?);
+            expected.splice(0..0, ["'and'", "'or'"]);
+        } else if query.select.is_empty() {
+            expected.insert(0, "field name");
+        }
+        if self.peek_clause("group") {
+            self.advance();
+            self.expect_by()?;
+            let (group_by, spans) = self.parse_column_list("group by")?;
+            if let Some((_, span)) = group_by.iter().zip(spans).find(|(c, _)| c.is_aggregate()) {
+                return Err(QueryError::new("Aggregates can't be used in 'group by'", span).into());
+            }
+            query.group_by = group_by;
+            expected = vec!["','", "'order by'", "'limit'", "'offset'", "end of query"];
+        }
+        let mut order_spans = Vec::new();
+        if self.peek_clause("order") {
+            self.advance();
+            self.expect_by()?;
+            loop {
+                let start = self.span().start;
+                let column = self.parse_column("order by")?;
+                order_spans.push(start..self.previous_end());
+                let descending = if self.peek_clause("desc") {
+                    self.advance();
+                    true
+                } else {
+                    if self.peek_clause("asc") {
+                        self.advance();
+                    }
+                    false
+                };
+                query.order_by.push(OrderBy { column, descending });
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.advance();
+            }
+            expected = vec!["','", "'limit'", "'offset'", "end of query"];
+        }
+        if self.peek_clause("limit") {
+            self.advance();
+            query.limit = Some(self.parse_count("limit")?);
+            expected = vec!["'offset'", "end of query"];
+        }
+        if self.peek_clause("offset") {
+            self.advance();
+            query.offset = Some(self.parse_count("offset")?);
+            expected = vec!["end of query"];
+        }
+        if self.peek().is_some() {
+            return Err(self.unexpected(&expected));
+        }
+
+        if query.is_aggregate() {
+            // Like SQL, plain columns of a grouped statement must be group keys.
+            for (column, span) in query.select.iter().zip(select_spans) {
+                if !column.is_aggregate() && !query.group_by.contains(column) {
+                    return Err(QueryError::new(
+                        format!(
+                            "'{}' must be in 'group by' or inside an aggregate like count()",
+                            column
+                        ),
+                        span,
+                    )
+                    .into());
+                }
+            }
+            for (order, span) in query.order_by.iter().zip(order_spans) {
+                if !query.group_by.contains(&order.column) && !query.select.contains(&order.column)
+                {
+                    return Err(QueryError::new(
+                        format!(
+                            "'{}' must be in 'group by' or 'select' to order groups by it",
+                            order.column
+                        ),
+                        span,
+                    )
+                    .into());
+                }
+            }
+        } else if let Some((_, span)) = query
+            .order_by
+            .iter()
+            .zip(order_spans)
+            .find(|(order, _)| order.column.is_aggregate())
+        {
+            return Err(QueryError::new(
+                "Aggregates can only be ordered by in a grouped statement",
+                span,
+            )
+            .into());
+        }
+
+        query.fields = std::mem::take(&mut self.fields);
+        Ok(query)
+    }
+
+    fn expect_by(&mut self) -> Result<()> {
+        match self.peek() {
+            Some(Token::Identifier(s)) if s.eq_ignore_ascii_case("by") => {
+                self.advance();
+                Ok(())
+            }
+            _ => Err(self.unexpected(&["'by'"])),
+        }
+    }
+
+    /// Returns the end of the last consumed token.
+    fn previous_end(&self) -> usize {
+        self.position
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map_or(0, |t| t.span.end)
+    }
+
+    /// Returns true if the next token is the given clause keyword. A keyword followed
+    /// by an operator is a field name instead (`limit > 3`).
+    fn peek_clause(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Identifier(s)) if s.eq_ignore_ascii_case(keyword) => {}
+            _ => return false,
+        }
+        match self.peek_nth(1) {
+            Some(Token::Operator(_)) => false,
+            Some(Token::Identifier(s)) => !KEYWORD_OPERATORS.contains(&s.to_lowercase().as_str()),
+            _ => true,
+        }
+    }
+
+    /// Returns true if there is no filter before the remaining clauses.
+    fn at_clauses_end(&self) -> bool {
+        self.peek().is_none()
+            || ["group", "order", "limit", "offset"]
+                .iter()
+                .any(|keyword| self.peek_clause(keyword))
+    }
+
+    fn parse_field_name(&mut self, clause: &str) -> Result<String> {
+        match self.peek() {
+            Some(Token::Identifier(s)) if !CLAUSE_KEYWORDS.contains(&s.to_lowercase().as_str()) => {
+                let field = s.clone();
+                self.fields.push((field.clone(), self.span()));
+                self.advance();
+                Ok(field)
+            }
+            _ => Err(self.unexpected(&[&format!("field name in '{}'", clause)])),
+        }
+    }
+
+    /// Parses a field, a date bucket like `month(created_on)` or an aggregate like
+    /// `count()`.
+    fn parse_column(&mut self, clause: &str) -> Result<Column> {
+        if self.peek_nth(1) != Some(&Token::LParen) {
+            return self.parse_field_name(clause).map(Column::Field);
+        }
+        let name = match self.peek() {
+            Some(Token::Identifier(s)) => s.to_lowercase(),
+            _ => return Err(self.unexpected(&[&format!("field name in '{}'", clause)])),
+        };
+        let aggregate = AggregateFunction::ALL
+            .into_iter()
+            .find(|f| f.name() == name);
+        let unit = DateUnit::ALL.into_iter().find(|u| u.name() == name);
+        let column = match (aggregate, unit) {
+            (Some(function), _) => {
+                self.advance(); // the function name
+                self.advance(); // '('
+                let field = if function == AggregateFunction::Count
+                    && self.peek() == Some(&Token::RParen)
+                {
+                    None
+                } else {
+                    Some(self.parse_field_name(clause)?)
+                };
+                Column::Aggregate { function, field }
+            }
+            (None, Some(unit)) => {
+                self.advance(); // the function name
+                self.advance(); // '('
+                Column::Bucket {
+                    unit,
+                    field: self.parse_field_name(clause)?,
+                }
+            }
+            (None, None) => {
+                let function_names: Vec<String> = AggregateFunction::ALL
+                    .iter()
+                    .map(|f| format!("'{}'", f.name()))
+                    .chain(DateUnit::ALL.iter().map(|u| format!("'{}'", u.name())))
+                    .collect();
+                let expected: Vec<&str> = function_names.iter().map(String::as_str).collect();
+                return Err(self.unexpected(&expected));
+            }
+        };
+        match self.peek() {
+            Some(Token::RParen) => self.advance(),
+            _ => return Err(self.unexpected(&["')'"])),
+        }
+        Ok(column)
+    }
+
+    /// Parses a comma-separated list of columns, with the span of each column.
+    fn parse_column_list(&mut self, clause: &str) -> Result<(Vec<Column>, Vec<Range<usize>>)> {
+        let mut columns = Vec::new();
+        let mut spans = Vec::new();
+        loop {
+            let start = self.span().start;
+            columns.push(self.parse_column(clause)?);
+            spans.push(start..self.previous_end());
+            if self.peek() != Some(&Token::Comma) {
+                return Ok((columns, spans));
+            }
+            self.advance();
+        }
+    }
+
+    fn parse_count(&mut self, clause: &str) -> Result<usize> {
+        let count = match self.peek() {
+            Some(Token::Identifier(s)) => s.parse::<usize>().ok(),
+            _ => None,
+        };
+        match count {
+            Some(count) => {
+                self.advance();
+                Ok(count)
+            }
+            None => Err(self.unexpected(&[&format!("a non-negative integer after '{}'", clause)])),
         }
     }
 
@@ -107,8 +1005,8 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
     fn parse_or(&mut self) -> Result<Expr> {
         let mut expr = self.parse_and()?;
-        while let Some(Token::Or) = self.tokens.peek() {
-            self.tokens.next(); // consume 'or'
+        while let Some(Token::Or) = self.peek() {
+            self.advance(); // consume 'or'
             let rhs = self.parse_and()?;
             expr = Expr::Or(Box::new(expr), Box::new(rhs));
         }
@@ -117,8 +1015,8 @@ impl<I: Iterator<Item = Token>> Parser<I> {
 
     fn parse_and(&mut self) -> Result<Expr> {
         let mut expr = self.parse_unary()?;
-        while let Some(Token::And) = self.tokens.peek() {
-            self.tokens.next(); // consume 'and'
+        while let Some(Token::And) = self.peek() {
+            self.advance(); // consume 'and'
             let rhs = self.parse_unary()?;
             expr = Expr::And(Box::new(expr), Box::new(rhs));
         }
@@ -126,8 +1024,8 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     }
 
     fn parse_unary(&mut self) -> Result<Expr> {
-        if let Some(Token::Not) = self.tokens.peek() {
-            self.tokens.next();
+        if let Some(Token::Not) = self.peek() {
+            self.advance();
             let expr = self.parse_unary()?;
             Ok(Expr::Not(Box::new(expr)))
         } else {
@@ -136,12 +1034,15 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     }
 
     fn parse_primary(&mut self) -> Result<Expr> {
-        if let Some(Token::LParen) = self.tokens.peek() {
-            self.tokens.next(); // consume '('
+        if let Some(Token::LParen) = self.peek() {
+            self.advance(); // consume '('
             let expr = self.parse_expression()?;
-            match self.tokens.next() {
-                Some(Token::RParen) => Ok(expr),
-                _ => Err(anyhow!("Expected closing parenthesis")),
+            match self.peek() {
+                Some(Token::RParen) => {
+                    self.advance();
+                    Ok(expr)
+                }
+                _ => Err(self.unexpected(&["')'", "'and'", "'or'"])),
             }
         } else {
             self.parse_condition()
@@ -149,77 +1050,542 @@ impl<I: Iterator<Item = Token>> Parser<I> {
     }
 
     fn parse_condition(&mut self) -> Result<Expr> {
-        let field = match self.tokens.next() {
-            Some(Token::Identifier(s)) => s,
-            other => return Err(anyhow!("Expected field name, found {:?}", other)),
+        let field = match self.peek() {
+            Some(Token::Identifier(s)) => s.clone(),
+            _ => return Err(self.unexpected(&["field name", "'not'", "'('"])),
         };
-        let op = match self.tokens.next() {
-            Some(Token::Operator(s)) => s,
-            other => return Err(anyhow!("Expected operator, found {:?}", other)),
+        self.fields.push((field.clone(), self.span()));
+        self.advance();
+
+        let op = match self.peek() {
+            Some(Token::Operator(s)) if is_known_operator(s) => s.clone(),
+            Some(Token::Identifier(s))
+                if KEYWORD_OPERATORS.contains(&s.to_lowercase().as_str()) =>
+            {
+                s.to_lowercase()
+            }
+            _ => {
+                let expected: Vec<String> = SYMBOL_OPERATORS
+                    .iter()
+                    .chain(KEYWORD_OPERATORS.iter())
+                    .map(|op| format!("'{}'", op))
+                    .collect();
+                let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+                return Err(self.unexpected(&expected));
+            }
         };
-        let value = match self.tokens.next() {
-            Some(Token::Identifier(s)) => s,
-            Some(Token::StringLiteral(s)) => s,
-            other => return Err(anyhow!("Expected value, found {:?}", other)),
+        self.advance();
+
+        let value_span = self.span();
+        let value = match op.as_str() {
+            "in" => Value::List(self.parse_list()?),
+            "~" | "~*" => Pattern::regex(&self.parse_string_value(&op)?, op == "~*")
+                .map(Value::Pattern)
+                .map_err(|e| QueryError::new(format!("{:#}", e), value_span))?,
+            "glob" | "iglob" => Pattern::glob(&self.parse_string_value(&op)?, op == "iglob")
+                .map(Value::Pattern)
+                .map_err(|e| QueryError::new(format!("{:#}", e), value_span))?,
+            _ => self.parse_value()?,
         };
         Ok(Expr::Condition { field, op, value })
     }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        let value = match self.peek() {
+            Some(Token::Identifier(s)) => Value::from_unquoted(s),
+            Some(Token::StringLiteral(s)) => Value::String(s.clone()),
+            _ => return Err(self.unexpected(&["value"])),
+        };
+        self.advance();
+        Ok(value)
+    }
+
+    fn parse_string_value(&mut self, op: &str) -> Result<String> {
+        match self.peek() {
+            Some(Token::Identifier(s)) | Some(Token::StringLiteral(s)) => {
+                let pattern = s.clone();
+                self.advance();
+                Ok(pattern)
+            }
+            _ => Err(self.unexpected(&[&format!("a pattern after '{}'", op)])),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Value>> {
+        match self.peek() {
+            Some(Token::LParen) => self.advance(),
+            _ => return Err(self.unexpected(&["'('"])),
+        }
+        let mut values = vec![self.parse_value()?];
+        loop {
+            match self.peek() {
+                Some(Token::Comma) => {
+                    self.advance();
+                    values.push(self.parse_value()?);
+                }
+                Some(Token::RParen) => {
+                    self.advance();
+                    return Ok(values);
+                }
+                _ => return Err(self.unexpected(&["','", "')'"])),
+            }
+        }
+    }
+}
+
+/// Helper function to list expected tokens for an error message: "a, b or c".
+fn describe_expected(expected: &[&str]) -> String {
+    match expected {
+        [] => "nothing".to_string(),
+        [only] => only.to_string(),
+        [init @ .., last] => format!("{} or {}", init.join(", "), last),
+    }
+}
+
+/// Returns true for the symbolic comparison operators the evaluator understands.
+fn is_known_operator(op: &str) -> bool {
+    SYMBOL_OPERATORS.contains(&op)
 }
 
 /// Parses a DSL query string into an abstract syntax tree (AST).
+///
+/// # Errors
+/// * Returns a `QueryError` if the query is malformed, including when tokens are left
+///   over after the filter
 pub fn parse_query(input: &str) -> Result<Expr> {
-    let tokens = tokenize(input)?;
-    let mut parser = Parser::new(tokens.into_iter());
+    let mut parser = Parser::new(input)?;
     let expr = parser.parse_expression()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected(&["'and'", "'or'", "end of query"]));
+    }
     Ok(expr)
 }
 
+/// Parses a full DSL statement: an optional `select` projection, an optional filter and
+/// optional `group by`, `order by`, `limit` and `offset` clauses, in that order.
+///
+/// # Examples
+///
+/// ```ignore
+/// select title, modified_at where tag = "meeting" order by modified_at desc limit 20
+/// tag = "rust" order by priority desc, title offset 10
+/// order by created_on limit 5
+/// select type, month(created_on), count() group by type, month(created_on)
+/// ```
+///
+/// Columns of `select`, `group by` and `order by` can be fields, date buckets
+/// (`year()`, `month()`, `week()`, `day()`) or, in grouped statements, aggregates
+/// (`count()`, `count(field)`, `min()`, `max()`, `avg()`). `where` is only needed
+/// after `select`. The clause words can still be used as field
+/// names in conditions (`limit > 3`).
+///
+/// # Errors
+/// * Returns a `QueryError` if the statement is malformed or has tokens after its last
+///   clause
+pub fn parse_statement(input: &str) -> Result<Query> {
+    Parser::new(input)?.parse_statement()
+}
+
 /// Converts the AST into a jq expression string.
 /// For tag conditions:
 ///   - `tag = "CLI"` becomes `(.tags | index("CLI") != null)`
 ///   - `tag != "CLI"` becomes `(.tags | index("CLI") == null)`
-/// For other fields, a simple comparison is generated.
+///
+/// For other fields, a simple comparison is generated, with dotted fields turned into
+/// a jq path (`project.status` becomes `."project"."status"`). Unlike the native
+/// evaluator, jq compares dates as strings and doesn't look into lists for comparisons.
+/// The string operators (`contains`, `starts_with`, `ends_with`, `~`, `in` and their
+/// case-insensitive variants) match if any element of a list field matches.
 /// For negation, we now output the expression as: `({inner} | not)`
-pub fn build_jq_expression(expr: &Expr) -> String {
-    match expr {
+///
+/// # Errors
+/// * Returns an error for `glob` and `iglob`, which have no jq equivalent
+/// * Returns an error for full-text conditions on `body` or `text`
+/// * Returns an error for relative dates, which depend on the day the query runs
+pub fn build_jq_expression(expr: &Expr) -> Result<String> {
+    let jq = match expr {
         Expr::Condition { field, op, value } => {
-            if field.to_lowercase() == "tag" {
-                if op == "=" {
-                    format!("(.tags | index(\"{}\") != null)", value)
-                } else if op == "!=" {
-                    format!("(.tags | index(\"{}\") == null)", value)
-                } else {
-                    format!(".{} {} \"{}\"", field, op, value)
-                }
+            if matches!(field.to_lowercase().as_str(), "body" | "text") {
+                return Err(anyhow!(
+                    "Full-text conditions on '{}' are not supported by the jq backend",
+                    field
+                ));
+            }
+            let is_relative = |v: &Value| matches!(v, Value::Relative(_));
+            if is_relative(value)
+                || matches!(value, Value::List(values) if values.iter().any(is_relative))
+            {
+                return Err(anyhow!(
+                    "Relative dates like '{}' are not supported by the jq backend",
+                    value
+                ));
+            }
+            let path = if field.to_lowercase() == "tag" {
+                ".tags".to_string()
             } else {
-                format!(".{} {} \"{}\"", field, op, value)
+                jq_path(field)
+            };
+            match op.as_str() {
+                "=" | "!=" if field.to_lowercase() == "tag" => {
+                    let check = if op == "=" { "!=" } else { "==" };
+                    format!("(.tags | index({}) {} null)", jq_literal(value), check)
+                }
+                "contains" | "starts_with" | "ends_with" => jq_any(
+                    &path,
+                    &format!(
+                        "(tostring | {}({}))",
+                        jq_string_function(op),
+                        jq_literal(value)
+                    ),
+                ),
+                ":" | "icontains" | "istarts_with" | "iends_with" => jq_any(
+                    &path,
+                    &format!(
+                        "(tostring | ascii_downcase | {}({}))",
+                        jq_string_function(op.trim_start_matches(['i', ':'])),
+                        jq_literal(&Value::String(value.to_string().to_lowercase()))
+                    ),
+                ),
+                "~" | "~*" => {
+                    let flags = if op == "~*" { "; \"i\"" } else { "" };
+                    jq_any(
+                        &path,
+                        &format!(
+                            "(tostring | test({}{}))",
+                            jq_literal(&Value::String(value.to_string())),
+                            flags
+                        ),
+                    )
+                }
+                "in" => {
+                    let values = match value {
+                        Value::List(values) => values.clone(),
+                        other => vec![other.clone()],
+                    };
+                    let checks: Vec<String> = values
+                        .iter()
+                        .map(|v| format!(". == {}", jq_literal(v)))
+                        .collect();
+                    jq_any(&path, &format!("({})", checks.join(" or ")))
+                }
+                "glob" | "iglob" => {
+                    return Err(anyhow!(
+                        "The '{}' operator is not supported by the jq backend",
+                        op
+                    ));
+                }
+                _ => format!("{} {} {}", path, jq_operator(op), jq_literal(value)),
             }
         }
         Expr::And(lhs, rhs) => {
             format!(
                 "({} and {})",
-                build_jq_expression(lhs),
-                build_jq_expression(rhs)
+                build_jq_expression(lhs)?,
+                build_jq_expression(rhs)?
             )
         }
         Expr::Or(lhs, rhs) => {
             format!(
                 "({} or {})",
-                build_jq_expression(lhs),
-                build_jq_expression(rhs)
+                build_jq_expression(lhs)?,
+                build_jq_expression(rhs)?
             )
         }
         Expr::Not(inner) => {
             // Special-case negated tag condition.
             if let Expr::Condition { field, op, value } = &**inner {
                 if field.to_lowercase() == "tag" && op == "=" {
-                    format!("(.tags | index(\"{}\") == null)", value)
+                    format!("(.tags | index({}) == null)", jq_literal(value))
                 } else {
-                    format!("({} | not)", build_jq_expression(inner))
+                    format!("({} | not)", build_jq_expression(inner)?)
                 }
             } else {
-                format!("({} | not)", build_jq_expression(inner))
+                format!("({} | not)", build_jq_expression(inner)?)
             }
         }
+    };
+    Ok(jq)
+}
+
+/// Helper function to test a jq condition against a field or any element of a list field.
+fn jq_any(path: &str, condition: &str) -> String {
+    format!(
+        "any({} | if type == \"array\" then .[] else . end | select(. != null); {})",
+        path, condition
+    )
+}
+
+/// Helper function to map a string operator to the jq builtin implementing it.
+fn jq_string_function(op: &str) -> &str {
+    match op {
+        "starts_with" => "startswith",
+        "ends_with" => "endswith",
+        _ => "contains",
+    }
+}
+
+/// Helper function to turn a dotted field into a jq path with quoted keys.
+fn jq_path(field: &str) -> String {
+    field
+        .split('.')
+        .map(|segment| format!(".{}", serde_json::Value::String(segment.to_string())))
+        .collect()
+}
+
+/// Helper function to turn a DSL operator into its jq equivalent.
+fn jq_operator(op: &str) -> &str {
+    if op == "=" { "==" } else { op }
+}
+
+/// Helper function to format a value as a jq (JSON) literal.
+fn jq_literal(value: &Value) -> String {
+    match value {
+        Value::String(s) => serde_json::Value::String(s.clone()).to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::List(values) => {
+            let items: Vec<String> = values.iter().map(jq_literal).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Pattern(pattern) => {
+            serde_json::Value::String(pattern.source().to_string()).to_string()
+        }
+        Value::Relative(relative) => serde_json::Value::String(relative.to_string()).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_to_regex() -> Result<()> {
+        let glob = Pattern::glob("projects/**/*.{md,markdown}", false)?;
+        assert!(glob.is_match("projects/a.md"));
+        assert!(glob.is_match("projects/a/b/c.markdown"));
+        assert!(!glob.is_match("projects/a.txt"));
+        assert!(!glob.is_match("other/projects/a.md"));
+
+        let glob = Pattern::glob("note-?[!0-9].md", true)?;
+        assert!(glob.is_match("NOTE-1a.md"));
+        assert!(!glob.is_match("note-12.md"));
+
+        assert!(Pattern::glob("broken[", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_jq_expression() -> Result<()> {
+        let ast = parse_query(r#"title icontains "Meet" and tag in (rust, "cli")"#)?;
+        assert_eq!(
+            build_jq_expression(&ast)?,
+            r#"(any(."title" | if type == "array" then .[] else . end | select(. != null); (tostring | ascii_downcase | contains("meet"))) and any(.tags | if type == "array" then .[] else . end | select(. != null); (. == "rust" or . == "cli")))"#
+        );
+
+        let ast = parse_query(r#"path glob "projects/**""#)?;
+        assert!(build_jq_expression(&ast).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_statement() -> Result<()> {
+        let query = parse_statement(
+            "select title, modified_at where tag = meeting order by modified_at desc, title limit 20 offset 5",
+        )?;
+        assert_eq!(
+            query.select,
+            vec![
+                Column::Field("title".to_string()),
+                Column::Field("modified_at".to_string())
+            ]
+        );
+        assert!(matches!(query.filter, Some(Expr::Condition { ref field, .. }) if field == "tag"));
+        assert_eq!(
+            query.order_by,
+            vec![
+                OrderBy {
+                    column: Column::Field("modified_at".to_string()),
+                    descending: true
+                },
+                OrderBy {
+                    column: Column::Field("title".to_string()),
+                    descending: false
+                },
+            ]
+        );
+        assert_eq!((query.limit, query.offset), (Some(20), Some(5)));
+        assert!(!query.is_plain_filter());
+
+        let query = parse_statement("order by created_on limit 3")?;
+        assert!(query.filter.is_none());
+        assert_eq!(query.limit, Some(3));
+
+        let query = parse_statement("limit > 3 and order = 1")?;
+        assert!(query.is_plain_filter());
+
+        assert!(parse_statement("tag = rust limit").is_err());
+        assert!(parse_statement("tag = rust limit -1").is_err());
+        assert!(parse_statement("tag = rust order created_on").is_err());
+        assert!(parse_statement("tag = rust title = x").is_err());
+        Ok(())
+    }
+
+    fn query_error(input: &str) -> QueryError {
+        let error = parse_statement(input).expect_err("query should not parse");
+        error
+            .downcast::<QueryError>()
+            .expect("parse errors should be QueryErrors")
+    }
+
+    #[test]
+    fn test_tokenize_spans() -> Result<()> {
+        let input = r#"title ~* "^a b" and not tag=x"#;
+        let tokens = tokenize(input)?;
+        let slices: Vec<&str> = tokens.iter().map(|t| &input[t.span.clone()]).collect();
+        assert_eq!(
+            slices,
+            vec!["title", "~*", r#""^a b""#, "and", "not", "tag", "=", "x"]
+        );
+        assert_eq!(tokens[2].token, Token::StringLiteral("^a b".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_errors() -> Result<()> {
+        // Leftover tokens are rejected instead of silently ignored.
+        let error = query_error(r#"tag = "a" tag = "b""#);
+        assert_eq!(error.span, 10..13);
+        assert!(error.expected.contains(&"'and'".to_string()));
+        assert!(parse_query(r#"tag = "a" tag = "b""#).is_err());
+
+        let error = query_error(r#"title = "unterminated"#);
+        assert_eq!(error.message, "Unterminated string literal");
+        assert_eq!(error.span, 8..21);
+
+        let error = query_error("title");
+        assert_eq!(error.span, 5..5);
+        assert!(error.message.ends_with("found end of query"));
+        assert!(error.expected.contains(&"'contains'".to_string()));
+
+        let error = query_error("title contians meeting");
+        assert_eq!(error.span, 6..14);
+        assert_eq!(error.hint.as_deref(), Some("did you mean 'contains'?"));
+
+        let error = query_error("tag = rust limt 5");
+        assert_eq!(error.hint.as_deref(), Some("did you mean 'limit'?"));
+
+        let error = query_error("(tag = rust or tag = go");
+        assert_eq!(error.expected, vec!["')'", "'and'", "'or'"]);
+
+        let error = query_error(r#"title ~ "(""#);
+        assert_eq!(error.span, 8..11);
+
+        let error = query_error("title = @");
+        assert_eq!(error.span, 8..9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_group_by() -> Result<()> {
+        let query = parse_statement(
+            "select type, month(created_on), count(), max(modified_at) where tag = rust \
+             group by type, month(created_on) order by count() desc",
+        )?;
+        assert!(query.is_aggregate());
+        let select: Vec<String> = query.select.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            select,
+            vec!["type", "month(created_on)", "count()", "max(modified_at)"]
+        );
+        assert_eq!(
+            query.group_by[1],
+            Column::Bucket {
+                unit: DateUnit::Month,
+                field: "created_on".to_string()
+            }
+        );
+        assert!(query.order_by[0].descending);
+        assert!(parse_statement("select count()")?.is_aggregate());
+
+        let input = "select title, count() group by tag";
+        let error = query_error(input);
+        assert_eq!(&input[error.span.clone()], "title");
+
+        let error = query_error("group by count()");
+        assert_eq!(error.message, "Aggregates can't be used in 'group by'");
+        assert!(
+            query_error("order by count()")
+                .message
+                .contains("grouped statement")
+        );
+        assert!(
+            query_error("group by tag order by title")
+                .message
+                .contains("'title'")
+        );
+
+        let error = query_error("group by mnth(created_on)");
+        assert_eq!(error.hint.as_deref(), Some("did you mean 'month'?"));
+        assert!(parse_statement("group by month()").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_dates() -> Result<()> {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // Wednesday 2025-01-01
+        let today = date(2025, 1, 1);
+        let resolve = |literal: &str| RelativeDate::parse(literal).map(|r| r.resolve(today));
+
+        assert_eq!(resolve("today"), Some((today, today)));
+        assert_eq!(
+            resolve("yesterday"),
+            Some((date(2024, 12, 31), date(2024, 12, 31)))
+        );
+        assert_eq!(
+            resolve("-7d"),
+            Some((date(2024, 12, 25), date(2024, 12, 25)))
+        );
+        assert_eq!(resolve("+2w"), Some((date(2025, 1, 15), date(2025, 1, 15))));
+        assert_eq!(resolve("-1m"), Some((date(2024, 12, 1), date(2024, 12, 1))));
+        assert_eq!(
+            resolve("this_week"),
+            Some((date(2024, 12, 30), date(2025, 1, 5)))
+        );
+        assert_eq!(
+            resolve("last_month"),
+            Some((date(2024, 12, 1), date(2024, 12, 31)))
+        );
+        assert_eq!(
+            resolve("next_month"),
+            Some((date(2025, 2, 1), date(2025, 2, 28)))
+        );
+        assert_eq!(resolve("start_of_year"), Some((today, today)));
+        assert_eq!(
+            resolve("END_OF_YEAR"),
+            Some((date(2025, 12, 31), date(2025, 12, 31)))
+        );
+        assert_eq!(resolve("this_day"), None);
+        assert_eq!(resolve("-7"), None);
+
+        let ast = parse_query("date >= -7d and date < this_week")?;
+        let Expr::And(lhs, _) = &ast else {
+            panic!("expected an and expression");
+        };
+        assert!(matches!(
+            lhs.as_ref(),
+            Expr::Condition {
+                value: Value::Relative(RelativeDate::Offset { amount: -7, .. }),
+                ..
+            }
+        ));
+        assert!(build_jq_expression(&ast).is_err());
+        assert_eq!(Value::from_unquoted("last_week").to_string(), "last_week");
+        assert_eq!(Value::from_unquoted("+3d").to_string(), "+3d");
+        assert_eq!(
+            Value::from_unquoted("today"),
+            Value::Relative(RelativeDate::parse("today").unwrap())
+        );
+        Ok(())
     }
 }
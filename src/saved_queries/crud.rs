@@ -0,0 +1,202 @@
+// src/saved_queries/crud.rs
+
+use crate::query_parser::parse_statement;
+use crate::saved_queries::utils::{
+    QUERIES_DIRECTORY, SavedQuery, check_if_saved_query_exists, saved_query_path,
+};
+use anyhow::{Context, Result, anyhow};
+use std::collections::BTreeMap;
+use std::fs::{self, create_dir_all};
+use std::path::{Path, PathBuf};
+
+/// Saves a new query in the vault.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `saved` - The query to save
+///
+/// # Returns
+/// * `Result<PathBuf>` - The path to the created saved query file
+///
+/// # Errors
+/// * Returns an error if a saved query with the same name already exists
+/// * Returns an error if the query isn't valid (see `SavedQuery::render`)
+/// * Returns an error if the saved query file can't be written
+pub fn create_saved_query(vault_directory: &Path, saved: &SavedQuery) -> Result<PathBuf> {
+    if check_if_saved_query_exists(vault_directory, &saved.name) {
+        return Err(anyhow!(
+            "A saved query with the name '{}' already exists",
+            saved.name
+        ));
+    }
+    validate_saved_query(saved)?;
+    write_saved_query(vault_directory, saved)
+}
+
+/// Replaces an existing saved query. The query may be renamed by giving it a new name.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `name` - The current name of the saved query
+/// * `saved` - The new version of the query
+///
+/// # Returns
+/// * `Result<PathBuf>` - The path to the updated saved query file
+///
+/// # Errors
+/// * Returns an error if the saved query doesn't exist
+/// * Returns an error if it is renamed to the name of another saved query
+/// * Returns an error if the query isn't valid (see `SavedQuery::render`)
+/// * Returns an error if the saved query file can't be written
+pub fn update_saved_query(
+    vault_directory: &Path,
+    name: &str,
+    saved: &SavedQuery,
+) -> Result<PathBuf> {
+    if !check_if_saved_query_exists(vault_directory, name) {
+        return Err(anyhow!("Saved query '{}' does not exist", name));
+    }
+    let old_path = saved_query_path(vault_directory, name);
+    let new_path = saved_query_path(vault_directory, &saved.name);
+    if new_path != old_path && new_path.exists() {
+        return Err(anyhow!(
+            "A saved query with the name '{}' already exists",
+            saved.name
+        ));
+    }
+    validate_saved_query(saved)?;
+
+    let path = write_saved_query(vault_directory, saved)?;
+    if new_path != old_path {
+        fs::remove_file(&old_path)
+            .with_context(|| format!("Failed to remove saved query file: {:?}", old_path))?;
+    }
+    Ok(path)
+}
+
+/// Deletes a saved query.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `name` - The name of the saved query to delete
+///
+/// # Returns
+/// * `Result<()>` - Ok if the saved query was successfully deleted
+///
+/// # Errors
+/// * Returns an error if the saved query doesn't exist
+/// * Returns an error if the saved query file can't be deleted
+pub fn delete_saved_query(vault_directory: &Path, name: &str) -> Result<()> {
+    if !check_if_saved_query_exists(vault_directory, name) {
+        return Err(anyhow!("Saved query '{}' does not exist", name));
+    }
+    let path = saved_query_path(vault_directory, name);
+    fs::remove_file(&path).context(format!("Failed to delete saved query file: {}", name))?;
+    Ok(())
+}
+
+/// Helper function to check that a saved query builds a valid statement. Required
+/// parameters are filled with an empty string for the check.
+fn validate_saved_query(saved: &SavedQuery) -> Result<()> {
+    if saved.name.trim().is_empty() {
+        return Err(anyhow!("A saved query needs a name"));
+    }
+    let placeholders: BTreeMap<String, String> = saved
+        .params
+        .iter()
+        .filter(|(_, default)| default.is_null())
+        .map(|(name, _)| (name.clone(), String::new()))
+        .collect();
+    let statement = saved.render(&placeholders)?;
+    let parsed = parse_statement(&statement)
+        .with_context(|| format!("Invalid saved query '{}'", saved.name))?;
+    if parsed.is_aggregate() {
+        return Err(anyhow!(
+            "Saved query '{}' can't group notes; saved queries return notes",
+            saved.name
+        ));
+    }
+    Ok(())
+}
+
+/// Helper function to write a saved query to its file.
+fn write_saved_query(vault_directory: &Path, saved: &SavedQuery) -> Result<PathBuf> {
+    create_dir_all(vault_directory.join(QUERIES_DIRECTORY))
+        .context("Failed to create queries directory")?;
+    let path = saved_query_path(vault_directory, &saved.name);
+    let yaml = serde_yaml::to_string(saved).context("Failed to serialize saved query")?;
+    fs::write(&path, yaml)
+        .with_context(|| format!("Failed to write saved query file: {:?}", path))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::saved_queries::utils::{get_saved_query, list_saved_queries};
+    use serde_yaml::Value as YamlValue;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_create_saved_query() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        let mut saved = SavedQuery::new("Latest meetings", "tag = $tag");
+        saved
+            .params
+            .insert("tag".to_string(), YamlValue::from("meeting"));
+        saved.order_by = Some("modified_at desc".to_string());
+        saved.limit = Some(20);
+
+        let path = create_saved_query(vault_dir, &saved)?;
+        assert_eq!(path, vault_dir.join("queries/Latest-meetings.yaml"));
+        assert_eq!(get_saved_query(vault_dir, "Latest meetings")?, saved);
+
+        // Duplicates and invalid queries are rejected.
+        assert!(create_saved_query(vault_dir, &saved).is_err());
+        let invalid = SavedQuery::new("Invalid", "tag = ");
+        assert!(create_saved_query(vault_dir, &invalid).is_err());
+        let grouped = SavedQuery::new("Grouped", "group by tag");
+        assert!(create_saved_query(vault_dir, &grouped).is_err());
+        let mut bad_order = SavedQuery::new("Bad order", "tag = rust");
+        bad_order.order_by = Some("title sideways".to_string());
+        assert!(create_saved_query(vault_dir, &bad_order).is_err());
+        assert_eq!(list_saved_queries(vault_dir)?, vec!["Latest meetings"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_and_delete_saved_query() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        let mut saved = SavedQuery::new("Drafts", "status = draft");
+        create_saved_query(vault_dir, &saved)?;
+        create_saved_query(vault_dir, &SavedQuery::new("Other", "tag = x"))?;
+
+        saved.description = Some("Notes still being written".to_string());
+        update_saved_query(vault_dir, "Drafts", &saved)?;
+        assert_eq!(
+            get_saved_query(vault_dir, "Drafts")?.description.as_deref(),
+            Some("Notes still being written")
+        );
+
+        // Renaming moves the file, but not over another saved query.
+        saved.name = "Other".to_string();
+        assert!(update_saved_query(vault_dir, "Drafts", &saved).is_err());
+        saved.name = "Work in progress".to_string();
+        update_saved_query(vault_dir, "Drafts", &saved)?;
+        assert_eq!(
+            list_saved_queries(vault_dir)?,
+            vec!["Other", "Work in progress"]
+        );
+
+        delete_saved_query(vault_dir, "Work in progress")?;
+        assert_eq!(list_saved_queries(vault_dir)?, vec!["Other"]);
+        assert!(delete_saved_query(vault_dir, "Drafts").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+// src/saved_queries/utils.rs
+
+use crate::notes::utils::sanitize_title;
+use crate::query_native::{QueryRow, run_query};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The vault directory holding one YAML file per saved query.
+pub const QUERIES_DIRECTORY: &str = "queries";
+
+/// A named DSL query stored in the vault, e.g. `queries/Open-tasks.yaml`:
+///
+/// ```yaml
+/// name: Open tasks
+/// description: Tasks of a project that are still open
+/// query: type = task and status != done and project = $project
+/// params:
+///   project: null
+/// order_by: priority desc, created_on
+/// limit: 20
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The filter in the query DSL. `$param` placeholders outside of quoted strings are
+    /// replaced by the values of the parameters.
+    pub query: String,
+    /// The parameters of the query and their default values. A parameter with a null
+    /// default is required.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, YamlValue>,
+    /// The keys of the `order by` clause, like `modified_at desc, title`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl SavedQuery {
+    /// Creates a saved query without description, parameters, sort or limit.
+    pub fn new(name: &str, query: &str) -> Self {
+        SavedQuery {
+            name: name.to_string(),
+            description: None,
+            query: query.to_string(),
+            params: BTreeMap::new(),
+            order_by: None,
+            limit: None,
+        }
+    }
+
+    /// Builds the DSL statement to run, with the parameters filled in and the sort and
+    /// limit appended as `order by` and `limit` clauses.
+    ///
+    /// Parameter values are inserted as quoted strings, which still compare as numbers
+    /// or dates where the field is one.
+    ///
+    /// # Arguments
+    /// * `params` - The parameter values. Parameters not given use their default.
+    ///
+    /// # Returns
+    /// * `Result<String>` - The DSL statement
+    ///
+    /// # Errors
+    /// * Returns an error if a parameter is unknown, or required and not given
+    /// * Returns an error if a placeholder in the query isn't a declared parameter
+    /// * Returns an error if a parameter value contains a double quote
+    pub fn render(&self, params: &BTreeMap<String, String>) -> Result<String> {
+        if let Some(unknown) = params.keys().find(|name| !self.params.contains_key(*name)) {
+            return Err(anyhow!(
+                "Unknown parameter '{}' for saved query '{}'",
+                unknown,
+                self.name
+            ));
+        }
+
+        let mut statement = String::new();
+        let mut chars = self.query.chars().peekable();
+        let mut in_string = false;
+        while let Some(c) = chars.next() {
+            if c == '"' {
+                in_string = !in_string;
+            }
+            if c != '$' || in_string {
+                statement.push(c);
+                continue;
+            }
+
+            let mut name = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_alphanumeric() || ch == '_' {
+                    name.push(ch);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let default = self.params.get(&name).ok_or_else(|| {
+                anyhow!(
+                    "Saved query '{}' uses undeclared parameter '${}'",
+                    self.name,
+                    name
+                )
+            })?;
+            let value = match (params.get(&name), default) {
+                (Some(value), _) => value.clone(),
+                (None, YamlValue::String(s)) => s.clone(),
+                (None, YamlValue::Number(n)) => n.to_string(),
+                (None, YamlValue::Bool(b)) => b.to_string(),
+                (None, YamlValue::Null) => {
+                    return Err(anyhow!(
+                        "Missing value for required parameter '{}' of saved query '{}'",
+                        name,
+                        self.name
+                    ));
+                }
+                (None, _) => {
+                    return Err(anyhow!(
+                        "The default of parameter '{}' must be a string, number or boolean",
+                        name
+                    ));
+                }
+            };
+            if value.contains('"') {
+                return Err(anyhow!(
+                    "The value of parameter '{}' can't contain '\"'",
+                    name
+                ));
+            }
+            statement.push_str(&format!("\"{}\"", value));
+        }
+
+        if let Some(order_by) = self.order_by.as_deref().filter(|o| !o.trim().is_empty()) {
+            statement.push_str(&format!(" order by {}", order_by.trim()));
+        }
+        if let Some(limit) = self.limit {
+            statement.push_str(&format!(" limit {}", limit));
+        }
+        Ok(statement.trim().to_string())
+    }
+}
+
+/// Helper function to get the path of a saved query file from its name.
+pub(crate) fn saved_query_path(vault_directory: &Path, name: &str) -> PathBuf {
+    vault_directory
+        .join(QUERIES_DIRECTORY)
+        .join(format!("{}.yaml", sanitize_title(name)))
+}
+
+/// Checks if a saved query with the given name exists.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `name` - The name of the saved query
+///
+/// # Returns
+/// * `bool` - True if the saved query exists, False otherwise
+pub fn check_if_saved_query_exists(vault_directory: &Path, name: &str) -> bool {
+    saved_query_path(vault_directory, name).exists()
+}
+
+/// Reads a saved query from the vault.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `name` - The name of the saved query
+///
+/// # Returns
+/// * `Result<SavedQuery>` - The saved query
+///
+/// # Errors
+/// * Returns an error if the saved query doesn't exist
+/// * Returns an error if the saved query file can't be read or parsed
+pub fn get_saved_query(vault_directory: &Path, name: &str) -> Result<SavedQuery> {
+    let path = saved_query_path(vault_directory, name);
+    if !path.exists() {
+        return Err(anyhow!("Saved query '{}' does not exist", name));
+    }
+    read_saved_query(&path)
+}
+
+/// Helper function to read and parse a saved query file.
+fn read_saved_query(path: &Path) -> Result<SavedQuery> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read saved query file: {:?}", path))?;
+    serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse saved query file: {:?}", path))
+}
+
+/// Lists the names of the saved queries in the vault, sorted.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Vec<String>>` - The names of the saved queries
+///
+/// # Errors
+/// * Returns an error if the queries directory or a saved query file can't be read
+pub fn list_saved_queries(vault_directory: &Path) -> Result<Vec<String>> {
+    let queries_dir = vault_directory.join(QUERIES_DIRECTORY);
+
+    // If the queries directory doesn't exist, return an empty list
+    if !queries_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&queries_dir).context("Failed to read queries directory")? {
+        let entry = entry.context("Failed to access directory entry")?;
+        let path = entry.path();
+        if path.is_file()
+            && path
+                .extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        {
+            names.push(read_saved_query(&path)?.name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Runs a saved query with the given parameters through `query_native::run_query`.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `name` - The name of the saved query
+/// * `params` - The parameter values. Parameters not given use their default.
+///
+/// # Returns
+/// * `Result<Vec<QueryRow>>` - The notes matching the query, sorted and limited as saved
+///
+/// # Errors
+/// * Returns an error if the saved query doesn't exist or can't be read
+/// * Returns an error if the parameters don't fit the query (see `SavedQuery::render`)
+/// * Returns an error if the resulting statement can't be parsed or run
+pub fn run_saved_query(
+    vault_directory: &Path,
+    name: &str,
+    params: &BTreeMap<String, String>,
+) -> Result<Vec<QueryRow>> {
+    let saved = get_saved_query(vault_directory, name)?;
+    let statement = saved.render(params)?;
+    run_query(vault_directory, &statement)
+        .with_context(|| format!("Failed to run saved query '{}'", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn params(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let mut saved = SavedQuery::new(
+            "Open tasks",
+            r#"status != "$done" and project = $project and priority >= $min"#,
+        );
+        saved.params.insert("project".to_string(), YamlValue::Null);
+        saved.params.insert("min".to_string(), YamlValue::from(2));
+        saved.order_by = Some("priority desc".to_string());
+        saved.limit = Some(5);
+
+        assert_eq!(
+            saved.render(&params(&[("project", "core")]))?,
+            r#"status != "$done" and project = "core" and priority >= "2" order by priority desc limit 5"#
+        );
+        assert!(saved.render(&params(&[])).is_err());
+        assert!(saved.render(&params(&[("project", "a\"b")])).is_err());
+        assert!(
+            saved
+                .render(&params(&[("project", "core"), ("other", "x")]))
+                .is_err()
+        );
+
+        let undeclared = SavedQuery::new("Broken", "tag = $tag");
+        assert!(undeclared.render(&params(&[])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_saved_query() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        for (file, project, priority) in
+            [("a.md", "core", 1), ("b.md", "core", 3), ("c.md", "web", 5)]
+        {
+            fs::write(
+                vault_dir.join(file),
+                format!(
+                    "---\ntitle: {}\nproject: {}\npriority: {}\n---\n",
+                    file, project, priority
+                ),
+            )?;
+        }
+        fs::create_dir_all(vault_dir.join(QUERIES_DIRECTORY))?;
+        fs::write(
+            saved_query_path(vault_dir, "By project"),
+            "name: By project\nquery: project = $project\nparams:\n  project: core\norder_by: priority desc\nlimit: 1\n",
+        )?;
+
+        assert!(check_if_saved_query_exists(vault_dir, "By project"));
+        assert_eq!(list_saved_queries(vault_dir)?, vec!["By project"]);
+
+        let rows = run_saved_query(vault_dir, "By project", &params(&[]))?;
+        let paths: Vec<_> = rows.iter().map(|row| row.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.md"]);
+
+        let rows = run_saved_query(vault_dir, "By project", &params(&[("project", "web")]))?;
+        assert_eq!(rows[0].path, "c.md");
+
+        assert!(run_saved_query(vault_dir, "Missing", &params(&[])).is_err());
+        Ok(())
+    }
+}
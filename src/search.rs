@@ -0,0 +1,620 @@
+// src/search.rs
+
+use crate::index::{INDEX_DIRECTORY, RefreshStats, file_stamp, fnv1a_hash, write_atomically};
+use crate::notes::crud::strip_frontmatter;
+use crate::notes::frontmatter::Frontmatter;
+use crate::notes::utils::{fallback_title, relative_note_path, walk_markdown_files};
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Name of the full-text index file inside [`INDEX_DIRECTORY`].
+const SEARCH_FILE: &str = "search.json";
+
+/// Version of the on-disk format. Indexes written with another version are rebuilt.
+const SEARCH_VERSION: u32 = 2;
+
+/// BM25 term frequency saturation.
+const BM25_K1: f64 = 1.2;
+
+/// BM25 document length normalization.
+const BM25_B: f64 = 0.75;
+
+/// Number of tokens shown before the first match in a snippet.
+const SNIPPET_BEFORE: usize = 8;
+
+/// Number of tokens shown from the first match onwards in a snippet.
+const SNIPPET_AFTER: usize = 24;
+
+/// A ranked full-text search result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    /// Path of the note relative to the vault
+    pub path: String,
+    /// Title of the note, falling back to the file name
+    pub title: String,
+    /// BM25 score, higher is better
+    pub score: f64,
+    /// An excerpt of the body around the first match, with matches wrapped in `<mark>` tags
+    pub snippet: String,
+}
+
+/// The indexed terms of a single note.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Document {
+    title: String,
+    /// Number of tokens in the body
+    length: u32,
+    /// Stemmed term to the token positions where it occurs
+    terms: BTreeMap<String, Vec<u32>>,
+    mtime: u64,
+    size: u64,
+    hash: u64,
+}
+
+/// A parsed search query: loose terms and quoted phrases.
+#[derive(Debug, Default)]
+struct SearchQuery {
+    terms: Vec<String>,
+    phrases: Vec<Vec<String>>,
+}
+
+impl SearchQuery {
+    fn parse(query: &str) -> Self {
+        let mut parsed = SearchQuery::default();
+        for (i, part) in query.split('"').enumerate() {
+            let stems: Vec<String> = tokenize(part).into_iter().map(|(t, _)| t).collect();
+            if i % 2 == 1 {
+                // Inside quotes.
+                if stems.len() > 1 {
+                    parsed.phrases.push(stems);
+                } else {
+                    parsed.terms.extend(stems);
+                }
+            } else {
+                parsed.terms.extend(stems);
+            }
+        }
+        parsed
+    }
+
+    /// All distinct terms of the query, including those in phrases.
+    fn all_terms(&self) -> BTreeSet<&str> {
+        self.terms
+            .iter()
+            .chain(self.phrases.iter().flatten())
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.phrases.is_empty()
+    }
+}
+
+/// A full-text index over note bodies, stored under `.notemancy/search.json` in the vault.
+///
+/// Bodies are read without frontmatter (like `read_note(.., false)`), split into
+/// lowercase alphanumeric tokens and stemmed. Like `VaultIndex`, refreshing only
+/// reparses notes whose content changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchIndex {
+    version: u32,
+    #[serde(skip)]
+    vault_directory: PathBuf,
+    documents: BTreeMap<String, Document>,
+    /// Term to the paths of the documents containing it, rebuilt after loading
+    #[serde(skip)]
+    postings: HashMap<String, Vec<String>>,
+    /// Average number of terms per document, rebuilt with the postings
+    #[serde(skip)]
+    average_length: f64,
+}
+
+impl SearchIndex {
+    /// Loads the search index for the vault, refreshes it and writes it back to disk.
+    ///
+    /// # Arguments
+    /// * `vault_directory` - The base directory of the vault
+    ///
+    /// # Returns
+    /// * `Result<SearchIndex>` - An index that reflects the current state of the vault
+    ///
+    /// # Errors
+    /// * Returns an error if the vault can't be walked
+    /// * Returns an error if the index file can't be written
+    pub fn open(vault_directory: &Path) -> Result<Self> {
+        let mut index = Self::load(vault_directory)?;
+        index.refresh()?;
+        index.save()?;
+        Ok(index)
+    }
+
+    /// Loads the search index from disk without refreshing it.
+    ///
+    /// A missing, unreadable or outdated index file results in an empty index.
+    pub fn load(vault_directory: &Path) -> Result<Self> {
+        let stored = fs::read(Self::index_path(vault_directory))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<SearchIndex>(&bytes).ok())
+            .filter(|index| index.version == SEARCH_VERSION);
+
+        let mut index = stored.unwrap_or_else(|| SearchIndex {
+            version: SEARCH_VERSION,
+            vault_directory: PathBuf::new(),
+            documents: BTreeMap::new(),
+            postings: HashMap::new(),
+            average_length: 0.0,
+        });
+        index.vault_directory = vault_directory.to_path_buf();
+        index.rebuild_postings();
+        Ok(index)
+    }
+
+    /// Brings the index up to date with the notes in the vault.
+    ///
+    /// # Returns
+    /// * `Result<RefreshStats>` - How many documents were added, updated, reused and removed
+    ///
+    /// # Errors
+    /// * Returns an error if the vault can't be walked
+    pub fn refresh(&mut self) -> Result<RefreshStats> {
+        let mut stats = RefreshStats::default();
+        let mut seen = BTreeSet::new();
+        let mut to_read = Vec::new();
+
+        for path in walk_markdown_files(&self.vault_directory)? {
+            let relative_path = relative_note_path(&path, &self.vault_directory);
+            let Ok((mtime, size)) = file_stamp(&path) else {
+                continue;
+            };
+            seen.insert(relative_path.clone());
+            match self.documents.get(&relative_path) {
+                Some(doc) if doc.mtime == mtime && doc.size == size => stats.unchanged += 1,
+                _ => to_read.push((relative_path, path, mtime, size)),
+            }
+        }
+
+        let previous = &self.documents;
+        let results: Vec<(String, Document, bool)> = to_read
+            .into_par_iter()
+            .filter_map(|(relative_path, path, mtime, size)| {
+                let bytes = fs::read(&path).ok()?;
+                let hash = fnv1a_hash(&bytes);
+                match previous.get(&relative_path) {
+                    Some(doc) if doc.hash == hash => {
+                        let mut doc = doc.clone();
+                        doc.mtime = mtime;
+                        doc.size = size;
+                        Some((relative_path, doc, false))
+                    }
+                    _ => {
+                        let content = String::from_utf8_lossy(&bytes);
+                        let doc = index_document(&relative_path, &content, mtime, size, hash);
+                        Some((relative_path, doc, true))
+                    }
+                }
+            })
+            .collect();
+
+        for (relative_path, doc, reparsed) in results {
+            match (reparsed, self.documents.contains_key(&relative_path)) {
+                (false, _) => stats.unchanged += 1,
+                (true, true) => stats.updated += 1,
+                (true, false) => stats.added += 1,
+            }
+            self.documents.insert(relative_path, doc);
+        }
+
+        let before = self.documents.len();
+        self.documents.retain(|path, _| seen.contains(path));
+        stats.removed = before - self.documents.len();
+
+        self.rebuild_postings();
+        Ok(stats)
+    }
+
+    /// Writes the index to `.notemancy/search.json` in the vault.
+    ///
+    /// # Errors
+    /// * Returns an error if the index directory or file can't be written
+    pub fn save(&self) -> Result<PathBuf> {
+        let index_dir = self.vault_directory.join(INDEX_DIRECTORY);
+        fs::create_dir_all(&index_dir).context("Failed to create index directory")?;
+
+        let index_path = index_dir.join(SEARCH_FILE);
+        let json = serde_json::to_vec(self).context("Failed to serialize search index")?;
+        write_atomically(&index_path, &json).context("Failed to write search index")?;
+
+        Ok(index_path)
+    }
+
+    /// Returns the path of the search index file for the given vault.
+    pub fn index_path(vault_directory: &Path) -> PathBuf {
+        vault_directory.join(INDEX_DIRECTORY).join(SEARCH_FILE)
+    }
+
+    /// Returns the number of indexed notes.
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns true if no notes are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Searches note bodies and returns the best matches, ranked by BM25.
+    ///
+    /// Words in the query are matched after stemming, so `running` also finds `runs`.
+    /// A note matches if it contains any of the words. Quoted phrases (`"exact words"`)
+    /// are required: only notes containing every phrase, with its words next to each
+    /// other, are returned.
+    ///
+    /// # Arguments
+    /// * `query` - The search terms
+    /// * `limit` - The maximum number of results
+    ///
+    /// # Returns
+    /// * `Vec<SearchResult>` - Matches ordered by descending score, then by path
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let query = SearchQuery::parse(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let terms = query.all_terms();
+
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+        for term in &terms {
+            for path in self.postings.get(*term).into_iter().flatten() {
+                let doc = &self.documents[path];
+                *scores.entry(path.as_str()).or_default() += self.bm25(term, doc);
+            }
+        }
+
+        let mut ranked: Vec<(&str, f64)> = scores
+            .into_iter()
+            .filter(|(path, _)| {
+                let doc = &self.documents[*path];
+                query
+                    .phrases
+                    .iter()
+                    .all(|phrase| contains_phrase(doc, phrase))
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked.truncate(limit);
+
+        ranked
+            .into_iter()
+            .map(|(path, score)| SearchResult {
+                path: path.to_string(),
+                title: self.documents[path].title.clone(),
+                score,
+                snippet: self.snippet(path, &terms),
+            })
+            .collect()
+    }
+
+    /// Returns the paths of the notes whose body contains all words of `text`, or the
+    /// words as a consecutive phrase if `phrase` is true. Used by the query DSL.
+    pub(crate) fn matching_paths(&self, text: &str, phrase: bool) -> BTreeSet<String> {
+        let stems: Vec<String> = tokenize(text).into_iter().map(|(t, _)| t).collect();
+        if stems.is_empty() {
+            return BTreeSet::new();
+        }
+        self.documents
+            .iter()
+            .filter(|(_, doc)| {
+                if phrase {
+                    contains_phrase(doc, &stems)
+                } else {
+                    stems.iter().all(|stem| doc.terms.contains_key(stem))
+                }
+            })
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Helper function to compute the BM25 score of one term for one document.
+    fn bm25(&self, term: &str, doc: &Document) -> f64 {
+        let total = self.documents.len() as f64;
+        let frequency = self.postings.get(term).map_or(0, Vec::len) as f64;
+        let idf = (1.0 + (total - frequency + 0.5) / (frequency + 0.5)).ln();
+
+        let tf = doc.terms.get(term).map_or(0, Vec::len) as f64;
+        let norm = 1.0 - BM25_B + BM25_B * f64::from(doc.length) / self.average_length.max(1.0);
+
+        idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * norm)
+    }
+
+    /// Helper function to build an excerpt of a note around its first match.
+    fn snippet(&self, path: &str, terms: &BTreeSet<&str>) -> String {
+        let Ok(content) = fs::read_to_string(self.vault_directory.join(path)) else {
+            return String::new();
+        };
+        let body = strip_frontmatter(&content).unwrap_or(content);
+        make_snippet(&body, terms)
+    }
+
+    /// Helper function to rebuild the in-memory postings and average document length
+    /// from the documents.
+    fn rebuild_postings(&mut self) {
+        let mut postings: HashMap<String, Vec<String>> = HashMap::new();
+        let mut total_length = 0.0;
+        for (path, doc) in &self.documents {
+            for term in doc.terms.keys() {
+                postings.entry(term.clone()).or_default().push(path.clone());
+            }
+            total_length += f64::from(doc.length);
+        }
+        self.postings = postings;
+        self.average_length = total_length / (self.documents.len() as f64).max(1.0);
+    }
+}
+
+/// Searches note bodies in the vault, refreshing the search index first.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+/// * `query` - The search terms, optionally with quoted phrases
+/// * `limit` - The maximum number of results
+///
+/// # Returns
+/// * `Result<Vec<SearchResult>>` - Matches ordered by descending BM25 score
+///
+/// # Errors
+/// * Returns an error if the vault can't be walked or the index can't be written
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::search::search_notes;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let results = search_notes(vault_dir, r#"borrow checker "lifetime elision""#, 10);
+/// ```
+pub fn search_notes(
+    vault_directory: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    Ok(SearchIndex::open(vault_directory)?.search(query, limit))
+}
+
+/// Helper function to index the body of a note.
+fn index_document(
+    relative_path: &str,
+    content: &str,
+    mtime: u64,
+    size: u64,
+    hash: u64,
+) -> Document {
+    let title = Frontmatter::from_content(content)
+        .ok()
+        .flatten()
+        .and_then(|(frontmatter, _)| frontmatter.title)
+        .unwrap_or_else(|| fallback_title(relative_path));
+    let body = strip_frontmatter(content).unwrap_or_else(|_| content.to_string());
+
+    let tokens = tokenize(&body);
+    let mut terms: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+    for (position, (term, _)) in tokens.iter().enumerate() {
+        terms.entry(term.clone()).or_default().push(position as u32);
+    }
+
+    Document {
+        title,
+        length: tokens.len() as u32,
+        terms,
+        mtime,
+        size,
+        hash,
+    }
+}
+
+/// Helper function to check whether the stems of a phrase occur next to each other.
+fn contains_phrase(doc: &Document, phrase: &[String]) -> bool {
+    let Some(first) = phrase.first().and_then(|term| doc.terms.get(term)) else {
+        return false;
+    };
+    first.iter().any(|start| {
+        phrase.iter().enumerate().skip(1).all(|(offset, term)| {
+            doc.terms
+                .get(term)
+                .is_some_and(|positions| positions.binary_search(&(start + offset as u32)).is_ok())
+        })
+    })
+}
+
+/// Splits text into stemmed, lowercase tokens with their byte ranges.
+///
+/// Tokens are runs of alphanumeric characters.
+pub(crate) fn tokenize(text: &str) -> Vec<(String, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push((stem(&text[s..i].to_lowercase()), s..i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Reduces an English word to a simple stem by stripping common suffixes.
+///
+/// This is deliberately light: it only needs to map inflections of the same word to
+/// the same term, both when indexing and when searching. As in the Porter stemmer, a
+/// double consonant left by `-ing` or `-ed` is undoubled (`running` to `run`), except
+/// for `l`, `s` and `z` (`falling` to `fall`).
+pub(crate) fn stem(word: &str) -> String {
+    const SUFFIXES: [(&str, &str); 6] = [
+        ("ies", "y"),
+        ("ing", ""),
+        ("edly", ""),
+        ("ed", ""),
+        ("ly", ""),
+        ("s", ""),
+    ];
+    if word.chars().count() <= 3 || word.ends_with("ss") {
+        return word.to_string();
+    }
+    for (suffix, replacement) in SUFFIXES {
+        if let Some(base) = word.strip_suffix(suffix)
+            && base.chars().count() >= 3
+        {
+            let mut base = base.to_string();
+            let mut last = base.chars().rev();
+            if matches!(suffix, "ing" | "edly" | "ed")
+                && let (Some(c), Some(previous)) = (last.next(), last.next())
+                && c == previous
+                && c.is_ascii_alphabetic()
+                && !matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'l' | 's' | 'z')
+            {
+                base.pop();
+            }
+            return format!("{}{}", base, replacement);
+        }
+    }
+    word.to_string()
+}
+
+/// Helper function to cut a window of tokens around the first match and highlight matches.
+fn make_snippet(body: &str, terms: &BTreeSet<&str>) -> String {
+    let tokens = tokenize(body);
+    let Some(first) = tokens
+        .iter()
+        .position(|(term, _)| terms.contains(term.as_str()))
+    else {
+        return String::new();
+    };
+
+    let start_token = first.saturating_sub(SNIPPET_BEFORE);
+    let end_token = (first + SNIPPET_AFTER).min(tokens.len());
+    let start = tokens[start_token].1.start;
+    let end = tokens[end_token - 1].1.end;
+
+    let mut snippet = String::new();
+    if start_token > 0 {
+        snippet.push('…');
+    }
+    let mut cursor = start;
+    for (term, range) in &tokens[start_token..end_token] {
+        if terms.contains(term.as_str()) {
+            snippet.push_str(&body[cursor..range.start]);
+            snippet.push_str("<mark>");
+            snippet.push_str(&body[range.clone()]);
+            snippet.push_str("</mark>");
+            cursor = range.end;
+        }
+    }
+    snippet.push_str(&body[cursor..end]);
+    if end_token < tokens.len() {
+        snippet.push('…');
+    }
+
+    // Collapse line breaks and indentation into single spaces.
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tokenize_and_stem() {
+        let tokens: Vec<String> = tokenize("Running notes, tagged: Libraries!")
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect();
+        assert_eq!(tokens, vec!["run", "note", "tag", "library"]);
+        assert_eq!(stem("runs"), "run");
+        assert_eq!(stem("tags"), "tag");
+        assert_eq!(stem("falling"), stem("falls"));
+        assert_eq!(stem("class"), "class");
+    }
+
+    #[test]
+    fn test_search_ranking_phrases_and_snippets() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("rust.md"),
+            "---\ntitle: Rust Notes\ntags: [borrow]\n---\n\nThe borrow checker checks borrows.\nLifetimes keep the borrow checker happy.",
+        )?;
+        fs::write(
+            vault_dir.join("misc.md"),
+            "---\ntitle: Misc\n---\n\nA checker for spelling. I borrow books.",
+        )?;
+        fs::write(vault_dir.join("other.md"), "Nothing relevant here.")?;
+
+        let index = SearchIndex::open(vault_dir)?;
+        assert_eq!(index.len(), 3);
+
+        let results = index.search("borrow checker", 10);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].path, "rust.md");
+        assert_eq!(results[0].title, "Rust Notes");
+        assert!(results[0].score > results[1].score);
+        assert!(results[0].snippet.starts_with(
+            "The <mark>borrow</mark> <mark>checker</mark> checks <mark>borrows</mark>."
+        ));
+
+        // Phrases must appear with their words next to each other
+        let results = index.search(r#""borrow checker""#, 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "rust.md");
+
+        // Inflections of a word match each other
+        let results = index.search("borrowing", 10);
+        assert_eq!(results.len(), 2);
+        fs::write(vault_dir.join("other.md"), "She runs every morning.")?;
+        let index = SearchIndex::open(vault_dir)?;
+        let results = index.search("running", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "other.md");
+
+        // Frontmatter is not indexed
+        assert!(index.search("title", 10).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_incremental_refresh() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(vault_dir.join("a.md"), "apples and pears")?;
+        fs::write(vault_dir.join("b.md"), "bananas")?;
+        SearchIndex::open(vault_dir)?;
+
+        let path = vault_dir.join("a.md");
+        fs::write(&path, "cherries only")?;
+        fs::File::options()
+            .write(true)
+            .open(&path)?
+            .set_modified(SystemTime::now() + Duration::from_secs(5))?;
+        fs::remove_file(vault_dir.join("b.md"))?;
+
+        let mut index = SearchIndex::load(vault_dir)?;
+        let stats = index.refresh()?;
+        assert_eq!(stats.updated, 1);
+        assert_eq!(stats.removed, 1);
+        assert!(index.search("apples", 10).is_empty());
+        assert_eq!(index.search("cherry", 10)[0].path, "a.md");
+        Ok(())
+    }
+}
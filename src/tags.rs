@@ -1,92 +1,1018 @@
 // src/tags.rs
 
-use crate::notes::utils::get_title;
-use anyhow::{Context, Result};
-use regex::escape;
+use crate::index::VaultIndex;
+use crate::links::{code_spans, unfenced_lines};
+use crate::notes::frontmatter::Frontmatter;
+use crate::notes::utils::{get_title, relative_note_path, walk_markdown_files};
+use anyhow::{Context, Result, anyhow};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::ops::Range;
 use std::path::Path;
-use std::process::Command;
 
-/// Returns a list of all unique tags found in the vault directory.
+/// A `#hashtag` preceded by whitespace or the start of a line.
+static HASHTAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|\s)#([\p{L}\p{N}_\-/]+)").unwrap());
+
+/// Where a tag was found in a note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagSource {
+    /// An item of the `tags` field of the YAML frontmatter
+    Frontmatter,
+    /// A `#hashtag` in the body of the note
+    Inline,
+}
+
+/// A tag found in a note.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagOccurrence {
+    /// The tag, without quotes or `#`. Nested tags keep their slashes (`project/notemancy`).
+    pub tag: String,
+    pub source: TagSource,
+    /// Byte range of the tag text in the note content (without quotes or `#`)
+    pub span: Range<usize>,
+    /// 1-based line number of the tag in the note
+    pub line: usize,
+}
+
+/// Extracts the tags of a note from its content.
+///
+/// Frontmatter tags are read from the `tags` field, written as a block list, a flow
+/// list (`tags: [a, b]`) or a single string (`tags: a`). Inline tags are `#hashtags` in
+/// the body, preceded by a space or the start of a line and made of letters, digits,
+/// `_`, `-` and `/`, with at least one character that isn't a digit. Hashtags in code
+/// blocks and inline code are ignored.
+///
+/// Tags containing `/` are hierarchical: `project/notemancy/api` is a descendant of
+/// `project` and `project/notemancy` (see `is_tag_or_descendant`).
 ///
-/// This function executes a shell command using ripgrep (rg) and sort.
-/// It sets the current directory to the vault directory so that the dot (.)
-/// in the command refers to the vault root.
+/// # Arguments
+/// * `content` - The content of a note
 ///
-/// The command used is:
+/// # Returns
+/// * `Vec<TagOccurrence>` - The tags in order of appearance, frontmatter first. A tag
+///   appearing several times is returned once per occurrence.
 ///
-///   rg -U -oP '(?s)^---.*?^tags:\s*\n((?:\s*-\s*.*\n)+)' --no-filename . \
-///     | rg -oP '^\s*-\s*\K.*' \
-///     | rg -v '^\s*$' \
-///     | rg -v '^[-ー]+$' \
-///     | sort -u
+/// # Examples
+/// ```
+/// use notemancy_core::tags::{TagSource, extract_tags};
+///
+/// let tags = extract_tags("---\ntags: [rust, project/notemancy]\n---\nSee #todo/today.\n");
+/// let names: Vec<_> = tags.iter().map(|t| (t.tag.as_str(), t.source)).collect();
+/// assert_eq!(
+///     names,
+///     vec![
+///         ("rust", TagSource::Frontmatter),
+///         ("project/notemancy", TagSource::Frontmatter),
+///         ("todo/today", TagSource::Inline),
+///     ]
+/// );
+/// ```
+pub fn extract_tags(content: &str) -> Vec<TagOccurrence> {
+    let (mut tags, body_start) = match Frontmatter::split(content) {
+        Some((yaml, body)) => {
+            let yaml_start = yaml.as_ptr() as usize - content.as_ptr() as usize;
+            let body_start = body.as_ptr() as usize - content.as_ptr() as usize;
+            (
+                frontmatter_tags(content, yaml_start..yaml_start + yaml.len()),
+                body_start,
+            )
+        }
+        None => (Vec::new(), 0),
+    };
+    tags.extend(inline_tags(content, body_start));
+    tags
+}
+
+/// The distinct tags of a note, frontmatter tags first, in order of first appearance.
+///
+/// This is the tag list kept in the vault index and matched by tag conditions in
+/// queries, so that inline `#hashtags` count the same as frontmatter tags.
+pub(crate) fn note_tags(content: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    extract_tags(content)
+        .into_iter()
+        .filter(|occurrence| seen.insert(occurrence.tag.clone()))
+        .map(|occurrence| occurrence.tag)
+        .collect()
+}
+
+/// Returns true if `tag` is `ancestor` or, with `include_descendants`, a tag nested
+/// under it (`project/notemancy` under `project`).
+pub fn is_tag_or_descendant(tag: &str, ancestor: &str, include_descendants: bool) -> bool {
+    tag == ancestor
+        || (include_descendants
+            && tag
+                .strip_prefix(ancestor)
+                .is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// The vault file holding the tag normalization policy and aliases.
+pub const TAG_POLICY_FILE: &str = "tags.yaml";
+
+/// Separators normalized to `-` inside a tag: whitespace, `_` and runs of `-`.
+static SEPARATOR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\s_\-]+").unwrap());
+
+/// How the tags of a vault are resolved to a canonical form, read from `tags.yaml` at
+/// the root of the vault:
+///
+/// ```yaml
+/// fold_case: true
+/// normalize_separators: true
+/// aliases:
+///   cli: [command-line, terminal]
+/// ```
+///
+/// Tag listing, `get_notes_by_tag`, `get_tag_stats` and tag conditions in queries
+/// compare canonical forms, so with this policy `CLI`, `cli`, `command_line` and
+/// `Terminal/zsh` are read as `cli`, `cli`, `cli` and `cli/zsh`. Without the file, tags
+/// are compared as written.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagPolicy {
+    /// Compare tags in lowercase
+    #[serde(default)]
+    pub fold_case: bool,
+    /// Replace whitespace, `_` and runs of `-` in tags by a single `-`
+    #[serde(default)]
+    pub normalize_separators: bool,
+    /// Canonical tags and the other names they are known by. Tags nested under an
+    /// alias are moved under the canonical tag.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl TagPolicy {
+    /// Reads the tag policy of the vault, or the default policy (tags compared as
+    /// written) if the vault has no `tags.yaml`.
+    ///
+    /// # Arguments
+    /// * `vault_directory` - The base directory of the vault
+    ///
+    /// # Returns
+    /// * `Result<TagPolicy>` - The tag policy of the vault
+    ///
+    /// # Errors
+    /// * Returns an error if `tags.yaml` can't be read or parsed
+    pub fn load(vault_directory: &Path) -> Result<Self> {
+        let path = vault_directory.join(TAG_POLICY_FILE);
+        if !path.exists() {
+            return Ok(TagPolicy::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read tag policy file: {:?}", path))?;
+        if content.trim().is_empty() {
+            return Ok(TagPolicy::default());
+        }
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse tag policy file: {:?}", path))
+    }
+
+    /// Returns true if the policy leaves every tag as written.
+    pub fn is_identity(&self) -> bool {
+        !self.fold_case && !self.normalize_separators && self.aliases.is_empty()
+    }
+
+    /// Applies case folding and separator normalization to a tag, without aliases.
+    pub fn normalize(&self, tag: &str) -> String {
+        let mut tag = tag.to_string();
+        if self.normalize_separators {
+            tag = tag
+                .split('/')
+                .map(|segment| SEPARATOR_RE.replace_all(segment.trim(), "-"))
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+        if self.fold_case {
+            tag = tag.to_lowercase();
+        }
+        tag
+    }
+
+    /// Returns the canonical form of a tag: the tag normalized, then resolved through
+    /// the aliases. When several aliases match, the longest one wins.
+    ///
+    /// # Examples
+    /// ```
+    /// use notemancy_core::tags::TagPolicy;
+    ///
+    /// let mut policy = TagPolicy {
+    ///     fold_case: true,
+    ///     normalize_separators: true,
+    ///     ..TagPolicy::default()
+    /// };
+    /// policy.aliases.insert("cli".to_string(), vec!["command-line".to_string()]);
+    /// assert_eq!(policy.canonical("Command_Line/zsh"), "cli/zsh");
+    /// assert_eq!(policy.canonical("Rust"), "rust");
+    /// ```
+    pub fn canonical(&self, tag: &str) -> String {
+        let tag = self.normalize(tag);
+        let mut best: Option<(usize, &String)> = None;
+        for (canonical, aliases) in &self.aliases {
+            for name in std::iter::once(canonical).chain(aliases) {
+                let name = self.normalize(name);
+                if is_tag_or_descendant(&tag, &name, true)
+                    && best.is_none_or(|(length, _)| name.len() > length)
+                {
+                    best = Some((name.len(), canonical));
+                }
+            }
+        }
+        match best {
+            Some((length, canonical)) => format!("{}{}", self.normalize(canonical), &tag[length..]),
+            None => tag,
+        }
+    }
+
+    /// Helper function to get the canonical forms of a list of tags, borrowing the list
+    /// when the policy leaves tags as written.
+    pub(crate) fn canonical_tags<'a>(&self, tags: &'a [String]) -> Cow<'a, [String]> {
+        if self.is_identity() {
+            Cow::Borrowed(tags)
+        } else {
+            Cow::Owned(tags.iter().map(|tag| self.canonical(tag)).collect())
+        }
+    }
+}
+
+/// Returns a list of all unique tags found in the vault directory.
 ///
+/// Tags are read from the frontmatter and from inline `#hashtags` (see `extract_tags`).
+/// Empty items and separator-like items (such as `---`) are ignored. Tags are resolved
+/// to their canonical form through the vault's `TagPolicy`. The result is sorted and
+/// deduplicated.
 pub fn get_all_tags(vault_directory: &Path) -> Result<Vec<String>> {
-    let command = "rg -U -oP '(?s)^---.*?^tags:\\s*\\n((?:\\s*-\\s*.*\\n)+)' --no-filename . | \
-                   rg -oP '^\\s*-\\s*\\K.*' | \
-                   rg -v '^\\s*$' | \
-                   rg -v '^[-ー]+$' | \
-                   sort -u";
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .current_dir(vault_directory)
-        .output()
-        .context("Failed to execute rg command for get_all_tags")?;
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "get_all_tags command failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    let policy = TagPolicy::load(vault_directory)?;
+    let mut tags = BTreeSet::new();
+    for path in walk_markdown_files(vault_directory)? {
+        // Skip files that are not valid UTF-8; they can't hold a parseable tag list.
+        if let Ok(content) = fs::read_to_string(&path) {
+            tags.extend(
+                extract_tags(&content)
+                    .iter()
+                    .map(|t| policy.canonical(&t.tag)),
+            );
+        }
     }
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let tags: Vec<String> = stdout
-        .lines()
-        .map(|s| s.to_string())
-        .filter(|s| !s.trim().is_empty())
+    Ok(tags.into_iter().collect())
+}
+
+/// Returns a list of all unique tags using a `VaultIndex` instead of reading the vault.
+///
+/// The tags recorded in the index are resolved through the vault's `TagPolicy` like
+/// `get_all_tags` does.
+///
+/// # Arguments
+/// * `index` - A refreshed index of the vault
+///
+/// # Returns
+/// * `Result<Vec<String>>` - The canonical tags of the vault, sorted and deduplicated
+///
+/// # Errors
+/// * Returns an error if the tag policy can't be read
+pub fn get_all_tags_indexed(index: &VaultIndex) -> Result<Vec<String>> {
+    let policy = TagPolicy::load(index.vault_directory())?;
+    let tags: BTreeSet<String> = index
+        .entries()
+        .flat_map(|entry| entry.tags.iter().map(|tag| policy.canonical(tag)))
         .collect();
-    Ok(tags)
+    Ok(tags.into_iter().collect())
+}
+
+/// Returns the tags of a note with where they were found.
+///
+/// # Arguments
+/// * `relative_path` - The path of the note relative to the vault directory
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Vec<TagOccurrence>>` - The tags of the note (see `extract_tags`)
+///
+/// # Errors
+/// * Returns an error if the note can't be read
+pub fn get_note_tags(relative_path: &str, vault_directory: &Path) -> Result<Vec<TagOccurrence>> {
+    let path = vault_directory.join(relative_path);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read note file: {}", path.display()))?;
+    Ok(extract_tags(&content))
 }
 
 /// Returns a list of all notes (as tuples of relative path and title)
-/// that have the given tag. This function builds a regex that matches YAML
-/// frontmatter containing a list of tags in which one of the items exactly
-/// matches the provided tag. Then it executes ripgrep to list matching markdown files.
-/// For each file, it extracts the note title using get_title.
-///
-/// The command used is of the form:
-///
-///   rg -l -U -P '(?s)^---.*?\n\s*-\s*<TAG>\s*\n.*?^---' --glob '*.md'
-///
-pub fn get_notes_by_tag(tag: &str, vault_directory: &Path) -> Result<Vec<(String, String)>> {
-    // Escape the tag for use in the regex pattern.
-    let escaped_tag = escape(tag);
-    let pattern = format!("(?s)^---.*?\\n\\s*-\\s*{}\\s*\\n.*?^---", escaped_tag);
-    let command = format!("rg -l -U -P '{}' --glob '*.md'", pattern);
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(&command)
-        .current_dir(vault_directory)
-        .output()
-        .context("Failed to execute rg command for get_notes_by_tag")?;
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "get_notes_by_tag command failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// that have the given tag.
+///
+/// A note matches when one of its tags, in the frontmatter or inline, is exactly the
+/// provided tag or, with `include_descendants`, nested under it: `project` then also
+/// matches `project/notemancy`. Tags are compared in their canonical form (see
+/// `TagPolicy`). For each matching file, the note title is extracted using get_title.
+pub fn get_notes_by_tag(
+    tag: &str,
+    vault_directory: &Path,
+    include_descendants: bool,
+) -> Result<Vec<(String, String)>> {
+    let policy = TagPolicy::load(vault_directory)?;
+    let tag = policy.canonical(tag);
     let mut results = Vec::new();
-    for line in stdout.lines().filter(|s| !s.trim().is_empty()) {
-        // The output is a file path relative to vault_directory.
-        let rel_path = line.trim().to_string();
-        let file_path = vault_directory.join(&rel_path);
-        // Use get_title to extract the note title.
-        let title = get_title(&file_path).unwrap_or_else(|_| String::from("<No Title>"));
-        results.push((rel_path, title));
+    for path in walk_markdown_files(vault_directory)? {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if extract_tags(&content)
+            .iter()
+            .any(|t| is_tag_or_descendant(&policy.canonical(&t.tag), &tag, include_descendants))
+        {
+            let rel_path = relative_note_path(&path, vault_directory);
+            // Use get_title to extract the note title.
+            let title = get_title(&path).unwrap_or_else(|_| String::from("<No Title>"));
+            results.push((rel_path, title));
+        }
     }
     Ok(results)
 }
 
+/// Returns the notes that have the given tag using a `VaultIndex` instead of reading
+/// the vault.
+///
+/// Tags are matched as in `get_notes_by_tag`, and titles come from the index.
+///
+/// # Arguments
+/// * `tag` - The tag to look for
+/// * `index` - A refreshed index of the vault
+/// * `include_descendants` - If true, tags nested under `tag` match as well
+///
+/// # Returns
+/// * `Result<Vec<(String, String)>>` - The (relative path, title) pairs of the matching
+///   notes, ordered by path
+///
+/// # Errors
+/// * Returns an error if the tag policy can't be read
+pub fn get_notes_by_tag_indexed(
+    tag: &str,
+    index: &VaultIndex,
+    include_descendants: bool,
+) -> Result<Vec<(String, String)>> {
+    let policy = TagPolicy::load(index.vault_directory())?;
+    let tag = policy.canonical(tag);
+    Ok(index
+        .entries()
+        .filter(|entry| {
+            entry
+                .tags
+                .iter()
+                .any(|t| is_tag_or_descendant(&policy.canonical(t), &tag, include_descendants))
+        })
+        .map(|entry| (entry.path.clone(), entry.title.clone()))
+        .collect())
+}
+
+/// How much a tag is used in the vault.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagUsage {
+    pub tag: String,
+    /// Number of notes having the tag
+    pub notes: usize,
+    /// Number of times the tag appears, in the frontmatter or inline
+    pub occurrences: usize,
+    /// The earliest `created_on` date (`YYYY-MM-DD`) of the notes having the tag
+    pub first_used: Option<String>,
+    /// The latest `created_on` date (`YYYY-MM-DD`) of the notes having the tag
+    pub last_used: Option<String>,
+}
+
+/// Statistics about the tags of the vault, as returned by `get_tag_stats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TagStats {
+    /// The usage of each tag, sorted by tag
+    pub usage: Vec<TagUsage>,
+    /// For each tag, the number of notes it shares with each other tag. The matrix is
+    /// symmetric and sparse: tags that never appear together are left out.
+    pub cooccurrence: BTreeMap<String, BTreeMap<String, usize>>,
+    /// The tags used in a single note, sorted
+    pub orphans: Vec<String>,
+}
+
+impl TagStats {
+    /// Returns the usage of a tag, if it is used in the vault.
+    pub fn get(&self, tag: &str) -> Option<&TagUsage> {
+        self.usage
+            .binary_search_by(|usage| usage.tag.as_str().cmp(tag))
+            .ok()
+            .map(|i| &self.usage[i])
+    }
+}
+
+/// Computes usage counts, first and last use, co-occurrence and orphans for the tags of
+/// the vault.
+///
+/// Tags are read as for `get_all_tags`. First and last use come from the `created_on`
+/// field of the notes; notes without a valid date are counted but don't date the tag.
+/// Two tags co-occur when they appear in the same note.
+///
+/// # Arguments
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<TagStats>` - The statistics, which serialize to JSON
+///
+/// # Errors
+/// * Returns an error if the vault directory can't be walked
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::tags::get_tag_stats;
+///
+/// let stats = get_tag_stats(Path::new("/path/to/vault"))?;
+/// println!("{}", serde_json::to_string_pretty(&stats)?);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn get_tag_stats(vault_directory: &Path) -> Result<TagStats> {
+    let policy = TagPolicy::load(vault_directory)?;
+    let mut usage: BTreeMap<String, TagUsage> = BTreeMap::new();
+    let mut cooccurrence: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+
+    for path in walk_markdown_files(vault_directory)? {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let occurrences = extract_tags(&content);
+        if occurrences.is_empty() {
+            continue;
+        }
+        let created = Frontmatter::from_content(&content)
+            .ok()
+            .flatten()
+            .and_then(|(frontmatter, _)| frontmatter.created_date())
+            .map(|date| date.to_string());
+
+        let mut note_tags = BTreeSet::new();
+        for occurrence in occurrences {
+            let tag = policy.canonical(&occurrence.tag);
+            let entry = usage.entry(tag.clone()).or_insert_with(|| TagUsage {
+                tag: tag.clone(),
+                notes: 0,
+                occurrences: 0,
+                first_used: None,
+                last_used: None,
+            });
+            entry.occurrences += 1;
+            if note_tags.insert(tag) {
+                entry.notes += 1;
+                if let Some(created) = &created {
+                    // Dates in YYYY-MM-DD form compare like the dates themselves.
+                    if entry
+                        .first_used
+                        .as_ref()
+                        .is_none_or(|first| created < first)
+                    {
+                        entry.first_used = Some(created.clone());
+                    }
+                    if entry.last_used.as_ref().is_none_or(|last| created > last) {
+                        entry.last_used = Some(created.clone());
+                    }
+                }
+            }
+        }
+
+        for tag in &note_tags {
+            for other in &note_tags {
+                if tag != other {
+                    *cooccurrence
+                        .entry(tag.clone())
+                        .or_default()
+                        .entry(other.clone())
+                        .or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let orphans = usage
+        .values()
+        .filter(|usage| usage.notes == 1)
+        .map(|usage| usage.tag.clone())
+        .collect();
+    Ok(TagStats {
+        usage: usage.into_values().collect(),
+        cooccurrence,
+        orphans,
+    })
+}
+
+/// Helper function to collect the items of the `tags` field in the frontmatter, which
+/// spans the byte range `yaml` of the content.
+fn frontmatter_tags(content: &str, yaml: Range<usize>) -> Vec<TagOccurrence> {
+    let line_number = |offset: usize| content[..offset].matches('\n').count() + 1;
+    let mut items: Vec<Range<usize>> = Vec::new();
+
+    let mut lines = content[yaml.clone()]
+        .split_inclusive('\n')
+        .scan(yaml.start, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line.trim_end_matches(['\n', '\r'])))
+        })
+        .peekable();
+    while let Some((start, line)) = lines.next() {
+        let Some(rest) = line.strip_prefix("tags:") else {
+            continue;
+        };
+        let rest_start = start + line.len() - rest.len();
+        let value = strip_comment(rest);
+        if value.trim().is_empty() {
+            // A block list: the following `- item` lines.
+            while let Some((item_start, item_line)) = lines.peek().copied() {
+                let trimmed = item_line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    lines.next();
+                    continue;
+                }
+                let Some(item) = trimmed.strip_prefix('-') else {
+                    break;
+                };
+                if !item.is_empty() && !item.starts_with([' ', '\t']) {
+                    break;
+                }
+                let item_offset = item_start + item_line.len() - item.len();
+                items.push(scalar_range(
+                    content,
+                    item_offset..item_offset + strip_comment(item).len(),
+                ));
+                lines.next();
+            }
+        } else if value.trim_start().starts_with('[') {
+            // A flow list, possibly continued on the following lines.
+            let open = rest_start + rest.find('[').unwrap_or(0) + 1;
+            let mut end = open;
+            let mut quote = None;
+            let mut item_start = open;
+            while let Some(c) = content[end..yaml.end].chars().next() {
+                match (quote, c) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => {}
+                    (None, '"' | '\'') => quote = Some(c),
+                    (None, ',' | ']') => {
+                        items.push(scalar_range(content, item_start..end));
+                        item_start = end + 1;
+                        if c == ']' {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                end += c.len_utf8();
+            }
+        } else {
+            items.push(scalar_range(content, rest_start..rest_start + value.len()));
+        }
+        break;
+    }
+
+    items
+        .into_iter()
+        .map(|range| {
+            // A leading `#` is part of the markup, not of the tag.
+            let hash = usize::from(content[range.clone()].starts_with('#'));
+            range.start + hash..range.end
+        })
+        .filter(|range| {
+            let item = &content[range.clone()];
+            !item.is_empty() && !item.chars().all(|c| c == '-' || c == 'ー')
+        })
+        .map(|span| TagOccurrence {
+            tag: content[span.clone()].to_string(),
+            source: TagSource::Frontmatter,
+            line: line_number(span.start),
+            span,
+        })
+        .collect()
+}
+
+/// Helper function to drop a trailing YAML comment (` # ...`) outside of quotes.
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') if previous.is_whitespace() => return &text[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    text
+}
+
+/// Helper function to narrow the range of a YAML scalar to its text, without
+/// surrounding whitespace and quotes.
+fn scalar_range(content: &str, range: Range<usize>) -> Range<usize> {
+    let text = &content[range.clone()];
+    let start = range.start + text.len() - text.trim_start().len();
+    let end = range.end - (text.len() - text.trim_end().len());
+    let trimmed = &content[start..end];
+    for quote in ['"', '\''] {
+        if trimmed.len() >= 2 && trimmed.starts_with(quote) && trimmed.ends_with(quote) {
+            return start + 1..end - 1;
+        }
+    }
+    start..end
+}
+
+/// Helper function to find the `#hashtags` of the body, which starts at `body_start`.
+fn inline_tags(content: &str, body_start: usize) -> Vec<TagOccurrence> {
+    let mut tags = Vec::new();
+    for (line_number, line_start, line) in unfenced_lines(content, body_start) {
+        let code = code_spans(line);
+        for caps in HASHTAG_RE.captures_iter(line) {
+            let tag = caps.get(1).unwrap();
+            let tag_text = tag.as_str().trim_end_matches('/');
+            if code.iter().any(|span| span.contains(&tag.start()))
+                || tag_text.chars().all(|c| c.is_ascii_digit() || c == '/')
+            {
+                continue;
+            }
+            let start = line_start + tag.start();
+            tags.push(TagOccurrence {
+                tag: tag_text.to_string(),
+                source: TagSource::Inline,
+                span: start..start + tag_text.len(),
+                line: line_number,
+            });
+        }
+    }
+    tags
+}
+
+/// The change made to one note by `rename_tag`, `merge_tags` or `delete_tag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagEdit {
+    /// Path of the note relative to the vault directory
+    pub path: String,
+    /// Number of tag occurrences that were rewritten or removed
+    pub changes: usize,
+    /// The changed lines as a unified diff, for previewing a dry run
+    pub diff: String,
+}
+
+/// What happens to a tag occurrence in a rewrite: kept, renamed or removed.
+enum TagChange {
+    Keep,
+    Rename(String),
+    Remove,
+}
+
+/// Renames a tag in every note of the vault.
+///
+/// Both frontmatter tags and inline `#hashtags` are rewritten, and tags nested under
+/// the old tag move with it (`project/api` becomes `work/api` when renaming `project`
+/// to `work`). Only the tag text is changed; the rest of each file is kept byte for
+/// byte. A note that already has the new tag in its frontmatter doesn't get it twice.
+///
+/// Tags are compared in their canonical form under the vault's `TagPolicy`, so with
+/// `fold_case` renaming `cli` to `shell` also renames `CLI`, and `Cli/zsh` to `shell/zsh`.
+///
+/// # Arguments
+/// * `old_tag` - The tag to rename
+/// * `new_tag` - The new name of the tag
+/// * `vault_directory` - The base directory of the vault
+/// * `dry_run` - If true, nothing is written and the edits are only returned
+///
+/// # Returns
+/// * `Result<Vec<TagEdit>>` - The notes that are (or would be) changed, sorted by path
+///
+/// # Errors
+/// * Returns an error if the new tag isn't a valid tag name
+/// * Returns an error if the tag policy can't be read
+/// * Returns an error if a note can't be read or written
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::tags::rename_tag;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// for edit in rename_tag("cli", "command-line", vault_dir, true)? {
+///     println!("{}", edit.diff);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn rename_tag(
+    old_tag: &str,
+    new_tag: &str,
+    vault_directory: &Path,
+    dry_run: bool,
+) -> Result<Vec<TagEdit>> {
+    merge_tags(&[old_tag], new_tag, vault_directory, dry_run)
+}
+
+/// Merges several tags into one in every note of the vault.
+///
+/// Each of the `tags`, and the tags nested under them, are renamed as for `rename_tag`.
+/// Tags that are already `into` or nested under it are left as they are, so merging
+/// `project` into `project/old` doesn't turn `project/old` into `project/old/old`.
+///
+/// # Arguments
+/// * `tags` - The tags to merge
+/// * `into` - The tag they are merged into (which may be one of them)
+/// * `vault_directory` - The base directory of the vault
+/// * `dry_run` - If true, nothing is written and the edits are only returned
+///
+/// # Returns
+/// * `Result<Vec<TagEdit>>` - The notes that are (or would be) changed, sorted by path
+///
+/// # Errors
+/// * Returns an error if the target tag isn't a valid tag name
+/// * Returns an error if the tag policy can't be read
+/// * Returns an error if a note can't be read or written
+pub fn merge_tags(
+    tags: &[&str],
+    into: &str,
+    vault_directory: &Path,
+    dry_run: bool,
+) -> Result<Vec<TagEdit>> {
+    if !is_valid_tag(into) {
+        return Err(anyhow!(
+            "Invalid tag '{}': tags are made of letters, digits, '_', '-' and '/'",
+            into
+        ));
+    }
+    let policy = TagPolicy::load(vault_directory)?;
+    let target = policy.canonical(into);
+    let sources: Vec<&str> = tags
+        .iter()
+        .copied()
+        .filter(|tag| policy.canonical(tag) != target)
+        .collect();
+    let canonical_sources: Vec<String> = sources.iter().map(|tag| policy.canonical(tag)).collect();
+    rewrite_tags(vault_directory, &policy, &sources, dry_run, |tag| {
+        let canonical = policy.canonical(tag);
+        // Tags already at the target stay, even when it is nested under a source.
+        if is_tag_or_descendant(&canonical, &target, true) {
+            return TagChange::Keep;
+        }
+        canonical_sources
+            .iter()
+            .find(|source| is_tag_or_descendant(&canonical, source, true))
+            .map_or(TagChange::Keep, |source| {
+                let depth = canonical[source.len()..].matches('/').count();
+                TagChange::Rename(format!("{}{}", into, nested_suffix(tag, depth)))
+            })
+    })
+}
+
+/// Helper function to get the last `depth` segments of a tag, with their leading `/`
+/// (`/zsh` for `Terminal/zsh` and a depth of 1). The policy rewrites the leading
+/// segments of a tag but keeps their number after an alias, so the nested part is
+/// taken from the tag as written.
+fn nested_suffix(tag: &str, depth: usize) -> &str {
+    match depth.checked_sub(1) {
+        Some(skip) => tag
+            .rmatch_indices('/')
+            .nth(skip)
+            .map_or("", |(index, _)| &tag[index..]),
+        None => "",
+    }
+}
+
+/// Removes a tag from every note of the vault.
+///
+/// The tag is removed from frontmatter lists and inline `#hashtags` are deleted from
+/// the text. Tags nested under it are kept. The rest of each file is kept byte for byte.
+/// Tags are compared in their canonical form under the vault's `TagPolicy`.
+///
+/// # Arguments
+/// * `tag` - The tag to remove
+/// * `vault_directory` - The base directory of the vault
+/// * `dry_run` - If true, nothing is written and the edits are only returned
+///
+/// # Returns
+/// * `Result<Vec<TagEdit>>` - The notes that are (or would be) changed, sorted by path
+///
+/// # Errors
+/// * Returns an error if the tag policy can't be read
+/// * Returns an error if a note can't be read or written
+pub fn delete_tag(tag: &str, vault_directory: &Path, dry_run: bool) -> Result<Vec<TagEdit>> {
+    let policy = TagPolicy::load(vault_directory)?;
+    let canonical = policy.canonical(tag);
+    rewrite_tags(vault_directory, &policy, &[tag], dry_run, |t| {
+        if policy.canonical(t) == canonical {
+            TagChange::Remove
+        } else {
+            TagChange::Keep
+        }
+    })
+}
+
+/// Returns true if the text can be used as a tag, both in the frontmatter and inline.
+fn is_valid_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && !tag.starts_with('/')
+        && !tag.ends_with('/')
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+        && !tag.chars().all(|c| c.is_ascii_digit() || c == '/')
+}
+
+/// Helper function to apply a tag change to the notes having one of the given tags
+/// (or a tag nested under them), found with `get_notes_by_tag`.
+fn rewrite_tags(
+    vault_directory: &Path,
+    policy: &TagPolicy,
+    tags: &[&str],
+    dry_run: bool,
+    change: impl Fn(&str) -> TagChange,
+) -> Result<Vec<TagEdit>> {
+    let mut paths = BTreeSet::new();
+    for tag in tags {
+        paths.extend(
+            get_notes_by_tag(tag, vault_directory, true)?
+                .into_iter()
+                .map(|(path, _)| path),
+        );
+    }
+
+    let mut edits = Vec::new();
+    for path in paths {
+        let file_path = vault_directory.join(&path);
+        let content = fs::read_to_string(&file_path)
+            .with_context(|| format!("Failed to read note file: {}", file_path.display()))?;
+        let (new_content, changes) = rewrite_note_tags(&content, policy, &change);
+        if changes == 0 {
+            continue;
+        }
+        if !dry_run {
+            fs::write(&file_path, &new_content)
+                .with_context(|| format!("Failed to write note file: {}", file_path.display()))?;
+        }
+        edits.push(TagEdit {
+            diff: line_diff(&path, &content, &new_content),
+            path,
+            changes,
+        });
+    }
+    Ok(edits)
+}
+
+/// Helper function to apply a tag change to the content of a note.
+///
+/// Returns the new content and the number of changed tag occurrences. Frontmatter tags
+/// are deduplicated by their canonical form under the policy.
+fn rewrite_note_tags(
+    content: &str,
+    policy: &TagPolicy,
+    change: &impl Fn(&str) -> TagChange,
+) -> (String, usize) {
+    let occurrences = extract_tags(content);
+    let mut frontmatter_tags: BTreeSet<String> = occurrences
+        .iter()
+        .filter(|t| t.source == TagSource::Frontmatter && matches!(change(&t.tag), TagChange::Keep))
+        .map(|t| policy.canonical(&t.tag))
+        .collect();
+
+    let mut replacements: Vec<(Range<usize>, String)> = Vec::new();
+    for occurrence in &occurrences {
+        let action = match change(&occurrence.tag) {
+            TagChange::Keep => continue,
+            // A frontmatter list keeps a single copy of each tag.
+            TagChange::Rename(new_tag)
+                if occurrence.source == TagSource::Frontmatter
+                    && !frontmatter_tags.insert(policy.canonical(&new_tag)) =>
+            {
+                TagChange::Remove
+            }
+            action => action,
+        };
+        replacements.push(match (action, occurrence.source) {
+            (TagChange::Rename(new_tag), _) => (occurrence.span.clone(), new_tag),
+            (_, TagSource::Frontmatter) => (
+                frontmatter_item_range(content, &occurrence.span),
+                String::new(),
+            ),
+            (_, TagSource::Inline) => (hashtag_range(content, &occurrence.span), String::new()),
+        });
+    }
+
+    let changes = replacements.len();
+    let mut new_content = content.to_string();
+    for (range, replacement) in replacements.into_iter().rev() {
+        new_content.replace_range(range, &replacement);
+    }
+    (new_content, changes)
+}
+
+/// Helper function to find the text to remove for a frontmatter tag: its whole line in
+/// a block list, the item and a comma in a flow list, or the value of a single tag.
+fn frontmatter_item_range(content: &str, span: &Range<usize>) -> Range<usize> {
+    let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[span.end..]
+        .find('\n')
+        .map_or(content.len(), |i| span.end + i + 1);
+    let before = &content[line_start..span.start];
+
+    // Include the quotes around the tag.
+    let quoted = span.start > 0
+        && matches!(content.as_bytes()[span.start - 1], b'"' | b'\'')
+        && content.as_bytes().get(span.end) == Some(&content.as_bytes()[span.start - 1]);
+    let item = if quoted {
+        span.start - 1..span.end + 1
+    } else {
+        span.clone()
+    };
+
+    if before.trim_start().starts_with('-') && !before.contains('[') {
+        return line_start..line_end;
+    }
+    if before.starts_with("tags:")
+        && before["tags:".len()..]
+            .trim()
+            .trim_matches(['"', '\''])
+            .is_empty()
+    {
+        return line_start + "tags:".len()..item.end;
+    }
+
+    // A flow list item: remove it with the comma after it, or else the one before it.
+    let after = &content[item.end..];
+    let next = after.len() - after.trim_start().len();
+    if after.trim_start().starts_with(',') {
+        let rest = &after[next + 1..];
+        return item.start
+            ..item.end + next + 1 + (rest.len() - rest.trim_start_matches([' ', '\t']).len());
+    }
+    let previous = content[..item.start].trim_end();
+    if previous.ends_with(',') {
+        return previous.len() - 1..item.end;
+    }
+    item
+}
+
+/// Helper function to find the text to remove for an inline hashtag: the `#tag` and
+/// one space next to it.
+fn hashtag_range(content: &str, span: &Range<usize>) -> Range<usize> {
+    let start = span.start - 1;
+    let before = content[..start].chars().next_back();
+    let after = content[span.end..].chars().next();
+    match (before, after) {
+        (Some(' ' | '\t'), _) => start - 1..span.end,
+        (_, Some(' ' | '\t')) => start..span.end + 1,
+        _ => start..span.end,
+    }
+}
+
+/// Helper function to render the lines that differ between two versions of a file as
+/// a unified diff without context lines.
+fn line_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    // Tag rewrites only change or remove lines, so the lines are matched greedily.
+    let mut diff = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len() {
+        if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (hunk_i, hunk_j) = (i, j);
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        while i < old_lines.len() && (j >= new_lines.len() || old_lines[i] != new_lines[j]) {
+            removed.push(old_lines[i]);
+            // A line removed outright is followed by the same next line in both versions.
+            let line_removed = new_lines.get(j) == old_lines.get(i + 1);
+            i += 1;
+            if !line_removed && j < new_lines.len() {
+                added.push(new_lines[j]);
+                j += 1;
+            }
+        }
+        if i >= old_lines.len() {
+            added.extend(&new_lines[j..]);
+            j = new_lines.len();
+        }
+        diff.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk_i + 1,
+            removed.len(),
+            hunk_j + 1,
+            added.len()
+        ));
+        for line in removed {
+            diff.push_str(&format!("-{}\n", line));
+        }
+        for line in added {
+            diff.push_str(&format!("+{}\n", line));
+        }
+    }
+    diff
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,7 +1073,7 @@ mod tests {
         create_markdown_note(vault_dir, "note3.md", "Note Three", &["CLI"])?;
 
         // Search for notes with tag "CLI"
-        let mut notes_cli = get_notes_by_tag("CLI", vault_dir)?;
+        let mut notes_cli = get_notes_by_tag("CLI", vault_dir, false)?;
         notes_cli.sort_by(|a, b| a.0.cmp(&b.0));
         let expected_cli = vec![
             (String::from("note1.md"), String::from("Note One")),
@@ -156,7 +1082,7 @@ mod tests {
         assert_eq!(notes_cli, expected_cli);
 
         // Search for notes with tag "rust"
-        let mut notes_rust = get_notes_by_tag("rust", vault_dir)?;
+        let mut notes_rust = get_notes_by_tag("rust", vault_dir, false)?;
         notes_rust.sort_by(|a, b| a.0.cmp(&b.0));
         let expected_rust = vec![
             (String::from("note1.md"), String::from("Note One")),
@@ -164,6 +1090,315 @@ mod tests {
         ];
         assert_eq!(notes_rust, expected_rust);
 
+        // The index gives the same answers, inline tags included.
+        fs::write(
+            vault_dir.join("note4.md"),
+            "Only inline #rust/async here.\n",
+        )?;
+        let index = VaultIndex::open(vault_dir)?;
+        assert_eq!(
+            get_notes_by_tag_indexed("rust", &index, false)?,
+            expected_rust
+        );
+        assert_eq!(
+            get_notes_by_tag_indexed("rust", &index, true)?.len(),
+            get_notes_by_tag("rust", vault_dir, true)?.len()
+        );
+        assert_eq!(get_all_tags_indexed(&index)?, get_all_tags(vault_dir)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tags_with_quotes_and_body_lists() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        // A tag containing a single quote used to break the shell command line.
+        create_markdown_note(vault_dir, "note1.md", "Note One", &["don't", "rust"])?;
+        // List items in the body are not tags.
+        fs::write(
+            vault_dir.join("note2.md"),
+            "---\ntitle: Note Two\ntags:\n  - rust\n---\n\n- buy milk\n",
+        )?;
+
+        let tags = get_all_tags(vault_dir)?;
+        assert_eq!(tags, vec!["don't".to_string(), "rust".to_string()]);
+
+        let notes = get_notes_by_tag("don't", vault_dir, false)?;
+        assert_eq!(
+            notes,
+            vec![(String::from("note1.md"), String::from("Note One"))]
+        );
+
+        let notes = get_notes_by_tag("buy milk", vault_dir, false)?;
+        assert!(notes.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        let tag_names = |content: &str| -> Vec<(String, TagSource)> {
+            extract_tags(content)
+                .into_iter()
+                .map(|t| (t.tag, t.source))
+                .collect()
+        };
+        let frontmatter = |tags: &[&str]| -> Vec<(String, TagSource)> {
+            tags.iter()
+                .map(|t| (t.to_string(), TagSource::Frontmatter))
+                .collect()
+        };
+
+        assert_eq!(
+            tag_names("---\ntags: [rust, \"two words\", 'cli'] # comment\n---\n"),
+            frontmatter(&["rust", "two words", "cli"])
+        );
+        assert_eq!(
+            tag_names("---\ntitle: x\ntags: single\n---\n"),
+            frontmatter(&["single"])
+        );
+        assert_eq!(
+            tag_names("---\ntags:\n  - a/b\n  # skipped\n  - \"#c\"\nother: [d]\n---\n"),
+            frontmatter(&["a/b", "c"])
+        );
+        assert_eq!(
+            tag_names("---\ntags: [a,\n  b]\n---\n"),
+            frontmatter(&["a", "b"])
+        );
+
+        let content = "---\ncolor: \"#fff\"\n---\n# Heading\n#todo and #project/notemancy/ but not #123,\n`#code` [[Note#Heading]] x#y\n```\n#fenced\n```\n#done\n";
+        let tags = extract_tags(content);
+        let names: Vec<_> = tags.iter().map(|t| t.tag.as_str()).collect();
+        assert_eq!(names, vec!["todo", "project/notemancy", "done"]);
+        assert!(tags.iter().all(|t| t.source == TagSource::Inline));
+        assert_eq!(
+            (tags[1].line, &content[tags[1].span.clone()]),
+            (5, "project/notemancy")
+        );
+        assert_eq!(tags[2].line, 10);
+    }
+
+    #[test]
+    fn test_nested_and_inline_tags() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("api.md"),
+            "---\ntitle: API\ntags: [project/notemancy/api]\n---\n",
+        )?;
+        fs::write(
+            vault_dir.join("plan.md"),
+            "---\ntitle: Plan\n---\nWork on #project\n",
+        )?;
+        fs::write(vault_dir.join("other.md"), "Mentions #projects only\n")?;
+
+        assert_eq!(
+            get_all_tags(vault_dir)?,
+            vec!["project", "project/notemancy/api", "projects"]
+        );
+        let paths = |notes: Vec<(String, String)>| -> Vec<String> {
+            notes.into_iter().map(|(path, _)| path).collect()
+        };
+        assert_eq!(
+            paths(get_notes_by_tag("project", vault_dir, false)?),
+            vec!["plan.md"]
+        );
+        assert_eq!(
+            paths(get_notes_by_tag("project", vault_dir, true)?),
+            vec!["api.md", "plan.md"]
+        );
+        assert_eq!(
+            get_note_tags("plan.md", vault_dir)?[0].source,
+            TagSource::Inline
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename_and_merge_tags() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        let note1 = "---\ntitle: One\ntags: [project, \"project/api\", rust]\n---\n\nSee #project/web and #other.\n";
+        let note2 =
+            "---\ntitle: Two\ntags:\n  - project\n  - work # kept\n---\n\nNothing inline.\n";
+        fs::write(vault_dir.join("one.md"), note1)?;
+        fs::write(vault_dir.join("two.md"), note2)?;
+        create_markdown_note(vault_dir, "three.md", "Three", &["rust"])?;
+
+        // A dry run returns the diffs and writes nothing.
+        let edits = rename_tag("project", "work", vault_dir, true)?;
+        let paths: Vec<_> = edits.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["one.md", "two.md"]);
+        assert_eq!(edits[0].changes, 3);
+        assert_eq!(
+            edits[0].diff,
+            "--- a/one.md\n+++ b/one.md\n\
+             @@ -3,1 +3,1 @@\n-tags: [project, \"project/api\", rust]\n+tags: [work, \"work/api\", rust]\n\
+             @@ -6,1 +6,1 @@\n-See #project/web and #other.\n+See #work/web and #other.\n"
+        );
+        // "work" is already in the list of the second note, so "project" is removed.
+        assert_eq!(
+            edits[1].diff,
+            "--- a/two.md\n+++ b/two.md\n@@ -4,1 +4,0 @@\n-  - project\n"
+        );
+        assert_eq!(fs::read_to_string(vault_dir.join("one.md"))?, note1);
+
+        rename_tag("project", "work", vault_dir, false)?;
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("one.md"))?,
+            "---\ntitle: One\ntags: [work, \"work/api\", rust]\n---\n\nSee #work/web and #other.\n"
+        );
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("two.md"))?,
+            "---\ntitle: Two\ntags:\n  - work # kept\n---\n\nNothing inline.\n"
+        );
+
+        let edits = merge_tags(&["rust", "other"], "code", vault_dir, false)?;
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("one.md"))?,
+            "---\ntitle: One\ntags: [work, \"work/api\", code]\n---\n\nSee #work/web and #code.\n"
+        );
+        let tags = get_note_tags("three.md", vault_dir)?;
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].tag, "code");
+
+        // Renaming a tag under itself leaves the tags already there alone.
+        fs::write(
+            vault_dir.join("four.md"),
+            "---\ntags: [work, work/old]\n---\n#work and #work/old/notes\n",
+        )?;
+        rename_tag("work", "work/old", vault_dir, false)?;
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("four.md"))?,
+            "---\ntags: [work/old]\n---\n#work/old and #work/old/notes\n"
+        );
+
+        assert!(rename_tag("work", "not valid", vault_dir, true).is_err());
+        assert!(rename_tag("missing", "other", vault_dir, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_tag() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        fs::write(
+            vault_dir.join("flow.md"),
+            "---\ntags: [draft, 'idea', draft/v2]\n---\n#draft Start, middle #draft, end #draft\n",
+        )?;
+        fs::write(vault_dir.join("scalar.md"), "---\ntags: draft\n---\nText\n")?;
+        create_markdown_note(vault_dir, "block.md", "Block", &["idea", "draft"])?;
+
+        let edits = delete_tag("draft", vault_dir, false)?;
+        assert_eq!(edits.len(), 3);
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("flow.md"))?,
+            "---\ntags: ['idea', draft/v2]\n---\nStart, middle, end\n"
+        );
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("scalar.md"))?,
+            "---\ntags:\n---\nText\n"
+        );
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("block.md"))?,
+            "---\ntitle: Block\ntags:\n  - idea\n---\n\nContent of the note."
+        );
+        assert!(get_notes_by_tag("draft", vault_dir, false)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tag_stats() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        fs::write(
+            vault_dir.join("a.md"),
+            "---\ncreated_on: 2024-03-01\ntags: [rust, cli]\n---\nMore #rust and #draft.\n",
+        )?;
+        fs::write(
+            vault_dir.join("b.md"),
+            "---\ncreated_on: 2024-01-15 09:00:00\ntags: [rust]\n---\n",
+        )?;
+        fs::write(vault_dir.join("c.md"), "No frontmatter, #cli only.\n")?;
+
+        let stats = get_tag_stats(vault_dir)?;
+        let rust = stats.get("rust").unwrap();
+        assert_eq!((rust.notes, rust.occurrences), (2, 3));
+        assert_eq!(rust.first_used.as_deref(), Some("2024-01-15"));
+        assert_eq!(rust.last_used.as_deref(), Some("2024-03-01"));
+
+        let cli = stats.get("cli").unwrap();
+        assert_eq!(cli.notes, 2);
+        assert_eq!(cli.first_used, cli.last_used);
+        assert!(stats.get("missing").is_none());
+
+        assert_eq!(stats.cooccurrence["rust"]["cli"], 1);
+        assert_eq!(stats.cooccurrence["cli"]["draft"], 1);
+        assert!(!stats.cooccurrence["rust"].contains_key("rust"));
+        assert_eq!(stats.orphans, vec!["draft"]);
+
+        let json = serde_json::to_value(&stats)?;
+        assert_eq!(json["usage"][0]["tag"], "cli");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_policy() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        assert!(TagPolicy::load(vault_dir)?.is_identity());
+
+        create_markdown_note(vault_dir, "note1.md", "Note One", &["CLI", "rust"])?;
+        create_markdown_note(vault_dir, "note2.md", "Note Two", &["cli"])?;
+        fs::write(
+            vault_dir.join("note3.md"),
+            "Inline #Command_Line/zsh tag.\n",
+        )?;
+        fs::write(
+            vault_dir.join(TAG_POLICY_FILE),
+            "fold_case: true\nnormalize_separators: true\naliases:\n  cli: [command-line, terminal]\n  cli/shell: [terminal/zsh]\n",
+        )?;
+
+        let policy = TagPolicy::load(vault_dir)?;
+        assert_eq!(policy.canonical("Terminal"), "cli");
+        // The longest alias wins.
+        assert_eq!(
+            policy.canonical("terminal/zsh/plugins"),
+            "cli/shell/plugins"
+        );
+        assert_eq!(policy.canonical("command   line"), "cli");
+        assert_eq!(policy.canonical("terminals"), "terminals");
+
+        assert_eq!(get_all_tags(vault_dir)?, vec!["cli", "cli/zsh", "rust"]);
+        let mut notes = get_notes_by_tag("Command-Line", vault_dir, true)?;
+        notes.sort();
+        let paths: Vec<_> = notes.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec!["note1.md", "note2.md", "note3.md"]);
+        assert_eq!(get_tag_stats(vault_dir)?.get("cli").unwrap().notes, 2);
+
+        // Renames and deletions compare tags through the policy too.
+        let edits = rename_tag("cli", "shell", vault_dir, false)?;
+        let paths: Vec<_> = edits.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["note1.md", "note2.md", "note3.md"]);
+        assert_eq!(
+            fs::read_to_string(vault_dir.join("note3.md"))?,
+            "Inline #shell/zsh tag.\n"
+        );
+        assert_eq!(get_all_tags(vault_dir)?, vec!["rust", "shell", "shell/zsh"]);
+        assert_eq!(delete_tag("SHELL", vault_dir, false)?.len(), 2);
+        assert_eq!(get_all_tags(vault_dir)?, vec!["rust", "shell/zsh"]);
+
+        fs::write(
+            vault_dir.join(TAG_POLICY_FILE),
+            "aliases: [not, a, mapping]\n",
+        )?;
+        assert!(get_all_tags(vault_dir).is_err());
         Ok(())
     }
 }
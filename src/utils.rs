@@ -1,11 +1,16 @@
 // src/utils.rs
 
-use crate::notes::utils::{get_file_path, get_title}; // Make sure get_title is imported
+use crate::index::VaultIndex;
+use crate::links::{ResolvedLink, code_spans, extract_links, scan_vault_links, unfenced_lines};
+use crate::notes::frontmatter::Frontmatter;
+use crate::notes::utils::{fallback_title, get_file_path, relative_note_path, walk_markdown_files};
 use anyhow::{Context, Result, anyhow};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
 use serde_yaml;
 use std::fs;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
 /// Reads the config.yaml file and extracts the vault directory path for the specified vault name.
 ///
@@ -39,18 +44,18 @@ pub fn get_vault_directory(vault_name: &str, config_dir: &Path) -> Result<PathBu
     // Find the specified vault
     if let Some(vaults) = vaults.as_sequence() {
         for vault in vaults {
-            if let Some(name) = vault.get("name") {
-                if name.as_str() == Some(vault_name) {
-                    if let Some(path) = vault.get("vault_directory") {
-                        if let Some(path_str) = path.as_str() {
-                            return Ok(PathBuf::from(path_str));
-                        }
-                    }
-                    return Err(anyhow!(
-                        "Vault '{}' found but has no valid path specified",
-                        vault_name
-                    ));
+            if let Some(name) = vault.get("name")
+                && name.as_str() == Some(vault_name)
+            {
+                if let Some(path) = vault.get("vault_directory")
+                    && let Some(path_str) = path.as_str()
+                {
+                    return Ok(PathBuf::from(path_str));
                 }
+                return Err(anyhow!(
+                    "Vault '{}' found but has no valid path specified",
+                    vault_name
+                ));
             }
         }
     }
@@ -199,9 +204,13 @@ pub fn relative_to_absolute(relative_path: &str, vault_directory: &Path) -> Resu
     Ok(abs_path_str)
 }
 
-/// Finds all notes that link to a specific note by searching for its relative path.
+/// Finds all notes that link to a specific note.
 /// Returns a list of tuples containing the relative path and title of the linking notes.
 ///
+/// Every note in the vault is parsed for wikilinks and markdown links (ignoring code),
+/// and each link is resolved by path, title or alias. A note is a backlink if any of
+/// its links resolves to the target note.
+///
 /// # Arguments
 /// * `title` - The title of the note to find backlinks for
 /// * `vault_directory` - The base directory of the vault
@@ -211,88 +220,381 @@ pub fn relative_to_absolute(relative_path: &str, vault_directory: &Path) -> Resu
 ///
 /// # Errors
 /// * Returns an error if the note file can't be found
-/// * Returns an error if the ripgrep command fails
 /// * Returns an error if any path conversion fails
-/// * Returns an error if the title of a backlinking note cannot be determined
+/// * Returns an error if the vault can't be walked
 pub fn get_backlinks(title: &str, vault_directory: &Path) -> Result<Vec<(String, String)>> {
-    // Get the absolute path of the target note
-    let target_absolute_path_str = get_file_path(title, vault_directory)
+    // Get the relative path of the target note
+    let target_absolute_path = get_file_path(title, vault_directory)
         .with_context(|| format!("Failed to find note with title: {}", title))?;
-    let target_absolute_path = Path::new(&target_absolute_path_str);
-
-    // Convert the absolute path to a relative path for searching
-    let target_relative_path = absolute_to_relative(&target_absolute_path_str, vault_directory)?;
-
-    // Use ripgrep to search for all occurrences of the relative path in markdown files
-    let output = Command::new("rg")
-        .args(&[
-            "--files-with-matches", // Only show filenames that match
-            "--glob",
-            "*.md",                // Only search markdown files
-            &target_relative_path, // Search pattern (the relative path)
-            vault_directory
-                .to_str()
-                .ok_or_else(|| anyhow!("Invalid vault directory path"))?,
-        ])
-        .output()
-        .context("Failed to execute ripgrep command. Is 'rg' installed?")?;
-
-    // Check the exit status of ripgrep
-    if !output.status.success() {
-        // ripgrep exits with 1 if no matches are found.
-        // Treat exit code 1 as a successful run with empty results.
-        // Any other non-zero exit code is treated as a genuine error.
-        match output.status.code() {
-            Some(1) => {
-                // Exit code 1 means no matches were found, which is not an error for us.
-                // Return an empty vector.
-                return Ok(Vec::new());
-            }
-            _ => {
-                // Any other error (e.g., exit code 2, signal termination) is a real error.
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(anyhow!(
-                    "ripgrep command failed with status {}: {}",
-                    output.status,
-                    stderr.trim()
-                ));
-            }
-        }
-    }
+    let target_relative_path = absolute_to_relative(&target_absolute_path, vault_directory)?;
 
-    // If we reach here, ripgrep executed successfully (exit code 0) and found matches.
-    let stdout =
-        String::from_utf8(output.stdout).context("Failed to parse ripgrep command output")?;
+    let (resolver, notes) = scan_vault_links(vault_directory)?;
 
-    // Split by newlines to get all file paths, filtering out empty lines
-    let absolute_paths: Vec<&str> = stdout
-        .trim()
-        .split('\n')
-        .filter(|s| !s.is_empty())
+    let backlinks = notes
+        .into_iter()
+        // Skip the target file itself - we don't consider self-links as backlinks
+        .filter(|note| note.path != target_relative_path)
+        .filter(|note| {
+            note.links.iter().any(|link| {
+                resolver.resolve(&note.path, link) == Some(target_relative_path.as_str())
+            })
+        })
+        .map(|note| (note.path, note.title))
         .collect();
 
-    let mut backlinks = Vec::new();
+    Ok(backlinks)
+}
 
-    for abs_path_str in absolute_paths {
-        let abs_path = Path::new(abs_path_str);
+/// Lists the outgoing links of a note, each resolved to a note in the vault.
+///
+/// # Arguments
+/// * `title` - The title of the note whose links should be listed
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Vec<ResolvedLink>>` - The links in order of appearance, with the vault-relative
+///   path of the linked note (`None` for external links and links to missing notes)
+///
+/// # Errors
+/// * Returns an error if the note file can't be found
+/// * Returns an error if the vault can't be walked
+///
+/// # Examples
+/// ```
+/// use std::path::Path;
+/// use notemancy_core::utils::get_outgoing_links;
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let links = get_outgoing_links("My Note", vault_dir);
+/// ```
+pub fn get_outgoing_links(title: &str, vault_directory: &Path) -> Result<Vec<ResolvedLink>> {
+    let source_absolute_path = get_file_path(title, vault_directory)
+        .with_context(|| format!("Failed to find note with title: {}", title))?;
+    let source_relative_path = absolute_to_relative(&source_absolute_path, vault_directory)?;
+
+    let (resolver, notes) = scan_vault_links(vault_directory)?;
+    let source = notes
+        .into_iter()
+        .find(|note| note.path == source_relative_path)
+        .ok_or_else(|| anyhow!("Failed to read note: {}", source_relative_path))?;
+
+    Ok(source
+        .links
+        .into_iter()
+        .map(|link| {
+            let path = resolver.resolve(&source.path, &link).map(|p| p.to_string());
+            ResolvedLink { link, path }
+        })
+        .collect())
+}
 
-        // Skip the target file itself - we don't consider self-links as backlinks
-        if abs_path == target_absolute_path {
+/// Lists the outgoing links of a note using a `VaultIndex`.
+///
+/// # Arguments
+/// * `title` - The title of the note whose links should be listed
+/// * `index` - A refreshed index of the vault
+///
+/// # Returns
+/// * `Result<Vec<ResolvedLink>>` - The links in order of appearance with their resolved paths
+///
+/// # Errors
+/// * Returns an error if no note with the given title is in the index
+pub fn get_outgoing_links_indexed(title: &str, index: &VaultIndex) -> Result<Vec<ResolvedLink>> {
+    let source = index
+        .find_by_title(title)
+        .ok_or_else(|| anyhow!("Failed to find note with title: {}", title))?;
+    Ok(index.outgoing_links(&source.path))
+}
+
+/// Finds all notes that link to a specific note using a `VaultIndex`.
+/// Returns a list of tuples containing the relative path and title of the linking notes.
+///
+/// Unlike `get_backlinks`, no files are read: the outgoing links recorded in the index
+/// are resolved against the notes in the index.
+///
+/// # Arguments
+/// * `title` - The title of the note to find backlinks for
+/// * `index` - A refreshed index of the vault
+///
+/// # Returns
+/// * `Result<Vec<(String, String)>>` - A list of (relative path, title) pairs for notes linking to the specified note
+///
+/// # Errors
+/// * Returns an error if no note with the given title is in the index
+pub fn get_backlinks_indexed(title: &str, index: &VaultIndex) -> Result<Vec<(String, String)>> {
+    let target = index
+        .find_by_title(title)
+        .ok_or_else(|| anyhow!("Failed to find note with title: {}", title))?;
+
+    Ok(index
+        .backlinks(&target.path)
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry.title.clone()))
+        .collect())
+}
+
+/// A plain-text mention of a note's title or alias in another note, as returned by
+/// `get_unlinked_mentions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnlinkedMention {
+    /// Path of the mentioning note relative to the vault directory
+    pub path: String,
+    /// Title of the mentioning note
+    pub title: String,
+    /// 1-based line of the mention
+    pub line: usize,
+    /// 1-based column (in characters) of the mention in its line
+    pub column: usize,
+    /// Byte range of the mention in the content of the note
+    pub span: Range<usize>,
+    /// The mention as written
+    pub text: String,
+    /// The line of the mention, without its line break
+    pub context: String,
+}
+
+/// Finds the places where other notes mention a note by its title or one of its
+/// aliases without linking to it.
+///
+/// Mentions are matched case-insensitively as whole words in the body of the notes.
+/// Text inside links (to any note), code blocks and inline code is skipped. A note that
+/// already links to the target elsewhere is still searched.
+///
+/// # Arguments
+/// * `title` - The title of the mentioned note
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<Vec<UnlinkedMention>>` - The mentions, ordered by note and position
+///
+/// # Errors
+/// * Returns an error if the note file can't be found or read
+/// * Returns an error if the vault can't be walked
+///
+/// # Examples
+/// ```no_run
+/// use std::path::Path;
+/// use notemancy_core::utils::{get_unlinked_mentions, link_mention};
+///
+/// let vault_dir = Path::new("/path/to/vault");
+/// let mentions = get_unlinked_mentions("Meeting Notes", vault_dir)?;
+/// for mention in &mentions {
+///     println!("{}:{}: {}", mention.path, mention.line, mention.context);
+/// }
+/// if let Some(first) = mentions.first() {
+///     link_mention(first, "Meeting Notes", vault_dir)?;
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn get_unlinked_mentions(title: &str, vault_directory: &Path) -> Result<Vec<UnlinkedMention>> {
+    let target_absolute_path = get_file_path(title, vault_directory)
+        .with_context(|| format!("Failed to find note with title: {}", title))?;
+    let target_relative_path = absolute_to_relative(&target_absolute_path, vault_directory)?;
+    let target_content = fs::read_to_string(&target_absolute_path)
+        .with_context(|| format!("Failed to read note file: {}", target_absolute_path))?;
+    let frontmatter = Frontmatter::from_content(&target_content)
+        .ok()
+        .flatten()
+        .map(|(frontmatter, _)| frontmatter)
+        .unwrap_or_default();
+
+    // Longer names first, so that an alias containing the title wins.
+    let mut names: Vec<&str> = std::iter::once(frontmatter.title.as_deref().unwrap_or(title))
+        .chain(frontmatter.aliases.iter().map(String::as_str))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .collect();
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+    names.dedup();
+    let pattern = names
+        .iter()
+        .map(|name| regex::escape(name))
+        .collect::<Vec<_>>()
+        .join("|");
+    let pattern = RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .context("Failed to build the pattern of the note names")?;
+
+    let mut mentions = Vec::new();
+    for path in walk_markdown_files(vault_directory)? {
+        let relative_path = relative_note_path(&path, vault_directory);
+        if relative_path == target_relative_path {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let found = find_mentions(&content, &pattern);
+        if found.is_empty() {
             continue;
         }
+        let note_title = Frontmatter::from_content(&content)
+            .ok()
+            .flatten()
+            .and_then(|(frontmatter, _)| frontmatter.title)
+            .unwrap_or_else(|| fallback_title(&relative_path));
+        mentions.extend(found.into_iter().map(|mut mention| {
+            mention.path = relative_path.clone();
+            mention.title = note_title.clone();
+            mention
+        }));
+    }
+    Ok(mentions)
+}
 
-        // Convert absolute path to relative path
-        let rel_path = absolute_to_relative(abs_path_str, vault_directory)
-            .with_context(|| format!("Failed to convert path to relative: {}", abs_path_str))?;
+/// Helper function to find the unlinked mentions matching a pattern in the body of a
+/// note. The path and title of the returned mentions are left empty.
+fn find_mentions(content: &str, pattern: &Regex) -> Vec<UnlinkedMention> {
+    let body_start = Frontmatter::split(content).map_or(0, |(_, body)| {
+        body.as_ptr() as usize - content.as_ptr() as usize
+    });
+    let links: Vec<Range<usize>> = extract_links(content)
+        .into_iter()
+        .map(|link| link.span)
+        .collect();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+
+    let mut mentions = Vec::new();
+    for (line_number, line_start, line) in unfenced_lines(content, body_start) {
+        let code = code_spans(line);
+        for found in pattern.find_iter(line) {
+            let span = line_start + found.start()..line_start + found.end();
+            if is_word(line[..found.start()].chars().next_back())
+                || is_word(line[found.end()..].chars().next())
+                || code.iter().any(|c| c.contains(&found.start()))
+                || links
+                    .iter()
+                    .any(|link| link.start < span.end && span.start < link.end)
+            {
+                continue;
+            }
+            mentions.push(UnlinkedMention {
+                path: String::new(),
+                title: String::new(),
+                line: line_number,
+                column: line[..found.start()].chars().count() + 1,
+                span,
+                text: found.as_str().to_string(),
+                context: line.trim_end_matches(['\n', '\r']).to_string(),
+            });
+        }
+    }
+    mentions
+}
 
-        // Get the title of the backlinking note
-        let backlink_title = get_title(abs_path)
-            .with_context(|| format!("Failed to get title for backlink file: {}", abs_path_str))?;
+/// Turns an unlinked mention into a wikilink to the mentioned note, in place.
+///
+/// The mention becomes `[[Title]]` if it is written like the title, and
+/// `[[Title|mention]]` otherwise, so the text of the note reads the same. The rest of
+/// the note is left untouched.
+///
+/// # Arguments
+/// * `mention` - A mention returned by `get_unlinked_mentions`
+/// * `title` - The title of the mentioned note
+/// * `vault_directory` - The base directory of the vault
+///
+/// # Returns
+/// * `Result<String>` - The link that replaced the mention
+///
+/// # Errors
+/// * Returns an error if the note can't be read or written
+/// * Returns an error if the note changed since the mention was found
+pub fn link_mention(
+    mention: &UnlinkedMention,
+    title: &str,
+    vault_directory: &Path,
+) -> Result<String> {
+    let file_path = vault_directory.join(&mention.path);
+    let mut content = fs::read_to_string(&file_path)
+        .with_context(|| format!("Failed to read note file: {}", file_path.display()))?;
+    if content.get(mention.span.clone()) != Some(mention.text.as_str()) {
+        return Err(anyhow!(
+            "The mention of '{}' in {} has changed since it was found",
+            mention.text,
+            mention.path
+        ));
+    }
+
+    let link = if mention.text == title {
+        format!("[[{}]]", title)
+    } else {
+        format!("[[{}|{}]]", title, mention.text)
+    };
+    content.replace_range(mention.span.clone(), &link);
+    fs::write(&file_path, content)
+        .with_context(|| format!("Failed to write note file: {}", file_path.display()))?;
+    Ok(link)
+}
 
-        backlinks.push((rel_path, backlink_title));
+/// Computes the Levenshtein edit distance between two strings, counted in characters.
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
     }
+    previous[b.len()]
+}
 
-    Ok(backlinks)
+/// Finds the candidate closest to a (probably misspelled) word, for "did you mean" hints.
+///
+/// Candidates are compared case-insensitively. A candidate is only suggested if it is
+/// within an edit distance of a third of the word's length (at least 1); on ties the
+/// first candidate wins.
+///
+/// # Arguments
+/// * `word` - The word that didn't match anything
+/// * `candidates` - The valid words
+///
+/// # Returns
+/// * `Option<&str>` - The closest candidate, or None if none is close enough
+pub fn closest_match<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    closest_matches(word, candidates, 1).into_iter().next()
+}
+
+/// Finds the candidates closest to a (probably misspelled) word, closest first.
+///
+/// Candidates are compared as in `closest_match`; candidates at the same distance keep
+/// their order.
+///
+/// # Arguments
+/// * `word` - The word that didn't match anything
+/// * `candidates` - The valid words
+/// * `limit` - The maximum number of candidates to return
+///
+/// # Returns
+/// * `Vec<&str>` - The candidates close enough to the word
+pub fn closest_matches<'a, I>(word: &str, candidates: I, limit: usize) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let word = word.to_lowercase();
+    let max_distance = (word.chars().count() / 3).max(1);
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                levenshtein_distance(&word, &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    matches.sort_by_key(|(distance, _)| *distance);
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, candidate)| candidate)
+        .collect()
 }
 
 #[cfg(test)]
@@ -436,4 +738,180 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_get_backlinks() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        // Create a target note and two notes in a subdirectory, one of which links to it
+        fs::create_dir_all(vault_dir.join("project"))?;
+        fs::write(
+            vault_dir.join("Target-Note.md"),
+            "---\ntitle: Target Note\n---\n\nThe target.",
+        )?;
+        fs::write(
+            vault_dir.join("project/Linking-Note.md"),
+            "---\ntitle: Linking Note\n---\n\nSee [target](Target-Note.md).",
+        )?;
+        fs::write(
+            vault_dir.join("project/Other-Note.md"),
+            "---\ntitle: Other Note\n---\n\nNo links here.",
+        )?;
+
+        let backlinks = get_backlinks("Target Note", vault_dir)?;
+        assert_eq!(
+            backlinks,
+            vec![(
+                "project/Linking-Note.md".to_string(),
+                "Linking Note".to_string()
+            )]
+        );
+
+        // A note without backlinks returns an empty list
+        let backlinks = get_backlinks("Other Note", vault_dir)?;
+        assert!(backlinks.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_backlinks_indexed() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        fs::create_dir_all(vault_dir.join("project"))?;
+        fs::write(
+            vault_dir.join("Target-Note.md"),
+            "---\ntitle: Target Note\n---\n\nThe target.",
+        )?;
+        fs::write(
+            vault_dir.join("project/Linking-Note.md"),
+            "---\ntitle: Linking Note\n---\n\nSee [target](Target-Note.md).",
+        )?;
+        fs::write(
+            vault_dir.join("project/Wiki-Note.md"),
+            "---\ntitle: Wiki Note\n---\n\nSee [[Target Note]].",
+        )?;
+
+        let index = VaultIndex::open(vault_dir)?;
+        let backlinks = get_backlinks_indexed("Target Note", &index)?;
+        assert_eq!(
+            backlinks,
+            vec![
+                (
+                    "project/Linking-Note.md".to_string(),
+                    "Linking Note".to_string()
+                ),
+                ("project/Wiki-Note.md".to_string(), "Wiki Note".to_string()),
+            ]
+        );
+
+        assert!(get_backlinks_indexed("Missing Note", &index).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_backlinks_and_outgoing_links() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+
+        fs::create_dir_all(vault_dir.join("project"))?;
+        fs::write(
+            vault_dir.join("project/Target Note.md"),
+            "---\ntitle: Target Note\naliases:\n  - Bullseye\n---\n\nThe target.",
+        )?;
+        fs::write(
+            vault_dir.join("Alias.md"),
+            "---\ntitle: Alias\n---\n\nSee [[Bullseye|the target]].",
+        )?;
+        fs::write(
+            vault_dir.join("Encoded.md"),
+            "---\ntitle: Encoded\n---\n\nSee [target](project/Target%20Note.md#intro).",
+        )?;
+        fs::write(
+            vault_dir.join("Prose.md"),
+            "---\ntitle: Prose\n---\n\nThe file project/Target Note.md is mentioned, \
+             and `[[Target Note]]` is code.",
+        )?;
+
+        let backlinks = get_backlinks("Target Note", vault_dir)?;
+        assert_eq!(
+            backlinks,
+            vec![
+                ("Alias.md".to_string(), "Alias".to_string()),
+                ("Encoded.md".to_string(), "Encoded".to_string()),
+            ]
+        );
+
+        let outgoing = get_outgoing_links("Encoded", vault_dir)?;
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].path.as_deref(), Some("project/Target Note.md"));
+
+        let index = VaultIndex::open(vault_dir)?;
+        assert_eq!(get_outgoing_links_indexed("Encoded", &index)?, outgoing);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_closest_match() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("über", "uber"), 1);
+
+        let fields = ["status", "title", "tags"];
+        assert_eq!(closest_match("staus", fields), Some("status"));
+        assert_eq!(closest_match("TAGS", fields), Some("tags"));
+        assert_eq!(closest_match("priority", fields), None);
+    }
+
+    #[test]
+    fn test_unlinked_mentions() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let vault_dir = temp_dir.path();
+        fs::write(
+            vault_dir.join("Meeting-Notes.md"),
+            "---\ntitle: Meeting Notes\naliases: [standup]\n---\n\nMeeting notes mention themselves.",
+        )?;
+        fs::write(
+            vault_dir.join("Journal.md"),
+            "---\ntitle: Journal\n---\n\nRead the meeting notes, then [[Meeting Notes]].\n\
+             Daily Standup: done. Meeting Notesy is not it.\n\
+             `meeting notes` in code\n```\nMeeting Notes\n```\n",
+        )?;
+
+        let mentions = get_unlinked_mentions("Meeting Notes", vault_dir)?;
+        let found: Vec<_> = mentions
+            .iter()
+            .map(|m| (m.path.as_str(), m.line, m.column, m.text.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Journal.md", 5, 10, "meeting notes"),
+                ("Journal.md", 6, 7, "Standup"),
+            ]
+        );
+        assert_eq!(mentions[0].title, "Journal");
+        assert_eq!(
+            mentions[1].context,
+            "Daily Standup: done. Meeting Notesy is not it."
+        );
+
+        assert_eq!(
+            link_mention(&mentions[1], "Meeting Notes", vault_dir)?,
+            "[[Meeting Notes|Standup]]"
+        );
+        // Linking a later mention leaves the spans of earlier ones valid.
+        assert!(link_mention(&mentions[0], "Meeting Notes", vault_dir).is_ok());
+        let content = fs::read_to_string(vault_dir.join("Journal.md"))?;
+        assert!(content.contains("Read the [[Meeting Notes|meeting notes]], then"));
+        assert!(content.contains("Daily [[Meeting Notes|Standup]]: done."));
+        assert!(get_unlinked_mentions("Meeting Notes", vault_dir)?.is_empty());
+        // A mention that was already linked no longer matches the note.
+        assert!(link_mention(&mentions[1], "Meeting Notes", vault_dir).is_err());
+        Ok(())
+    }
 }
@@ -56,12 +56,12 @@ pub fn list_workspaces(vault_directory: &Path) -> Result<Vec<String>> {
         let path = entry.path();
 
         // Check if it's a txt file
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "txt") {
-            if let Some(file_stem) = path.file_stem() {
-                if let Some(workspace_name) = file_stem.to_str() {
-                    workspaces.push(workspace_name.to_string());
-                }
-            }
+        if path.is_file()
+            && path.extension().is_some_and(|ext| ext == "txt")
+            && let Some(file_stem) = path.file_stem()
+            && let Some(workspace_name) = file_stem.to_str()
+        {
+            workspaces.push(workspace_name.to_string());
         }
     }
 